@@ -1,22 +1,73 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
+
+use crate::provider::pgn_database::DEFAULT_BUFFER_POSITIONS;
 
 /// CLI for building a repertoire PGN by composing quality and popularity providers.
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
     /// Path to config TOML
-    #[arg(long, default_value = "src/config/default_config.toml")]
+    #[arg(long, global = true, default_value = "src/config/default_config.toml")]
     pub config: Option<String>,
     /// Side for which to optimize (white|black)
-    #[arg(long)]
-    pub side: String,
-    /// Ply budget
-    #[arg(long)]
-    pub plies: u32,
+    #[arg(long, required_unless_present = "resume", conflicts_with = "resume")]
+    pub side: Option<String>,
+    /// Ply budget; when resuming, defaults to the checkpoint's
+    #[arg(long, required_unless_present = "resume")]
+    pub plies: Option<u32>,
     /// Starting moves in SAN (e.g., "1.e4 e5 2.Nf3 Nc6")
-    #[arg(long)]
+    #[arg(long, conflicts_with = "resume")]
     pub start: Option<String>,
-    /// Output PGN path
+    /// Save the build in progress here, to continue it later with --resume
+    #[arg(long)]
+    pub checkpoint: Option<String>,
+    /// Continue the build saved in this checkpoint (which keeps being updated
+    /// unless --checkpoint points elsewhere)
+    #[arg(long)]
+    pub resume: Option<String>,
+    /// Output path; a ".bin" extension writes a Polyglot book instead of PGN
     #[arg(long, default_value = "repertoire.pgn")]
     pub out: String,
 }
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Build an on-disk opening index from PGN files (for popularity source = "index")
+    Index(IndexArgs),
+    /// Import a Lichess cloud-eval dump (JSON lines) for quality source = "evaldb"
+    ImportEvals(ImportEvalsArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct IndexArgs {
+    /// PGN files to index
+    #[arg(required = true)]
+    pub pgn: Vec<String>,
+    /// Output index path
+    #[arg(long, default_value = "openings.idx")]
+    pub out: String,
+    /// Index only the first N plies of each game
+    #[arg(long, default_value_t = 30)]
+    pub max_plies: usize,
+    /// Drop positions reached by fewer games than this
+    #[arg(long, default_value_t = 1)]
+    pub min_games: u64,
+    /// Positions to keep in memory before spilling a sorted run next to the output
+    #[arg(long, default_value_t = DEFAULT_BUFFER_POSITIONS)]
+    pub buffer_positions: usize,
+}
+
+#[derive(Args, Debug)]
+pub struct ImportEvalsArgs {
+    /// Decompressed lichess_db_eval.jsonl, or "-" for stdin
+    pub dump: String,
+    /// Output database path
+    #[arg(long, default_value = "evals.db")]
+    pub out: String,
+    /// Skip positions with fewer pieces than this (keeps an opening-only database small)
+    #[arg(long)]
+    pub min_pieces: Option<usize>,
+}
@@ -3,6 +3,7 @@ pub mod cache_config;
 pub mod http_config;
 pub mod policy_config;
 pub mod popularity_config;
+pub mod popularity_source_config;
 pub mod quality_config;
 pub mod rate_config;
 pub mod search_config;
@@ -13,6 +14,7 @@ pub use cache_config::CacheConfig;
 pub use http_config::HttpConfig;
 pub use policy_config::PolicyConfig;
 pub use popularity_config::PopularityConfig;
+pub use popularity_source_config::PopularitySourceConfig;
 pub use quality_config::QualityConfig;
 pub use rate_config::RateConfig;
 pub use search_config::SearchConfig;
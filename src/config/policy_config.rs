@@ -1,6 +1,6 @@
-use super::toml_utils::{load_config_type_from_file, ConfigTypes};
-use crate::domain::{Centipawns, PlayRate};
-use anyhow::{anyhow, Result};
+use super::toml_utils::{ConfigTypes, load_config_type_from_file};
+use crate::domain::{Centipawns, PlayRate, WinModel};
+use anyhow::{Result, anyhow};
 use derive_builder::Builder;
 use serde::Deserialize;
 use shakmaty::Color;
@@ -11,6 +11,31 @@ pub struct PolicyConfig {
     pub my_side: Option<String>,
     pub cp_window: Centipawns,
     pub min_play_rate: PlayRate,
+    /// Window for my-side moves as the most expected score (0.0 to 1.0) a move may
+    /// give up against the best one, e.g. 0.03 for three percentage points. Used
+    /// instead of `cp_window` when set.
+    #[builder(default = "None")]
+    pub max_score_loss: Option<f32>,
+    /// Curve turning evaluations into expected scores for `max_score_loss`;
+    /// the Lichess model when unset.
+    #[builder(default = "None")]
+    pub win_model: Option<WinModel>,
+    /// Expand my-side nodes with both engine evals and explorer statistics, picking
+    /// the most practical of the moves within the window.
+    #[serde(default)]
+    #[builder(default = "false")]
+    pub hybrid_my_side: bool,
+    /// "side_split" (the default) or "coverage": opponent replies are expanded until
+    /// `coverage` of their play-rate mass is covered, and lines end once the chance
+    /// of reaching them drops below `min_reach`.
+    #[builder(default = "None")]
+    pub mode: Option<String>,
+    /// Share of the opponent's play-rate mass to cover in "coverage" mode; defaults to 0.9.
+    #[builder(default = "None")]
+    pub coverage: Option<f32>,
+    /// Smallest reach probability still expanded in "coverage" mode; defaults to 0.01.
+    #[builder(default = "None")]
+    pub min_reach: Option<f32>,
 }
 
 impl PolicyConfig {
@@ -29,6 +54,12 @@ impl PolicyConfig {
     /// assert_eq!(cfg.my_side, Some("white".to_string()));
     /// assert_eq!(cfg.cp_window, Centipawns::from_int(50));
     /// assert_eq!(cfg.min_play_rate, PlayRate::new(0.07));
+    /// assert_eq!(cfg.max_score_loss, None);
+    /// assert_eq!(cfg.win_model, None);
+    /// assert!(!cfg.hybrid_my_side);
+    /// assert_eq!(cfg.mode, None);
+    /// assert_eq!(cfg.coverage(), 0.9);
+    /// assert_eq!(cfg.min_reach(), 0.01);
     /// ```
     pub fn load(filename: &str) -> Result<Self> {
         load_config_type_from_file(filename, "policy").and_then(|cfg| match cfg {
@@ -43,7 +74,7 @@ impl PolicyConfig {
     /// # Examples
     /// ```
     /// use repgrow::config::PolicyConfig;
-    /// use repgrow::domain::{Centipawns, PlayRate};
+    /// use repgrow::domain::{Centipawns, PlayRate, WinModel};
     ///
     /// let cfg = PolicyConfig::builder()
     ///     .my_side(Some("black".to_string()))
@@ -54,11 +85,45 @@ impl PolicyConfig {
     /// assert_eq!(cfg.my_side, Some("black".to_string()));
     /// assert_eq!(cfg.cp_window, Centipawns::from_int(100));
     /// assert_eq!(cfg.min_play_rate, PlayRate::new(0.02));
+    /// assert_eq!(cfg.max_score_loss, None);
+    ///
+    /// let cfg = PolicyConfig::builder()
+    ///     .my_side(None)
+    ///     .cp_window(Centipawns::from_int(50))
+    ///     .min_play_rate(PlayRate::new(0.05))
+    ///     .max_score_loss(Some(0.03))
+    ///     .win_model(Some(WinModel::new(0.004)))
+    ///     .hybrid_my_side(true)
+    ///     .mode(Some("coverage".to_string()))
+    ///     .coverage(Some(0.8))
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(cfg.max_score_loss, Some(0.03));
+    /// assert_eq!(cfg.win_model(), WinModel::new(0.004));
+    /// assert!(cfg.hybrid_my_side);
+    /// assert_eq!(cfg.mode.as_deref(), Some("coverage"));
+    /// assert_eq!(cfg.coverage(), 0.8);
+    /// assert_eq!(cfg.min_reach(), 0.01);
     /// ```
     pub fn builder() -> PolicyConfigBuilder {
         PolicyConfigBuilder::default()
     }
 
+    /// The configured win model, or the Lichess curve.
+    pub fn win_model(&self) -> WinModel {
+        self.win_model.unwrap_or_default()
+    }
+
+    /// Opponent play-rate mass to cover in "coverage" mode.
+    pub fn coverage(&self) -> f32 {
+        self.coverage.unwrap_or(0.9)
+    }
+
+    /// Reach probability below which "coverage" mode stops a line.
+    pub fn min_reach(&self) -> f32 {
+        self.min_reach.unwrap_or(0.01)
+    }
+
     pub fn resolve_side_override(&self, cli_side: &str) -> Result<Color> {
         let s = if !cli_side.is_empty() {
             Some(cli_side.to_string())
@@ -1,10 +1,12 @@
 use derive_builder::Builder;
 use serde::Deserialize;
 
+use crate::config::PopularitySourceConfig;
+
 #[derive(Debug, Clone, Deserialize, Builder)]
 pub struct PopularityConfig {
     #[builder(default = "\"explorer\".to_string()")]
-    pub source: String, // "explorer" for now
+    pub source: String, // "explorer", "pgn", "index", "polyglot" or "merge"
     #[builder(default = "\"https://explorer.lichess.ovh\".to_string()")]
     pub base_url: String,
     #[builder(default = "\"all\".to_string()")]
@@ -15,6 +17,29 @@ pub struct PopularityConfig {
     pub max_rating: u32,
     #[builder(default = "2019")]
     pub since_year: u32,
+    #[builder(default = "\"standard\".to_string()")]
+    pub variant: String,
+    /// PGN files to index, used by `source = "pgn"`.
+    #[serde(default)]
+    #[builder(default = "Vec::new()")]
+    pub pgn_paths: Vec<String>,
+    /// Only the first this many plies of each PGN game are indexed; whole games when unset.
+    #[builder(default = "None")]
+    pub pgn_max_plies: Option<usize>,
+    /// Opening index file built by `repgrow index`, used by `source = "index"`.
+    #[builder(default = "None")]
+    pub index_path: Option<String>,
+    /// Polyglot `.bin` book, used by `source = "polyglot"`.
+    #[builder(default = "None")]
+    pub book_path: Option<String>,
+    /// Sources blended by `source = "merge"`, each overriding parts of this section.
+    #[serde(default)]
+    #[builder(default = "Vec::new()")]
+    pub merge: Vec<PopularitySourceConfig>,
+    /// How `source = "merge"` blends sources: "weight" (the configured weights, the
+    /// default) or "games" (pooled game counts, so bigger sources count for more).
+    #[builder(default = "None")]
+    pub merge_by: Option<String>,
 }
 
 impl PopularityConfig {
@@ -34,6 +59,9 @@ impl PopularityConfig {
     /// assert_eq!(cfg.min_rating, 800);
     /// assert_eq!(cfg.max_rating, 2000);
     /// assert_eq!(cfg.since_year, 2019);
+    /// assert_eq!(cfg.variant, "standard".to_string());
+    /// assert!(cfg.pgn_paths.is_empty());
+    /// assert!(cfg.merge.is_empty());
     /// ```
     pub fn load(filename: &str) -> anyhow::Result<Self> {
         crate::config::toml_utils::load_config_type_from_file(filename, "popularity").and_then(
@@ -58,6 +86,7 @@ impl PopularityConfig {
     ///     .min_rating(1000)
     ///     .max_rating(2200)
     ///     .since_year(2020)
+    ///     .variant("chess960".to_string())
     ///     .build()
     ///     .unwrap();
     /// assert_eq!(built_cfg.source, "explorer".to_string());
@@ -66,6 +95,19 @@ impl PopularityConfig {
     /// assert_eq!(built_cfg.min_rating, 1000);
     /// assert_eq!(built_cfg.max_rating, 2200);
     /// assert_eq!(built_cfg.since_year, 2020);
+    /// assert_eq!(built_cfg.variant, "chess960".to_string());
+    ///
+    /// let pgn_cfg = PopularityConfig::builder()
+    ///     .source("pgn".to_string())
+    ///     .pgn_paths(vec!["club.pgn".to_string()])
+    ///     .pgn_max_plies(Some(30))
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(pgn_cfg.pgn_paths, vec!["club.pgn".to_string()]);
+    /// assert_eq!(pgn_cfg.pgn_max_plies, Some(30));
+    /// assert_eq!(pgn_cfg.index_path, None);
+    /// assert_eq!(pgn_cfg.book_path, None);
+    /// assert_eq!(pgn_cfg.merge_by, None);
     /// ```
     pub fn builder() -> PopularityConfigBuilder {
         PopularityConfigBuilder::default()
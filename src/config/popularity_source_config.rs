@@ -0,0 +1,106 @@
+use derive_builder::Builder;
+use serde::Deserialize;
+
+use crate::config::PopularityConfig;
+
+/// One `[[popularity.merge]]` entry: a popularity source and the settings it overrides
+/// from the `[popularity]` section, e.g. a rating band for the explorer or the path of
+/// a club index.
+#[derive(Debug, Clone, Deserialize, Builder)]
+pub struct PopularitySourceConfig {
+    /// "explorer", "pgn", "index" or "polyglot".
+    pub source: String,
+    /// Label for per-source game counts; defaults to `source`.
+    #[builder(default = "None")]
+    pub name: Option<String>,
+    /// Share of the blend when merging by weight; weights are relative, so 70 and 30
+    /// mean the same as 0.7 and 0.3. Defaults to 1.
+    #[builder(default = "None")]
+    pub weight: Option<f32>,
+    #[builder(default = "None")]
+    pub base_url: Option<String>,
+    #[builder(default = "None")]
+    pub speed: Option<String>,
+    #[builder(default = "None")]
+    pub min_rating: Option<u32>,
+    #[builder(default = "None")]
+    pub max_rating: Option<u32>,
+    #[builder(default = "None")]
+    pub since_year: Option<u32>,
+    #[builder(default = "None")]
+    pub pgn_paths: Option<Vec<String>>,
+    #[builder(default = "None")]
+    pub index_path: Option<String>,
+    #[builder(default = "None")]
+    pub book_path: Option<String>,
+}
+
+impl PopularitySourceConfig {
+    /// Create a builder for PopularitySourceConfig.
+    /// # Examples
+    /// ```
+    /// use repgrow::config::{PopularityConfig, PopularitySourceConfig};
+    ///
+    /// let club = PopularitySourceConfig::builder()
+    ///     .source("index".to_string())
+    ///     .weight(Some(0.3))
+    ///     .index_path(Some("games/club.idx".to_string()))
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(club.weight, Some(0.3));
+    /// assert_eq!(club.min_rating, None);
+    /// assert_eq!(club.name(), "index");
+    ///
+    /// let base = PopularityConfig::builder().min_rating(1600).build().unwrap();
+    /// let cfg = club.apply(&base);
+    /// assert_eq!(cfg.source, "index".to_string());
+    /// assert_eq!(cfg.index_path, Some("games/club.idx".to_string()));
+    /// assert_eq!(cfg.min_rating, 1600);
+    /// assert!(cfg.merge.is_empty());
+    /// ```
+    pub fn builder() -> PopularitySourceConfigBuilder {
+        PopularitySourceConfigBuilder::default()
+    }
+
+    /// Label used for this source's game counts.
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.source)
+    }
+
+    /// Relative weight of this source in a weighted merge.
+    pub fn weight(&self) -> f32 {
+        self.weight.unwrap_or(1.0)
+    }
+
+    /// The `[popularity]` section with this source's overrides applied.
+    pub fn apply(&self, base: &PopularityConfig) -> PopularityConfig {
+        let mut cfg = base.clone();
+        cfg.source = self.source.clone();
+        cfg.merge = Vec::new();
+        if let Some(v) = &self.base_url {
+            cfg.base_url = v.clone();
+        }
+        if let Some(v) = &self.speed {
+            cfg.speed = v.clone();
+        }
+        if let Some(v) = self.min_rating {
+            cfg.min_rating = v;
+        }
+        if let Some(v) = self.max_rating {
+            cfg.max_rating = v;
+        }
+        if let Some(v) = self.since_year {
+            cfg.since_year = v;
+        }
+        if let Some(v) = &self.pgn_paths {
+            cfg.pgn_paths = v.clone();
+        }
+        if self.index_path.is_some() {
+            cfg.index_path = self.index_path.clone();
+        }
+        if self.book_path.is_some() {
+            cfg.book_path = self.book_path.clone();
+        }
+        cfg
+    }
+}
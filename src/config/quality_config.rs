@@ -9,6 +9,38 @@ pub struct QualityConfig {
     pub multi_pv: usize,
     #[builder(default = "\"https://lichess.org/api/cloud-eval\".to_string()")]
     pub base_url: String,
+    /// Path to a UCI engine binary, used by `source = "uci"`.
+    #[builder(default = "None")]
+    pub engine_path: Option<String>,
+    /// Fixed search depth for the local engine.
+    #[builder(default = "None")]
+    pub engine_depth: Option<u32>,
+    /// Fixed search time per position for the local engine, in milliseconds.
+    #[builder(default = "None")]
+    pub engine_movetime_ms: Option<u64>,
+    /// Engine hash table size in MB; engine default when unset.
+    #[builder(default = "None")]
+    pub engine_hash_mb: Option<usize>,
+    /// Engine search threads; engine default when unset.
+    #[builder(default = "None")]
+    pub engine_threads: Option<usize>,
+    /// Number of engine processes to run side by side; one when unset.
+    #[builder(default = "None")]
+    pub engine_pool_size: Option<usize>,
+    /// Wall-clock budget per position in milliseconds; the search is stopped and the
+    /// deepest lines so far are returned when it runs out.
+    #[builder(default = "None")]
+    pub engine_timeout_ms: Option<u64>,
+    /// Eval database imported with `repgrow import-evals`, used by `source = "evaldb"`.
+    #[builder(default = "None")]
+    pub evaldb_path: Option<String>,
+    /// Sources tried in order by `source = "chain"`, e.g. `["evaldb", "cloud", "uci"]`.
+    #[serde(default)]
+    #[builder(default = "Vec::new()")]
+    pub chain: Vec<String>,
+    /// In a chain, answers shallower than this fall through to the next source.
+    #[builder(default = "None")]
+    pub min_depth: Option<u8>,
 }
 
 impl QualityConfig {
@@ -25,6 +57,8 @@ impl QualityConfig {
     /// assert_eq!(cfg.source, "cloud".to_string());
     /// assert_eq!(cfg.multi_pv, 4);
     /// assert_eq!(cfg.base_url, "https://lichess.org/api/cloud-eval".to_string());
+    /// assert_eq!(cfg.engine_path, None);
+    /// assert!(cfg.chain.is_empty());
     /// ```
     pub fn load(filename: &str) -> anyhow::Result<Self> {
         crate::config::toml_utils::load_config_type_from_file(filename, "quality").and_then(|cfg| {
@@ -51,6 +85,32 @@ impl QualityConfig {
     /// assert_eq!(cfg.source, "andy".to_string());
     /// assert_eq!(cfg.multi_pv, 25);
     /// assert_eq!(cfg.base_url, "https://lichess.org/api/cloud-eval-andy".to_string());
+    ///
+    /// let uci = QualityConfig::builder()
+    ///     .source("uci".to_string())
+    ///     .engine_path(Some("/usr/bin/stockfish".to_string()))
+    ///     .engine_depth(Some(22))
+    ///     .engine_hash_mb(Some(256))
+    ///     .engine_pool_size(Some(4))
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(uci.engine_path, Some("/usr/bin/stockfish".to_string()));
+    /// assert_eq!(uci.engine_depth, Some(22));
+    /// assert_eq!(uci.engine_movetime_ms, None);
+    /// assert_eq!(uci.engine_hash_mb, Some(256));
+    /// assert_eq!(uci.engine_threads, None);
+    /// assert_eq!(uci.engine_pool_size, Some(4));
+    /// assert_eq!(uci.engine_timeout_ms, None);
+    /// assert_eq!(uci.evaldb_path, None);
+    ///
+    /// let chain = QualityConfig::builder()
+    ///     .source("chain".to_string())
+    ///     .chain(vec!["evaldb".to_string(), "cloud".to_string()])
+    ///     .min_depth(Some(24))
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(chain.chain, vec!["evaldb".to_string(), "cloud".to_string()]);
+    /// assert_eq!(chain.min_depth, Some(24));
     /// ```
     pub fn builder() -> QualityConfigBuilder {
         QualityConfigBuilder::default()
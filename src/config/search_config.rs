@@ -10,6 +10,20 @@ pub struct SearchConfig {
     pub max_children_my_side: Option<usize>,
     /// Maximum number of children to explore on the opponent's side.
     pub max_children_opp_side: Option<usize>,
+    /// Order nodes are expanded in: "fifo" (breadth-first, the default) or
+    /// "priority" (most important first, by the policy's priority such as reach).
+    #[serde(default)]
+    pub queue: Option<String>,
+    /// Stop the build after this many seconds.
+    #[serde(default)]
+    pub max_seconds: Option<u64>,
+    /// Stop the build after this many provider calls (engine or explorer lookups).
+    #[serde(default)]
+    pub max_api_calls: Option<usize>,
+    /// Seconds between checkpoints when the build writes one (`--checkpoint`);
+    /// 60 if unset.
+    #[serde(default)]
+    pub checkpoint_seconds: Option<u64>,
 }
 
 impl Default for SearchConfig {
@@ -19,6 +33,10 @@ impl Default for SearchConfig {
             max_total_nodes: Some(1000000),
             max_children_my_side: Some(10000),
             max_children_opp_side: Some(10000),
+            queue: None,
+            max_seconds: None,
+            max_api_calls: None,
+            checkpoint_seconds: None,
         }
     }
 }
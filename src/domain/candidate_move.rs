@@ -12,7 +12,7 @@ pub struct CandidateMove {
 
 #[cfg(test)]
 mod tests {
-    use crate::domain::{Centipawns, PieceColor, PlayRate};
+    use crate::domain::{Eval, PieceColor, PlayRate};
 
     use super::*;
 
@@ -20,26 +20,31 @@ mod tests {
     fn test_candidate_move_creation() {
         let move_ = CandidateMove {
             uci: UciMove::from_uci("e2e4").unwrap(),
-            next_fen: FenKey {
-                fen_string: "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
-                    .to_string(),
-                side_to_move: PieceColor::Black,
-            },
+            next_fen: FenKey::parse("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1")
+                .unwrap(),
             signals: Signals {
-                eval_cp: Some(Centipawns::from_float(0.85)),
+                eval: Some(Eval::Cp(85)),
                 depth: None,
                 play_rate: Some(PlayRate::new(0.75)),
                 games: None,
+                wdl: None,
+                expected_score: None,
+                avg_rating: None,
+                source: None,
+                games_by_source: Vec::new(),
+                surviving_candidates: None,
+                reach: None,
+                pending: false,
             },
         };
 
         assert_eq!(move_.uci, UciMove::from_uci("e2e4").unwrap());
         assert_eq!(
-            move_.next_fen.fen_string,
+            move_.next_fen.fen_string(),
             "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
         );
-        assert_eq!(move_.next_fen.side_to_move, PieceColor::Black);
+        assert_eq!(move_.next_fen.side_to_move(), PieceColor::Black);
         assert_eq!(move_.signals.play_rate, Some(PlayRate::new(0.75)));
-        assert_eq!(move_.signals.eval_cp, Some(Centipawns::from_float(0.85)));
+        assert_eq!(move_.signals.eval, Some(Eval::Cp(85)));
     }
 }
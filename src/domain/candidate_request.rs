@@ -14,6 +14,10 @@ pub struct CandidateRequest {
     pub min_play_rate: PlayRate,
     #[builder(default = "1")]
     pub multipv: usize,
+    /// Probability of reaching this position given the opponent's play rates along
+    /// the path; 1.0 at the root.
+    #[builder(default = "1.0")]
+    pub reach: f32,
 }
 
 impl CandidateRequest {
@@ -24,6 +28,8 @@ impl CandidateRequest {
     /// * `cp_window` - Centipawn window for move quality filtering.
     /// * `min_play_rate` - Minimum play rate for move popularity filtering.
     /// * `multipv` - Number of principal variations to request from the engine.
+    ///
+    /// `reach` starts at 1.0; set it for positions deeper in the tree.
     /// # Returns
     /// * `CandidateRequest` - The constructed CandidateRequest.
     /// # Examples
@@ -32,12 +38,13 @@ impl CandidateRequest {
     /// use repgrow::domain::FenKey;
     /// let fen_key = FenKey::starting_position();
     /// let req = CandidateRequest::new(fen_key, 10, Centipawns::from_int(50), PlayRate::new(0.05), 3);
-    /// assert_eq!(req.fen_key.fen_string, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string());
-    /// assert_eq!(req.fen_key.side_to_move.to_string(), "white".to_string());
+    /// assert_eq!(req.fen_key.fen_string(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string());
+    /// assert_eq!(req.fen_key.side_to_move().to_string(), "white".to_string());
     /// assert_eq!(req.max_candidates, 10);
     /// assert_eq!(req.cp_window, Centipawns::from_int(50));
     /// assert_eq!(req.min_play_rate, PlayRate::new(0.05));
     /// assert_eq!(req.multipv, 3);
+    /// assert_eq!(req.reach, 1.0);
     /// ```
     pub fn new(
         fen_key: FenKey,
@@ -52,6 +59,7 @@ impl CandidateRequest {
             cp_window,
             min_play_rate,
             multipv,
+            reach: 1.0,
         }
     }
 }
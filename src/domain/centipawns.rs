@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+/// A centipawn amount, such as a policy window. Engine scores use `Eval`, which also
+/// represents forced mates.
 #[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize, PartialOrd)]
 pub struct Centipawns(f32);
 
@@ -52,14 +52,14 @@ impl UciMove {
     ///     to: ChessSquare::new(ChessFile::A, ChessRank::Eight),
     ///     promotion: Some(ChessPieceType::Knight),
     /// }));
+    /// assert_eq!(UciMove::from_uci("z9e4"), Err(UciMoveParseError));
     /// ```
     pub fn from_uci(s: &str) -> Result<Self, UciMoveParseError> {
         if s.len() < 4 || s.len() > 5 {
             return Err(UciMoveParseError);
         }
-        let from_square =
-            ChessSquare::extract_uci_from_square(s).expect("Failed to extract from square");
-        let to_square = ChessSquare::extract_uci_to_square(s).expect("Failed to extract to square");
+        let from_square = ChessSquare::extract_uci_from_square(s).map_err(|_| UciMoveParseError)?;
+        let to_square = ChessSquare::extract_uci_to_square(s).map_err(|_| UciMoveParseError)?;
         let promotion_piece = ChessPieceType::from_uci_string(s);
 
         // Validate promotion rules
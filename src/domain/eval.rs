@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, ops::Neg};
+
+use crate::domain::{PieceColor, WinModel};
+
+/// Centipawn magnitude a mate stands for in `cp_equivalent`.
+const MATE_CP: i32 = 100_000;
+
+/// Engine evaluation of a position: a centipawn score or a forced mate.
+///
+/// Providers report scores from White's perspective (positive is good for White).
+/// `Mate(n)` with `n > 0` means White mates in `n` moves, `n < 0` means Black does;
+/// `Mate(0)` means the reported side has been mated. Ordering is "better for the
+/// reported side": any winning mate beats any centipawn score, shorter wins beat
+/// longer ones, and longer losses beat shorter ones. Use `for_side` to compare from
+/// the side to move's perspective.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Eval {
+    Cp(i32),
+    Mate(i32),
+}
+
+impl Eval {
+    /// The same evaluation from `side`'s perspective, given one from White's.
+    /// # Examples
+    /// ```
+    /// use repgrow::domain::{Eval, PieceColor};
+    /// assert_eq!(Eval::Cp(35).for_side(PieceColor::Black), Eval::Cp(-35));
+    /// assert_eq!(Eval::Mate(-2).for_side(PieceColor::Black), Eval::Mate(2));
+    /// assert_eq!(Eval::Cp(35).for_side(PieceColor::White), Eval::Cp(35));
+    /// ```
+    pub fn for_side(self, side: PieceColor) -> Self {
+        if side.is_white() { self } else { -self }
+    }
+
+    pub fn is_mate(&self) -> bool {
+        matches!(self, Eval::Mate(_))
+    }
+
+    /// Centipawns for arithmetic such as windows and losses; mates map beyond any
+    /// realistic score, shorter mates further out.
+    /// # Examples
+    /// ```
+    /// use repgrow::domain::Eval;
+    /// assert_eq!(Eval::Cp(-40).cp_equivalent(), -40);
+    /// assert_eq!(Eval::Mate(3).cp_equivalent(), 99_997);
+    /// assert_eq!(Eval::Mate(-2).cp_equivalent(), -99_998);
+    /// ```
+    pub fn cp_equivalent(&self) -> i32 {
+        match *self {
+            Eval::Cp(cp) => cp.clamp(-(MATE_CP - 1_000), MATE_CP - 1_000),
+            Eval::Mate(n) if n > 0 => MATE_CP - n.min(999),
+            Eval::Mate(n) => -MATE_CP + n.unsigned_abs().min(999) as i32,
+        }
+    }
+
+    /// Chance of winning for the reported side, between 0.0 and 1.0, using the
+    /// logistic model Lichess uses to turn centipawns into winning chances. See
+    /// `WinModel` for other curves.
+    /// # Examples
+    /// ```
+    /// use repgrow::domain::Eval;
+    /// assert_eq!(Eval::Cp(0).win_probability(), 0.5);
+    /// assert!(Eval::Cp(100).win_probability() > 0.59);
+    /// assert_eq!(Eval::Mate(7).win_probability(), 1.0);
+    /// assert_eq!(Eval::Mate(-1).win_probability(), 0.0);
+    /// ```
+    pub fn win_probability(&self) -> f32 {
+        WinModel::lichess().expected_score(*self)
+    }
+
+    /// Rank used for ordering: winning mates, then centipawns, then losing mates.
+    fn rank(&self) -> (i8, i64) {
+        match *self {
+            Eval::Mate(n) if n > 0 => (1, -i64::from(n)),
+            Eval::Cp(cp) => (0, i64::from(cp)),
+            // Being mated later is better; mated already (0) is worst.
+            Eval::Mate(0) => (-1, i64::MIN),
+            Eval::Mate(n) => (-1, i64::from(n.unsigned_abs())),
+        }
+    }
+}
+
+impl Neg for Eval {
+    type Output = Eval;
+
+    fn neg(self) -> Eval {
+        match self {
+            Eval::Cp(cp) => Eval::Cp(-cp),
+            Eval::Mate(n) => Eval::Mate(-n),
+        }
+    }
+}
+
+impl Ord for Eval {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+impl PartialOrd for Eval {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_total_order() {
+        let mut evals = vec![
+            Eval::Cp(30),
+            Eval::Mate(-1),
+            Eval::Mate(5),
+            Eval::Cp(3000),
+            Eval::Mate(-8),
+            Eval::Mate(1),
+            Eval::Cp(-3000),
+            Eval::Mate(0),
+        ];
+        evals.sort();
+        assert_eq!(
+            evals,
+            vec![
+                Eval::Mate(0),
+                Eval::Mate(-1),
+                Eval::Mate(-8),
+                Eval::Cp(-3000),
+                Eval::Cp(30),
+                Eval::Cp(3000),
+                Eval::Mate(5),
+                Eval::Mate(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_order_from_black_perspective() {
+        // Black to move: White mating in 3 is the worst option, Black mating the best.
+        let mut evals = [Eval::Mate(3), Eval::Cp(-50), Eval::Mate(-4), Eval::Cp(20)];
+        evals.sort_by_key(|e| std::cmp::Reverse(e.for_side(PieceColor::Black)));
+        assert_eq!(
+            evals,
+            [Eval::Mate(-4), Eval::Cp(-50), Eval::Cp(20), Eval::Mate(3)]
+        );
+    }
+
+    #[test]
+    fn test_win_probability_is_symmetric() {
+        let p = Eval::Cp(250).win_probability();
+        assert!((p + Eval::Cp(-250).win_probability() - 1.0).abs() < 1e-6);
+        assert!(p > 0.7 && p < 0.75);
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        assert_eq!(
+            serde_json::to_string(&Eval::Cp(18)).unwrap(),
+            r#"{"cp":18}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Eval::Mate(-3)).unwrap(),
+            r#"{"mate":-3}"#
+        );
+        let e: Eval = serde_json::from_str(r#"{"mate":4}"#).unwrap();
+        assert_eq!(e, Eval::Mate(4));
+    }
+}
@@ -1,12 +1,19 @@
 use serde::{Deserialize, Serialize};
 
-use crate::domain::{Centipawns, chess::UciMove};
+use crate::domain::{Eval, chess::UciMove};
 
 /// Convenience struct returned by specialized providers before normalization.
 /// EvalLine represents a single move evaluation from a chess engine.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EvalLine {
     pub uci: UciMove,
-    pub eval_cp: Centipawns,
+    /// Score after this move, from White's perspective.
+    pub eval: Eval,
     pub depth: u8,
+    /// Full principal variation starting with `uci`; empty when the provider only reports the first move.
+    #[serde(default)]
+    pub pv: Vec<UciMove>,
+    /// Provider that produced the line, e.g. `"evaldb"`; filled in by the fallback chain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
@@ -1,33 +1,88 @@
-use crate::domain::PieceColor;
+use crate::domain::{PieceColor, PositionKey};
+use anyhow::Context;
 use serde::{Deserialize, Serialize};
-use std::fmt::{Formatter, Result};
+use shakmaty::{CastlingMode, Chess, EnPassantMode, Position, fen::Fen};
+use std::{
+    fmt::{Formatter, Result},
+    hash::{Hash, Hasher},
+};
 
-/// FEN + side-to-move forms a canonical lookup key.
-#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// FEN of the standard starting position.
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// A legal position as a FEN, keyed by its canonical `PositionKey`.
+///
+/// Equality and hashing use the position key, so the same position reached on a
+/// different move number is the same key; `fen_string()` keeps the FEN as given, for
+/// display. The side to move and the key are derived from the parsed position and
+/// the fields are private, so they can't go stale.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "FenKeyFields")]
 pub struct FenKey {
-    pub fen_string: String,
-    pub side_to_move: PieceColor,
+    fen_string: String,
+    side_to_move: PieceColor,
+    #[serde(skip_serializing)]
+    key: PositionKey,
+}
+
+#[derive(Deserialize)]
+struct FenKeyFields {
+    fen_string: String,
+}
+
+impl TryFrom<FenKeyFields> for FenKey {
+    type Error = anyhow::Error;
+
+    fn try_from(fields: FenKeyFields) -> anyhow::Result<Self> {
+        Self::parse(&fields.fen_string)
+    }
+}
+
+impl PartialEq for FenKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for FenKey {}
+
+impl Hash for FenKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key.hash(state);
+    }
 }
 
 impl FenKey {
-    /// Creates a new FenKey from a FEN string and side to move.
-    /// # Arguments
-    /// * `fen_string` - The FEN string representing the board position.
-    /// * `side_to_move` - The side to move (PieceColor).
-    /// # Returns
-    /// * `FenKey` - A new FenKey instance.
+    /// Parses a FEN. `"startpos"` is accepted as the starting position and stored as
+    /// its full FEN.
+    /// # Errors
+    /// If the FEN is malformed or not a legal standard chess position.
     /// # Examples
     /// ```
-    /// use repgrow::domain::{FenKey, PieceColor};
-    /// let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string();
-    /// let key = FenKey::new(fen.clone(), PieceColor::White);
-    /// assert_eq!(key.fen_string, fen);
-    /// assert_eq!(key.side_to_move, PieceColor::White);
+    /// use repgrow::domain::{FenKey, PieceColor, fen_key::STARTING_FEN};
+    /// let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1";
+    /// let key = FenKey::parse(fen).unwrap();
+    /// assert_eq!(key.fen_string(), fen);
+    /// assert_eq!(key.side_to_move(), PieceColor::Black);
+    /// assert_eq!(FenKey::parse("startpos").unwrap().fen_string(), STARTING_FEN);
+    /// assert!(FenKey::parse("not a fen").is_err());
     /// ```
-    pub fn new(fen_string: String, side_to_move: PieceColor) -> Self {
+    pub fn parse(fen: &str) -> anyhow::Result<Self> {
+        let fen = if fen == "startpos" { STARTING_FEN } else { fen };
+        let pos = parse_position(fen).with_context(|| format!("invalid FEN '{fen}'"))?;
+        Ok(Self {
+            fen_string: fen.to_string(),
+            side_to_move: PieceColor::from_shakmaty(pos.turn()),
+            key: PositionKey::of(&pos),
+        })
+    }
+
+    /// Key for a position already on the board, without parsing a FEN.
+    pub fn from_position(pos: &Chess) -> Self {
         Self {
-            fen_string,
-            side_to_move,
+            fen_string: Fen::from_position(pos.clone(), EnPassantMode::Legal).to_string(),
+            side_to_move: PieceColor::from_shakmaty(pos.turn()),
+            key: PositionKey::of(pos),
         }
     }
 
@@ -38,25 +93,71 @@ impl FenKey {
     /// ```
     /// use repgrow::domain::{FenKey, PieceColor};
     /// let starting_key = FenKey::starting_position();
-    /// assert_eq!(starting_key.fen_string, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string());
-    /// assert_eq!(starting_key.side_to_move, PieceColor::White);
+    /// assert_eq!(starting_key.fen_string(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string());
+    /// assert_eq!(starting_key.side_to_move(), PieceColor::White);
     /// ```
     pub fn starting_position() -> Self {
-        Self {
-            fen_string: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(),
-            side_to_move: PieceColor::White,
-        }
+        Self::from_position(&Chess::default())
+    }
+
+    /// The FEN as given, with `"startpos"` spelled out.
+    pub fn fen_string(&self) -> &str {
+        &self.fen_string
+    }
+
+    pub fn side_to_move(&self) -> PieceColor {
+        self.side_to_move
+    }
+
+    /// The canonical key of the position.
+    /// # Examples
+    /// ```
+    /// use repgrow::domain::FenKey;
+    /// let a = FenKey::parse("8/8/8/4k3/8/8/8/4K3 w - - 0 1").unwrap();
+    /// let b = FenKey::parse("8/8/8/4k3/8/8/8/4K3 w - - 6 40").unwrap();
+    /// assert_eq!(a.key(), b.key());
+    /// assert_eq!(a, b);
+    /// assert_eq!(FenKey::parse("startpos").unwrap(), FenKey::starting_position());
+    /// ```
+    pub fn key(&self) -> PositionKey {
+        self.key
+    }
+
+    /// The FEN with the move counters reset to `0 1` and the en passant square only
+    /// when the capture is legal: one string per position, for lookups and cache keys.
+    /// # Examples
+    /// ```
+    /// use repgrow::domain::FenKey;
+    /// let key = FenKey::parse("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+    /// assert_eq!(key.normalized_fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+    /// ```
+    pub fn normalized_fen(&self) -> String {
+        let fen = Fen::from_position(self.position(), EnPassantMode::Legal).to_string();
+        let fields: Vec<&str> = fen.split(' ').take(4).collect();
+        format!("{} 0 1", fields.join(" "))
+    }
+
+    /// The position on the board.
+    pub fn position(&self) -> Chess {
+        parse_position(&self.fen_string).expect("a FenKey only holds FENs that parsed")
+    }
+
+    pub fn is_starting_position(&self) -> bool {
+        self.key == PositionKey::of(&Chess::default())
     }
 }
 
+fn parse_position(fen: &str) -> anyhow::Result<Chess> {
+    Ok(fen.parse::<Fen>()?.into_position(CastlingMode::Standard)?)
+}
+
 impl std::fmt::Display for FenKey {
     /// Formats the FenKey as "FEN side_to_move".
     /// # Examples
     /// ```
-    /// use repgrow::domain::{FenKey, PieceColor};
-    /// let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string();
-    /// let key = FenKey::new(fen.clone(), PieceColor::White);
-    /// assert_eq!(format!("{}", key), format!("{} {}", fen, "white"));
+    /// use repgrow::domain::FenKey;
+    /// let key = FenKey::starting_position();
+    /// assert_eq!(format!("{}", key), format!("{} {}", key.fen_string(), "white"));
     /// ```
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         write!(f, "{} {}", self.fen_string, self.side_to_move)
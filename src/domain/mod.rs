@@ -3,20 +3,28 @@ pub mod candidate_request;
 pub mod centipawns;
 pub mod chess;
 pub mod color;
+pub mod eval;
 pub mod eval_line;
 pub mod fen_key;
 pub mod play_rate;
 pub mod popularity_row;
+pub mod position_key;
 pub mod repertoire_node;
 pub mod signals;
+pub mod wdl;
+pub mod win_model;
 
 pub use candidate_move::CandidateMove;
 pub use candidate_request::CandidateRequest;
 pub use centipawns::Centipawns;
 pub use color::PieceColor;
+pub use eval::Eval;
 pub use eval_line::EvalLine;
 pub use fen_key::FenKey;
 pub use play_rate::PlayRate;
 pub use popularity_row::PopularityRow;
-pub use repertoire_node::RepertoireNode;
+pub use position_key::PositionKey;
+pub use repertoire_node::{ParentLink, RepertoireNode};
 pub use signals::Signals;
+pub use wdl::Wdl;
+pub use win_model::WinModel;
@@ -1,8 +1,14 @@
-use crate::domain::{PlayRate, chess::UciMove};
+use crate::domain::{PlayRate, Wdl, chess::UciMove};
 
 #[derive(Clone, Debug)]
 pub struct PopularityRow {
     pub uci: UciMove,
     pub play_rate: PlayRate,
     pub games: u32,
+    /// Results of the games with this move, when the source records them.
+    pub wdl: Option<Wdl>,
+    /// Average rating of the players in those games, when the source records it.
+    pub avg_rating: Option<u32>,
+    /// Games per source when the row was merged from several sources; empty otherwise.
+    pub games_by_source: Vec<(String, u32)>,
 }
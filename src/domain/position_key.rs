@@ -0,0 +1,30 @@
+use shakmaty::{
+    Chess, EnPassantMode,
+    zobrist::{Zobrist64, ZobristHash},
+};
+use std::fmt;
+
+/// Canonical identity of a position: a 64-bit Zobrist hash of the piece placement,
+/// side to move, castling rights and en passant square (only when the capture is
+/// legal). Move counters are left out, so transpositions share a key whatever move
+/// they happen on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PositionKey(pub u64);
+
+impl PositionKey {
+    /// # Examples
+    /// ```
+    /// use repgrow::domain::PositionKey;
+    /// use shakmaty::Chess;
+    /// assert_eq!(PositionKey::of(&Chess::default()).0, 0x463b96181691fc9c);
+    /// ```
+    pub fn of(pos: &Chess) -> Self {
+        Self(pos.zobrist_hash::<Zobrist64>(EnPassantMode::Legal).0)
+    }
+}
+
+impl fmt::Display for PositionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
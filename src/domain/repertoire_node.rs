@@ -1,10 +1,13 @@
 /// RepertoireNode represents a node in the repertoire tree.
 /// Each node corresponds to a position reached by a sequence of moves.
 /// The tree is stored in an arena, with nodes referencing children by their IDs.
+/// A position reached by several move orders is a single node with several parents,
+/// so the tree is really a DAG.
 use super::fen_key::FenKey;
-use crate::domain::{PieceColor, Signals, chess::UciMove};
+use crate::domain::{PlayRate, Signals, chess::UciMove};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RepertoireNode {
     pub id: u64,
     pub parent: Option<u64>,
@@ -13,6 +16,18 @@ pub struct RepertoireNode {
     pub ply_depth: u32,
     pub children: Vec<u64>,
     pub signals: Signals,
+    /// Parents other than `parent` that transpose into this node.
+    pub other_parents: Vec<ParentLink>,
+}
+
+/// An extra parent of a node reached by transposition: the move played from it, and
+/// that move's play rate there (the node's own `signals` describe the first parent's
+/// move).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ParentLink {
+    pub parent: u64,
+    pub uci: UciMove,
+    pub play_rate: Option<PlayRate>,
 }
 
 impl RepertoireNode {
@@ -31,12 +46,39 @@ impl RepertoireNode {
             ply_depth,
             children: Vec::new(),
             signals: Signals::default(),
+            other_parents: Vec::new(),
         }
     }
 
     pub fn is_root(&self) -> bool {
         self.parent.is_none()
     }
+
+    /// The move leading here from `parent`, if `parent` is one of this node's parents.
+    pub fn move_from(&self, parent: u64) -> Option<&UciMove> {
+        if self.parent == Some(parent) {
+            return self.last_move_uci.as_ref();
+        }
+        self.link_from(parent).map(|link| &link.uci)
+    }
+
+    /// Play rate of the move leading here from `parent`, if known.
+    pub fn play_rate_from(&self, parent: u64) -> Option<PlayRate> {
+        if self.parent == Some(parent) {
+            return self.signals.play_rate;
+        }
+        self.link_from(parent).and_then(|link| link.play_rate)
+    }
+
+    fn link_from(&self, parent: u64) -> Option<&ParentLink> {
+        self.other_parents.iter().find(|link| link.parent == parent)
+    }
+
+    /// Whether this node is reached from `parent` by transposition, i.e. the line
+    /// through `parent` continues under this node's first parent.
+    pub fn transposes_from(&self, parent: u64) -> bool {
+        self.parent != Some(parent) && self.link_from(parent).is_some()
+    }
 }
 
 impl Default for RepertoireNode {
@@ -44,14 +86,12 @@ impl Default for RepertoireNode {
         Self {
             id: 0,
             parent: None,
-            fen_key: FenKey {
-                fen_string: String::from("startpos"),
-                side_to_move: PieceColor::White,
-            },
+            fen_key: FenKey::starting_position(),
             last_move_uci: None,
             ply_depth: 0,
             children: Vec::new(),
             signals: Signals::default(),
+            other_parents: Vec::new(),
         }
     }
 }
@@ -59,14 +99,14 @@ impl Default for RepertoireNode {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::{Centipawns, PieceColor, Signals, fen_key::FenKey};
+    use crate::{
+        domain::{Eval, PieceColor, Signals, fen_key::FenKey},
+        test_support::black_to_move,
+    };
 
     #[test]
     fn test_new_node() {
-        let fen_key = FenKey {
-            fen_string: "8/8/8/8/8/8/8/8 w - - 0 1".to_string(),
-            side_to_move: PieceColor::Black,
-        };
+        let fen_key = black_to_move();
         let node = RepertoireNode::new(
             42,
             Some(1),
@@ -76,8 +116,8 @@ mod tests {
         );
         assert_eq!(node.id, 42);
         assert_eq!(node.parent, Some(1));
-        assert_eq!(node.fen_key.fen_string, fen_key.fen_string);
-        assert_eq!(node.fen_key.side_to_move, PieceColor::Black);
+        assert_eq!(node.fen_key.fen_string(), fen_key.fen_string());
+        assert_eq!(node.fen_key.side_to_move(), PieceColor::Black);
         assert_eq!(
             Some(node.last_move_uci.unwrap().to_uci()),
             Some("e2e4".to_string())
@@ -92,8 +132,8 @@ mod tests {
         let node = RepertoireNode::default();
         assert_eq!(node.id, 0);
         assert_eq!(node.parent, None);
-        assert_eq!(node.fen_key.fen_string, "startpos");
-        assert_eq!(node.fen_key.side_to_move, PieceColor::White);
+        assert!(node.fen_key.is_starting_position());
+        assert_eq!(node.fen_key.side_to_move(), PieceColor::White);
         assert_eq!(node.last_move_uci, None);
         assert_eq!(node.ply_depth, 0);
         assert!(node.children.is_empty());
@@ -114,6 +154,31 @@ mod tests {
         assert!(!node2.is_root());
     }
 
+    #[test]
+    fn test_move_from_each_parent() {
+        let mut node = RepertoireNode::new(
+            5,
+            Some(3),
+            FenKey::starting_position(),
+            Some(UciMove::from_uci("g1f3").unwrap()),
+            4,
+        );
+        node.signals.play_rate = Some(PlayRate::new(0.6));
+        node.other_parents.push(ParentLink {
+            parent: 4,
+            uci: UciMove::from_uci("e2e4").unwrap(),
+            play_rate: Some(PlayRate::new(0.1)),
+        });
+        assert_eq!(node.move_from(3).unwrap().to_uci(), "g1f3");
+        assert_eq!(node.move_from(4).unwrap().to_uci(), "e2e4");
+        assert!(node.move_from(2).is_none());
+        assert!(!node.transposes_from(3));
+        assert!(node.transposes_from(4));
+        assert_eq!(node.play_rate_from(3), Some(PlayRate::new(0.6)));
+        assert_eq!(node.play_rate_from(4), Some(PlayRate::new(0.1)));
+        assert_eq!(node.play_rate_from(2), None);
+    }
+
     #[test]
     fn test_children_mutation() {
         let mut node = RepertoireNode::default();
@@ -125,8 +190,8 @@ mod tests {
     #[test]
     fn test_signals_field() {
         let mut node = RepertoireNode::default();
-        node.signals.eval_cp = Some(Centipawns::from_float(1.5));
-        assert_eq!(node.signals.eval_cp, Some(Centipawns::from_float(1.5)));
+        node.signals.eval = Some(Eval::Cp(150));
+        assert_eq!(node.signals.eval, Some(Eval::Cp(150)));
     }
 
     #[test]
@@ -134,10 +199,7 @@ mod tests {
         let node = RepertoireNode::new(
             99,
             Some(88),
-            FenKey {
-                fen_string: "testfen".to_string(),
-                side_to_move: PieceColor::Black,
-            },
+            FenKey::starting_position(),
             Some(UciMove::from_uci("g1f3").unwrap()),
             12,
         );
@@ -1,12 +1,13 @@
-use crate::domain::{Centipawns, PlayRate};
+use crate::domain::{Eval, PlayRate, Wdl};
+use serde::{Deserialize, Serialize};
 
 /// Signals union carried by candidates; expandable without changing traits.
-#[derive(Clone, Debug, Default, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Signals {
-    /// Centipawn evaluation from engine analysis, positive for White, negative for Black. None if no evaluation available.
-    pub eval_cp: Option<Centipawns>,
+    /// Engine evaluation from White's perspective, centipawns or mate. None if no evaluation available.
+    pub eval: Option<Eval>,
 
-    /// Depth of the engine analysis that produced eval_cp. None if no analysis available.
+    /// Depth of the engine analysis that produced eval. None if no analysis available.
     pub depth: Option<u8>,
 
     /// Play rate of this move in the given position, as a fraction between 0.0 and 1.0. None if no data available.
@@ -14,6 +15,35 @@ pub struct Signals {
 
     /// Number of games played in this position. None if no data available.
     pub games: Option<u32>,
+
+    /// Results of the games with this move, from White's perspective. None if no data available.
+    pub wdl: Option<Wdl>,
+
+    /// Score the mover made with this move, derived from `wdl`. None if no data available.
+    pub expected_score: Option<f32>,
+
+    /// Average rating of the players in those games. None if no data available.
+    pub avg_rating: Option<u32>,
+
+    /// Provider that answered for this move, e.g. "evaldb" or "cloud". None if not recorded.
+    pub source: Option<String>,
+
+    /// Games per popularity source when several were merged; empty otherwise.
+    pub games_by_source: Vec<(String, u32)>,
+
+    /// Candidate moves from this node's position that survived the policy window,
+    /// before the `max_children_*` cap. None until the node has been expanded.
+    pub surviving_candidates: Option<u32>,
+
+    /// Probability of reaching this node: the product of the opponent's play rates
+    /// along the path, summed over transpositions and capped at 1.0; 1.0 at the root.
+    /// None if the node was never reached by a search.
+    pub reach: Option<f32>,
+
+    /// Set on leaves the build stopped before expanding (interrupted or out of budget),
+    /// so the writers can mark the line as unfinished.
+    #[serde(default)]
+    pub pending: bool,
 }
 
 #[cfg(test)]
@@ -23,36 +53,68 @@ mod tests {
     #[test]
     fn test_signals_default() {
         let s = Signals::default();
-        assert_eq!(s.eval_cp, None);
+        assert_eq!(s.eval, None);
         assert_eq!(s.depth, None);
         assert_eq!(s.play_rate, None);
         assert_eq!(s.games, None);
+        assert_eq!(s.wdl, None);
+        assert_eq!(s.expected_score, None);
+        assert_eq!(s.avg_rating, None);
+        assert_eq!(s.source, None);
+        assert!(s.games_by_source.is_empty());
+        assert_eq!(s.surviving_candidates, None);
+        assert_eq!(s.reach, None);
+        assert!(!s.pending);
     }
 
     #[test]
     fn test_signals_new_values() {
         let s = Signals {
-            eval_cp: Some(Centipawns::from_float(42.5)),
+            eval: Some(Eval::Cp(42)),
             depth: Some(12),
             play_rate: Some(PlayRate::new(0.8)),
             games: Some(100),
+            wdl: Some(Wdl::new(40, 30, 30)),
+            expected_score: Some(0.55),
+            avg_rating: Some(1750),
+            source: Some("cloud".to_string()),
+            games_by_source: vec![("lichess".to_string(), 90), ("club".to_string(), 10)],
+            surviving_candidates: Some(2),
+            reach: Some(0.25),
+            pending: true,
         };
-        assert_eq!(s.eval_cp, Some(Centipawns::from_float(42.5)));
+        assert_eq!(s.eval, Some(Eval::Cp(42)));
         assert_eq!(s.depth, Some(12));
         assert_eq!(s.play_rate, Some(PlayRate::new(0.8)));
         assert_eq!(s.games, Some(100));
+        assert_eq!(s.wdl.map(|w| w.games()), Some(100));
+        assert_eq!(s.expected_score, Some(0.55));
+        assert_eq!(s.avg_rating, Some(1750));
+        assert_eq!(s.source.as_deref(), Some("cloud"));
+        assert_eq!(s.games_by_source[1], ("club".to_string(), 10));
+        assert_eq!(s.surviving_candidates, Some(2));
+        assert_eq!(s.reach, Some(0.25));
+        assert!(s.pending);
     }
 
     #[test]
     fn test_signals_clone() {
         let s1 = Signals {
-            eval_cp: Some(Centipawns::from_float(-3.2)),
+            eval: Some(Eval::Mate(-3)),
             depth: Some(5),
             play_rate: None,
             games: Some(7),
+            wdl: None,
+            expected_score: None,
+            avg_rating: None,
+            source: None,
+            games_by_source: Vec::new(),
+            surviving_candidates: None,
+            reach: None,
+            pending: false,
         };
         let s2 = s1.clone();
-        assert_eq!(s1.eval_cp, s2.eval_cp);
+        assert_eq!(s1.eval, s2.eval);
         assert_eq!(s1.depth, s2.depth);
         assert_eq!(s1.play_rate, s2.play_rate);
         assert_eq!(s1.games, s2.games);
@@ -61,15 +123,23 @@ mod tests {
     #[test]
     fn test_signals_debug() {
         let s = Signals {
-            eval_cp: Some(Centipawns::from_float(1.0)),
+            eval: Some(Eval::Cp(100)),
             depth: Some(2),
             play_rate: Some(PlayRate::new(0.5)),
             games: Some(10),
+            wdl: None,
+            expected_score: None,
+            avg_rating: None,
+            source: None,
+            games_by_source: Vec::new(),
+            surviving_candidates: None,
+            reach: None,
+            pending: false,
         };
         let dbg = format!("{:?}", s);
         println!("Results from the debug macro:\n{}", dbg);
         assert!(dbg.contains("Signals"));
-        assert!(dbg.contains("eval_cp: Some(Centipawns(1.0))"));
+        assert!(dbg.contains("eval: Some(Cp(100))"));
         assert!(dbg.contains("depth: Some(2)"));
         assert!(dbg.contains("play_rate: Some(PlayRate(0.5))"));
         assert!(dbg.contains("games: Some(10)"));
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::PieceColor;
+
+/// Game results after a move, counted from White's perspective like the Lichess explorer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Wdl {
+    pub white: u64,
+    pub draws: u64,
+    pub black: u64,
+}
+
+impl Wdl {
+    /// # Examples
+    /// ```
+    /// use repgrow::domain::Wdl;
+    /// let wdl = Wdl::new(5, 3, 2);
+    /// assert_eq!(wdl.games(), 10);
+    /// ```
+    pub fn new(white: u64, draws: u64, black: u64) -> Self {
+        Self {
+            white,
+            draws,
+            black,
+        }
+    }
+
+    /// One game's result from a PGN `Result` tag; unfinished games (`*`) have none.
+    /// # Examples
+    /// ```
+    /// use repgrow::domain::Wdl;
+    /// assert_eq!(Wdl::from_pgn_result("1/2-1/2"), Some(Wdl::new(0, 1, 0)));
+    /// assert_eq!(Wdl::from_pgn_result("*"), None);
+    /// ```
+    pub fn from_pgn_result(result: &str) -> Option<Self> {
+        match result {
+            "1-0" => Some(Self::new(1, 0, 0)),
+            "1/2-1/2" => Some(Self::new(0, 1, 0)),
+            "0-1" => Some(Self::new(0, 0, 1)),
+            _ => None,
+        }
+    }
+
+    pub fn games(&self) -> u64 {
+        self.white + self.draws + self.black
+    }
+
+    pub fn add(&mut self, other: &Wdl) {
+        self.white += other.white;
+        self.draws += other.draws;
+        self.black += other.black;
+    }
+
+    /// Games won by `side`.
+    pub fn wins_for(&self, side: PieceColor) -> u64 {
+        if side.is_white() {
+            self.white
+        } else {
+            self.black
+        }
+    }
+
+    /// Games lost by `side`.
+    pub fn losses_for(&self, side: PieceColor) -> u64 {
+        if side.is_white() {
+            self.black
+        } else {
+            self.white
+        }
+    }
+
+    /// Score `side` made in these games, wins plus half the draws, as a fraction
+    /// between 0.0 and 1.0. `None` when there are no games.
+    /// # Examples
+    /// ```
+    /// use repgrow::domain::{PieceColor, Wdl};
+    /// let wdl = Wdl::new(5, 3, 2);
+    /// assert_eq!(wdl.expected_score(PieceColor::White), Some(0.65));
+    /// assert_eq!(wdl.expected_score(PieceColor::Black), Some(0.35));
+    /// assert_eq!(Wdl::default().expected_score(PieceColor::White), None);
+    /// ```
+    pub fn expected_score(&self, side: PieceColor) -> Option<f32> {
+        let games = self.games();
+        if games == 0 {
+            return None;
+        }
+        let points = self.wins_for(side) as f64 + self.draws as f64 / 2.0;
+        Some((points / games as f64) as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_and_perspective() {
+        let mut wdl = Wdl::new(1, 2, 3);
+        wdl.add(&Wdl::new(4, 0, 1));
+        assert_eq!(wdl, Wdl::new(5, 2, 4));
+        assert_eq!(wdl.wins_for(PieceColor::Black), 4);
+        assert_eq!(wdl.losses_for(PieceColor::Black), 5);
+        assert_eq!(wdl.expected_score(PieceColor::Black), Some(5.0 / 11.0));
+    }
+}
@@ -0,0 +1,104 @@
+use serde::Deserialize;
+
+use crate::domain::{Eval, PieceColor};
+
+/// Logistic slope of the Lichess win-probability model, per centipawn.
+pub const LICHESS_SLOPE: f64 = 0.003_682_08;
+
+/// Converts evaluations into expected scores with a logistic curve,
+/// `1 / (1 + e^(-slope * cp))`.
+///
+/// Centipawn differences mean less the further a position is from equal: +50 vs
+/// +100 changes the expected score far more than +600 vs +650. Comparing expected
+/// scores instead of raw centipawns keeps a policy window meaningful in both.
+/// Forced mates score 1.0 for the mating side and 0.0 for the mated side.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct WinModel {
+    /// Steepness of the curve per centipawn; larger values turn small advantages
+    /// into near-certain wins sooner.
+    #[serde(default = "default_slope")]
+    pub slope: f64,
+}
+
+fn default_slope() -> f64 {
+    LICHESS_SLOPE
+}
+
+impl Default for WinModel {
+    fn default() -> Self {
+        Self::lichess()
+    }
+}
+
+impl WinModel {
+    /// # Examples
+    /// ```
+    /// use repgrow::domain::{Eval, WinModel};
+    /// let steep = WinModel::new(0.01);
+    /// assert!(steep.expected_score(Eval::Cp(100)) > WinModel::lichess().expected_score(Eval::Cp(100)));
+    /// ```
+    pub fn new(slope: f64) -> Self {
+        Self { slope }
+    }
+
+    /// The curve Lichess uses to turn centipawns into winning chances.
+    pub fn lichess() -> Self {
+        Self::new(LICHESS_SLOPE)
+    }
+
+    /// Expected score for the side `eval` is reported for, between 0.0 and 1.0.
+    /// # Examples
+    /// ```
+    /// use repgrow::domain::{Eval, WinModel};
+    /// let model = WinModel::lichess();
+    /// assert_eq!(model.expected_score(Eval::Cp(0)), 0.5);
+    /// assert_eq!(model.expected_score(Eval::Mate(3)), 1.0);
+    /// assert_eq!(model.expected_score(Eval::Mate(-3)), 0.0);
+    /// ```
+    pub fn expected_score(&self, eval: Eval) -> f32 {
+        match eval {
+            Eval::Cp(cp) => (1.0 / (1.0 + (-self.slope * f64::from(cp)).exp())) as f32,
+            Eval::Mate(n) if n > 0 => 1.0,
+            Eval::Mate(_) => 0.0,
+        }
+    }
+
+    /// Expected score for `side`, given an evaluation from White's perspective.
+    pub fn expected_score_for(&self, eval: Eval, side: PieceColor) -> f32 {
+        self.expected_score(eval.for_side(side))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equal_gaps_matter_less_when_winning() {
+        let model = WinModel::lichess();
+        let near_equal = model.expected_score(Eval::Cp(100)) - model.expected_score(Eval::Cp(50));
+        let winning = model.expected_score(Eval::Cp(650)) - model.expected_score(Eval::Cp(600));
+        assert!(near_equal > 0.04);
+        assert!(winning < 0.02);
+    }
+
+    #[test]
+    fn test_expected_score_for_black() {
+        let model = WinModel::lichess();
+        let white = model.expected_score_for(Eval::Cp(120), PieceColor::White);
+        let black = model.expected_score_for(Eval::Cp(120), PieceColor::Black);
+        assert!((white + black - 1.0).abs() < 1e-6);
+        assert_eq!(
+            model.expected_score_for(Eval::Mate(2), PieceColor::Black),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_slope_from_toml() {
+        let model: WinModel = toml::from_str("slope = 0.005").unwrap();
+        assert_eq!(model, WinModel::new(0.005));
+        let model: WinModel = toml::from_str("").unwrap();
+        assert_eq!(model, WinModel::lichess());
+    }
+}
@@ -1,5 +1,8 @@
 use reqwest::Client;
 
+#[cfg(test)]
+pub mod stub_server;
+
 pub fn build_http(timeout_ms: u64) -> Client {
     reqwest::Client::builder()
         .timeout(std::time::Duration::from_millis(timeout_ms))
@@ -0,0 +1,338 @@
+//! Read-only key → blob file for large offline datasets.
+//!
+//! `KeyedFileWriter` streams blobs to disk in one pass. Their `(key, offset, len)`
+//! entries are staged on disk as well when keys arrive in order, and otherwise sorted
+//! in memory at the end; the sorted table is then written in front of the blobs.
+//! `KeyedFile` memory-maps the result and binary-searches the table.
+//!
+//! Layout, all integers little endian: magic `[u8; 4]`, version `u32`, a `u32` the
+//! caller is free to use (`meta`), entry count `u64`, then `(key u64, offset u64,
+//! len u32)` per entry sorted by key, then the blobs.
+
+use anyhow::{Context, Result, anyhow, bail};
+use memmap2::Mmap;
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+const VERSION: u32 = 2;
+const HEADER_LEN: usize = 20;
+const ENTRY_LEN: usize = 20;
+
+pub struct KeyedFileWriter {
+    path: PathBuf,
+    data_path: PathBuf,
+    data: BufWriter<File>,
+    data_len: u64,
+    table_path: PathBuf,
+    table: BufWriter<File>,
+    len: usize,
+    /// The last key appended, while keys have been strictly increasing.
+    last_key: Option<u64>,
+    sorted: bool,
+    magic: [u8; 4],
+    meta: u32,
+}
+
+impl KeyedFileWriter {
+    /// Start writing `path`; blobs and entries are staged in `<path>.data` and
+    /// `<path>.table` until `finish`.
+    pub fn create(path: &Path, magic: [u8; 4]) -> Result<Self> {
+        let staging = |suffix: &str| -> Result<(PathBuf, BufWriter<File>)> {
+            let mut name = path.as_os_str().to_owned();
+            name.push(suffix);
+            let name = PathBuf::from(name);
+            let file =
+                File::create(&name).with_context(|| format!("creating {}", name.display()))?;
+            Ok((name, BufWriter::new(file)))
+        };
+        let (data_path, data) = staging(".data")?;
+        let (table_path, table) = staging(".table")?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            data_path,
+            data,
+            data_len: 0,
+            table_path,
+            table,
+            len: 0,
+            last_key: None,
+            sorted: true,
+            magic,
+            meta: 0,
+        })
+    }
+
+    /// Store `meta` in the header, for `KeyedFile::meta` to return.
+    pub fn with_meta(mut self, meta: u32) -> Self {
+        self.meta = meta;
+        self
+    }
+
+    /// Number of blobs appended so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn append(&mut self, key: u64, blob: &[u8]) -> Result<()> {
+        // Keys appended in order keep the table on disk; a repeat of the last one
+        // can be dropped right away, since the first blob wins.
+        if self.sorted {
+            match self.last_key {
+                Some(last) if key == last => return Ok(()),
+                Some(last) if key < last => self.sorted = false,
+                _ => self.last_key = Some(key),
+            }
+        }
+        let len = u32::try_from(blob.len()).context("blob larger than 4 GiB")?;
+        self.data.write_all(blob)?;
+        write_entry(&mut self.table, (key, self.data_len, len))?;
+        self.data_len += u64::from(len);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Write the sorted table and the blobs to the final file. When a key was
+    /// appended more than once, the first blob wins. Returns the number of keys.
+    pub fn finish(mut self) -> Result<usize> {
+        self.data.flush()?;
+        self.table.flush()?;
+        drop((self.data, self.table));
+        let mut table = BufReader::new(File::open(&self.table_path)?);
+        // Out-of-order keys leave no choice but to sort the entries in memory.
+        let entries = if self.sorted {
+            None
+        } else {
+            let mut entries = Vec::with_capacity(self.len);
+            for _ in 0..self.len {
+                entries.push(read_entry(&mut table)?);
+            }
+            entries.sort_by_key(|&(key, _, _)| key);
+            entries.dedup_by_key(|&mut (key, _, _)| key);
+            Some(entries)
+        };
+        let len = entries.as_ref().map_or(self.len, Vec::len);
+
+        let file = File::create(&self.path)
+            .with_context(|| format!("creating {}", self.path.display()))?;
+        let mut out = BufWriter::new(file);
+        out.write_all(&self.magic)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        out.write_all(&self.meta.to_le_bytes())?;
+        out.write_all(&(len as u64).to_le_bytes())?;
+        match entries {
+            Some(entries) => {
+                for entry in entries {
+                    write_entry(&mut out, entry)?;
+                }
+            }
+            None => {
+                io::copy(&mut table, &mut out)?;
+            }
+        }
+        io::copy(&mut File::open(&self.data_path)?, &mut out)?;
+        out.flush()?;
+        fs::remove_file(&self.table_path)?;
+        fs::remove_file(&self.data_path)?;
+        Ok(len)
+    }
+}
+
+fn write_entry(out: &mut impl Write, (key, offset, len): (u64, u64, u32)) -> Result<()> {
+    out.write_all(&key.to_le_bytes())?;
+    out.write_all(&offset.to_le_bytes())?;
+    out.write_all(&len.to_le_bytes())?;
+    Ok(())
+}
+
+fn read_entry(input: &mut impl Read) -> Result<(u64, u64, u32)> {
+    let mut e = [0; ENTRY_LEN];
+    input.read_exact(&mut e)?;
+    Ok(parse_entry(&e))
+}
+
+fn parse_entry(e: &[u8]) -> (u64, u64, u32) {
+    (
+        u64::from_le_bytes(e[0..8].try_into().expect("8 bytes")),
+        u64::from_le_bytes(e[8..16].try_into().expect("8 bytes")),
+        u32::from_le_bytes(e[16..20].try_into().expect("4 bytes")),
+    )
+}
+
+/// A memory-mapped file written by `KeyedFileWriter`.
+pub struct KeyedFile {
+    mmap: Mmap,
+    len: usize,
+    meta: u32,
+}
+
+impl std::fmt::Debug for KeyedFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyedFile")
+            .field("len", &self.len)
+            .field("meta", &self.meta)
+            .finish()
+    }
+}
+
+impl KeyedFile {
+    /// Open `path`, checking that it was written with the same `magic`.
+    pub fn open(path: &Path, magic: [u8; 4]) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
+        // SAFETY: keyed files are written once and only read afterwards; replacing
+        // one while a run has it mapped is not supported.
+        let mmap = unsafe { Mmap::map(&file)? };
+        if mmap.len() < HEADER_LEN || mmap[..4] != magic {
+            bail!("{} is not the expected kind of file", path.display());
+        }
+        let version = u32::from_le_bytes(mmap[4..8].try_into()?);
+        if version != VERSION {
+            bail!("{} has unsupported version {version}", path.display());
+        }
+        let meta = u32::from_le_bytes(mmap[8..12].try_into()?);
+        let len = usize::try_from(u64::from_le_bytes(mmap[12..20].try_into()?))?;
+        // The count comes from the file, so a corrupt one must not overflow.
+        let table_end = len
+            .checked_mul(ENTRY_LEN)
+            .and_then(|table| table.checked_add(HEADER_LEN));
+        match table_end {
+            Some(end) if end <= mmap.len() => {}
+            _ => bail!("{} is truncated", path.display()),
+        }
+        Ok(Self { mmap, len, meta })
+    }
+
+    /// Number of keys in the file.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The value passed to `KeyedFileWriter::with_meta`.
+    pub fn meta(&self) -> u32 {
+        self.meta
+    }
+
+    /// `open` checked that the whole table is in the file.
+    fn entry(&self, i: usize) -> (u64, u64, u32) {
+        let at = HEADER_LEN + i * ENTRY_LEN;
+        parse_entry(&self.mmap[at..at + ENTRY_LEN])
+    }
+
+    /// The blob stored under `key`, if any.
+    pub fn get(&self, key: u64) -> Result<Option<&[u8]>> {
+        let (mut lo, mut hi) = (0, self.len);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let (k, offset, len) = self.entry(mid);
+            match k.cmp(&key) {
+                std::cmp::Ordering::Equal => {
+                    // Offset and length come from the file too.
+                    let data_start = HEADER_LEN + self.len * ENTRY_LEN;
+                    return usize::try_from(offset)
+                        .ok()
+                        .and_then(|offset| data_start.checked_add(offset))
+                        .and_then(|start| Some(start..start.checked_add(len as usize)?))
+                        .and_then(|range| self.mmap.get(range))
+                        .map(Some)
+                        .ok_or_else(|| anyhow!("keyed file is truncated"));
+                }
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAGIC: [u8; 4] = *b"TEST";
+
+    #[test]
+    fn test_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blobs.bin");
+        let mut writer = KeyedFileWriter::create(&path, MAGIC).unwrap();
+        writer.append(30, b"thirty").unwrap();
+        writer.append(10, b"ten").unwrap();
+        writer.append(20, b"").unwrap();
+        writer.append(10, b"ten again").unwrap();
+        assert_eq!(writer.finish().unwrap(), 3);
+        assert!(!dir.path().join("blobs.bin.data").exists());
+        assert!(!dir.path().join("blobs.bin.table").exists());
+
+        let file = KeyedFile::open(&path, MAGIC).unwrap();
+        assert_eq!(file.len(), 3);
+        assert_eq!(file.get(10).unwrap(), Some(&b"ten"[..]));
+        assert_eq!(file.get(20).unwrap(), Some(&b""[..]));
+        assert_eq!(file.get(30).unwrap(), Some(&b"thirty"[..]));
+        assert_eq!(file.get(40).unwrap(), None);
+    }
+
+    #[test]
+    fn test_keys_in_order_with_meta() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blobs.bin");
+        let mut writer = KeyedFileWriter::create(&path, MAGIC)
+            .unwrap()
+            .with_meta(2020);
+        writer.append(1, b"one").unwrap();
+        writer.append(2, b"two").unwrap();
+        writer.append(2, b"two again").unwrap();
+        writer.append(u64::MAX, b"max").unwrap();
+        assert_eq!(writer.finish().unwrap(), 3);
+
+        let file = KeyedFile::open(&path, MAGIC).unwrap();
+        assert_eq!(file.meta(), 2020);
+        assert_eq!(file.get(2).unwrap(), Some(&b"two"[..]));
+        assert_eq!(file.get(u64::MAX).unwrap(), Some(&b"max"[..]));
+        assert_eq!(file.get(0).unwrap(), None);
+    }
+
+    #[test]
+    fn test_corrupt_header_and_entries_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blobs.bin");
+        let header = |len: u64| {
+            let mut bytes = MAGIC.to_vec();
+            bytes.extend_from_slice(&VERSION.to_le_bytes());
+            bytes.extend_from_slice(&0u32.to_le_bytes());
+            bytes.extend_from_slice(&len.to_le_bytes());
+            bytes
+        };
+        std::fs::write(&path, header(u64::MAX)).unwrap();
+        let err = KeyedFile::open(&path, MAGIC).unwrap_err();
+        assert!(err.to_string().contains("truncated"), "{err}");
+
+        // One entry whose blob would end past the end of the address space.
+        let mut bytes = header(1);
+        write_entry(&mut bytes, (7, u64::MAX - 1, 8)).unwrap();
+        std::fs::write(&path, bytes).unwrap();
+        let file = KeyedFile::open(&path, MAGIC).unwrap();
+        assert!(file.get(7).is_err());
+    }
+
+    #[test]
+    fn test_wrong_magic_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blobs.bin");
+        KeyedFileWriter::create(&path, MAGIC)
+            .unwrap()
+            .finish()
+            .unwrap();
+        assert!(KeyedFile::open(&path, *b"NOPE").is_err());
+        assert!(KeyedFile::open(&path, MAGIC).unwrap().is_empty());
+    }
+}
@@ -5,6 +5,7 @@ use std::sync::Arc;
 
 pub mod cache;
 pub mod http;
+pub mod keyed_file;
 pub mod rate;
 pub mod scheduler;
 pub mod singleflight;
@@ -40,7 +40,11 @@ where
         };
 
         if !do_fetch {
-            return Ok(rx_opt.unwrap().await.expect("inflight canceled"));
+            // The sender is dropped without a value when the leader's fetch fails.
+            return rx_opt
+                .unwrap()
+                .await
+                .map_err(|_| anyhow::anyhow!("inflight request failed"));
         }
 
         let res = f(k.clone()).await;
@@ -55,8 +59,8 @@ where
                 }
             }
             Err(_) => {
-                // Do not send anything to waiters on error, or optionally send a default value if appropriate.
-                // for w in waiters { let _ = w.send(Arc::new(panic!("no value"))); }
+                // Dropping the senders wakes waiters with an error.
+                drop(waiters);
             }
         }
         res
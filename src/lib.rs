@@ -7,5 +7,7 @@ pub mod pgn;
 pub mod policy;
 pub mod provider;
 pub mod search;
+#[cfg(test)]
+mod test_support;
 
 pub use cli::Cli;
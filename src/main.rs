@@ -1,14 +1,20 @@
+use anyhow::Context;
 use clap_builder::Parser;
 use repgrow::pgn::RepertoireWriter;
 use repgrow::{
-    cli::Cli,
+    cli::{Cli, Command, ImportEvalsArgs, IndexArgs},
     config::AppConfig,
+    domain::PieceColor,
     infra::build_infra,
-    pgn::PgnWriter,
-    policy::SideSplitPolicy,
-    provider::{build_popularity, build_quality},
-    search::Orchestrator,
+    orchestration::Interrupt,
+    pgn::{PgnWriter, PolyglotWriter},
+    policy::build_policy,
+    provider::{
+        build_popularity, build_quality, eval_db::import_eval_dump, pgn_database::IndexBuilder,
+    },
+    search::{Checkpoint, Orchestrator},
 };
+use std::path::{Path, PathBuf};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -16,7 +22,26 @@ async fn main() -> anyhow::Result<()> {
         .with_max_level(tracing::Level::DEBUG)
         .init();
     let cli = Cli::parse();
-    let cfg = AppConfig::load(cli.config.expect("Cannot find config file").as_ref())?;
+    let cfg = AppConfig::load(cli.config.as_deref().expect("Cannot find config file"))?;
+
+    match &cli.command {
+        Some(Command::Index(args)) => return build_index(&cfg, args),
+        Some(Command::ImportEvals(args)) => return import_evals(args),
+        None => {}
+    }
+    let resume = cli
+        .resume
+        .as_deref()
+        .map(|path| Checkpoint::load(Path::new(path)))
+        .transpose()?;
+    let side = match &resume {
+        Some(checkpoint) => checkpoint.side.clone(),
+        None => cli.side.clone().context("--side is required")?,
+    };
+    let plies = cli
+        .plies
+        .or(resume.as_ref().map(|c| c.max_plies))
+        .context("--plies is required")?;
 
     // Build infra
     let infra = build_infra(&cfg)?;
@@ -25,20 +50,98 @@ async fn main() -> anyhow::Result<()> {
     let quality = build_quality(&cfg.quality, &infra)?;
     let popularity = build_popularity(&cfg.popularity, &infra)?;
 
-    // Build policy (default: my side → quality; opp → popularity)
-    let my_side = cfg.policy.resolve_side_override(cli.side.as_str())?;
-    let policy = SideSplitPolicy::new(my_side, cfg.policy.cp_window, cfg.policy.min_play_rate);
-
-    // Orchestrator
-    let orch = Orchestrator::new(cfg.search.clone(), policy, quality, popularity);
-    let root = orch
-        .build_from_start(cli.start.as_deref(), cli.plies)
-        .await?;
-
-    // Write PGN
-    let writer = PgnWriter;
-    let pgn = writer.write(&root)?;
-    std::fs::write(&cli.out, pgn)?;
-    eprintln!("Wrote {}", cli.out);
+    // Build policy (default: my side → quality, or hybrid; opp → popularity)
+    let my_side = cfg.policy.resolve_side_override(&side)?;
+    let policy = build_policy(&cfg.policy, my_side)?;
+
+    // Orchestrator; Ctrl-C or SIGTERM stops it early and the partial tree is written
+    let interrupt = Interrupt::new();
+    tokio::spawn(watch_signals(interrupt.clone()));
+    let mut orch = Orchestrator::new(cfg.search.clone(), policy, quality, popularity)
+        .with_interrupt(interrupt);
+    if let Some(path) = cli.checkpoint.as_ref().or(cli.resume.as_ref()) {
+        orch = orch.with_checkpoint(PathBuf::from(path), side.clone());
+    }
+    let (root, summary) = match resume {
+        Some(checkpoint) => orch.resume(checkpoint, plies).await?,
+        None => orch.build_from_start(cli.start.as_deref(), plies).await?,
+    };
+
+    // Write PGN, or a Polyglot book when the output ends in ".bin"
+    let nodes = orch.all_nodes().await;
+    let writer: Box<dyn RepertoireWriter> = if cli.out.ends_with(".bin") {
+        Box::new(PolyglotWriter::new(PieceColor::from_shakmaty(my_side)))
+    } else {
+        Box::new(PgnWriter)
+    };
+    std::fs::write(&cli.out, writer.write_tree(&root, &nodes)?)?;
+    eprintln!("Wrote {} ({})", cli.out, summary);
+    Ok(())
+}
+
+/// The first Ctrl-C or SIGTERM stops the build gracefully: no new nodes are started,
+/// lookups in flight finish and what was built is written. A second one exits at once.
+async fn watch_signals(interrupt: Interrupt) {
+    if shutdown_signal().await.is_err() {
+        return;
+    }
+    eprintln!("Interrupted: finishing lookups in flight, then writing the partial repertoire");
+    eprintln!("(interrupt again to abort without writing)");
+    interrupt.trigger();
+    let _ = shutdown_signal().await;
+    eprintln!("Aborted");
+    std::process::exit(130);
+}
+
+#[cfg(unix)]
+async fn shutdown_signal() -> std::io::Result<()> {
+    use tokio::signal::unix::{SignalKind, signal};
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        res = tokio::signal::ctrl_c() => res,
+        _ = terminate.recv() => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+async fn shutdown_signal() -> std::io::Result<()> {
+    tokio::signal::ctrl_c().await
+}
+
+/// `repgrow index`: scan PGN files once and write the opening index.
+/// Variant and since_year come from the [popularity] config section.
+fn build_index(cfg: &AppConfig, args: &IndexArgs) -> anyhow::Result<()> {
+    let mut builder = IndexBuilder::new(&cfg.popularity, args.max_plies, args.out.as_ref())
+        .with_buffer_positions(args.buffer_positions);
+    for path in &args.pgn {
+        let (read, indexed) = builder.add_pgn_file(path)?;
+        eprintln!("{path}: indexed {indexed} of {read} games");
+    }
+    let games = builder.games();
+    let positions = builder.write(args.min_games)?;
+    eprintln!(
+        "Wrote {} ({positions} positions from {games} games)",
+        args.out
+    );
+    Ok(())
+}
+
+/// `repgrow import-evals`: load a cloud-eval dump into a local eval database.
+fn import_evals(args: &ImportEvalsArgs) -> anyhow::Result<()> {
+    let summary = if args.dump == "-" {
+        import_eval_dump(std::io::stdin().lock(), args.out.as_ref(), args.min_pieces)?
+    } else {
+        let file = std::fs::File::open(&args.dump)
+            .with_context(|| format!("opening eval dump {}", args.dump))?;
+        import_eval_dump(
+            std::io::BufReader::new(file),
+            args.out.as_ref(),
+            args.min_pieces,
+        )?
+    };
+    eprintln!(
+        "Wrote {} ({} positions imported, {} skipped, {} lines read)",
+        args.out, summary.imported, summary.skipped, summary.read
+    );
     Ok(())
 }
@@ -1,51 +1,145 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
 use anyhow::Result;
+use tokio::task::JoinSet;
+use tracing::{debug, info, warn};
 
 use crate::{
     config::SearchConfig,
     domain::CandidateRequest,
     orchestration::{
-        ChildEnqueuer, NodeExpansionOrchestrator, TerminationPolicyPort, WorkQueuePort,
+        CandidateSelector, CheckpointPort, ChildEnqueuer, ExpansionPlanner, MoveApplierPort,
+        NodeExpander, NodeExpansionOrchestrator, ProviderDecisionEngine, StopReason,
+        TerminationPolicyPort, WorkQueuePort,
     },
+    policy::MovePolicy,
+    provider::{MovePopularity, MoveQuality},
+    search::arena::NodeArenaStore,
 };
 
-pub struct HighLevelOrchestrator<'a> {
-    pub queue: &'a dyn WorkQueuePort,
-    pub node_orch: NodeExpansionOrchestrator<'a>,
-    pub enqueuer: ChildEnqueuer<'a>,
-    pub termination: &'a dyn TerminationPolicyPort,
-    pub cfg: &'a SearchConfig,
-}
-
-impl<'a> HighLevelOrchestrator<'a> {
-    pub async fn run(&self, root_id: u64, max_plies: u32) -> Result<()> {
-        self.queue.send(root_id).await?;
-        let base_req = CandidateRequest {
-            fen_key: todo!(),
-            max_candidates: todo!(),
-            cp_window: todo!(),
-            min_play_rate: todo!(),
-            multipv: todo!(),
-        };
-
-        // single-consumer dispatcher: read → spawn → track
-        let mut joinset = tokio::task::JoinSet::new();
+/// How often a waiting dispatcher re-checks the termination budgets.
+const BUDGET_POLL: Duration = Duration::from_millis(200);
+/// How often a checkpoint is written when `cfg.checkpoint_seconds` is unset.
+const DEFAULT_CHECKPOINT_EVERY: Duration = Duration::from_secs(60);
 
-        while let Some(nid) = self.queue.recv().await {
-            let node_orch = self.node_orch.clone_for_spawn(); // or Arc wrap
-            let enq = self.enqueuer; // trait obj reference
-            let base_req = base_req.clone();
+/// Single-consumer dispatcher: takes nodes off the queue and expands each in its own
+/// task, through a `NodeExpansionOrchestrator` pipeline, until a budget runs out or
+/// nothing is queued or in flight.
+pub struct HighLevelOrchestrator {
+    pub queue: Arc<dyn WorkQueuePort>,
+    pub termination: Arc<dyn TerminationPolicyPort>,
+    pub arena: Arc<dyn NodeArenaStore>,
+    pub policy: Arc<dyn MovePolicy>,
+    pub quality: Arc<dyn MoveQuality>,
+    pub popularity: Arc<dyn MovePopularity>,
+    pub applier: Arc<dyn MoveApplierPort>,
+    pub cfg: SearchConfig,
+    /// Settings shared by every node's request; see `ProviderDecisionEngine::fetch_raw`.
+    pub base_req: CandidateRequest,
+    /// Written every `cfg.checkpoint_seconds` and once more when the build stops.
+    pub checkpoint: Option<Arc<dyn CheckpointPort>>,
+}
 
+impl HighLevelOrchestrator {
+    /// Expands everything reachable from the queued nodes (send the root first) and
+    /// returns why the build stopped. Failed expansions are logged and skipped.
+    pub async fn run(self: Arc<Self>) -> Result<StopReason> {
+        let mut joinset = JoinSet::new();
+        let mut stop_reason = StopReason::Completed;
+        let checkpoint_every = self
+            .cfg
+            .checkpoint_seconds
+            .map_or(DEFAULT_CHECKPOINT_EVERY, Duration::from_secs);
+        let mut last_checkpoint = Instant::now();
+        loop {
+            if last_checkpoint.elapsed() >= checkpoint_every {
+                self.save_checkpoint().await;
+                last_checkpoint = Instant::now();
+            }
+            // Wait for a free worker slot before taking the next node, so the queue's
+            // order decides what gets expanded next.
+            while joinset.len() >= self.cfg.concurrency.max(1) {
+                joinset.join_next().await;
+            }
+            if let Some(reason) = self.termination.exhausted() {
+                info!("Stopping: {}", reason);
+                stop_reason = reason;
+                self.queue.close();
+                break;
+            }
+            // Quiescent: nothing queued and no worker left that could queue more.
+            // Workers queue their children before they finish, so this can't race.
+            if joinset.is_empty() && self.queue.is_empty() {
+                info!("Frontier empty, build complete");
+                break;
+            }
+            let nid = tokio::select! {
+                nid = self.queue.recv() => match nid {
+                    Some(nid) => nid,
+                    None => break,
+                },
+                // A finished worker may have been the last one; check again.
+                Some(_) = joinset.join_next(), if !joinset.is_empty() => continue,
+                // Wake up now and then so budgets are checked while workers are busy.
+                _ = tokio::time::sleep(BUDGET_POLL) => continue,
+            };
+            debug!("Dequeued node id: {} for expansion", nid);
+            let this = Arc::clone(&self);
             joinset.spawn(async move {
-                if let Ok(children) = node_orch.expand_one(nid, &base_req).await {
-                    let _ = enq.enqueue_all(&children).await;
+                if let Err(e) = this.expand_and_enqueue(nid).await {
+                    warn!("Expanding node id {} failed: {:#}", nid, e);
                 }
+                debug!("Worker finished for node id: {}", nid);
             });
         }
 
-        // when sender is dropped (outside), drain remaining workers
+        // Stopped or complete → wait for in-flight workers to finish
+        info!("Waiting for all workers to finish...");
         while let Some(_res) = joinset.join_next().await {}
-        Ok(())
+        self.save_checkpoint().await;
+        Ok(stop_reason)
     }
-}
 
-// Integration test (no HTTP): dummy providers + in-mem queue + small caps → ensure build finishes and child count matches expectation.
+    /// A failed save is logged; the build goes on without it.
+    async fn save_checkpoint(&self) {
+        if let Some(checkpoint) = &self.checkpoint
+            && let Err(e) = checkpoint.save().await
+        {
+            warn!("Writing checkpoint failed: {:#}", e);
+        }
+    }
+
+    async fn expand_and_enqueue(&self, node_id: u64) -> Result<()> {
+        let node_orch = NodeExpansionOrchestrator {
+            planner: ExpansionPlanner {
+                arena: &*self.arena,
+                termination: &*self.termination,
+            },
+            provider: ProviderDecisionEngine {
+                policy: &*self.policy,
+                quality: Arc::clone(&self.quality),
+                popularity: Arc::clone(&self.popularity),
+                termination: &*self.termination,
+                cfg: &self.cfg,
+            },
+            selector: CandidateSelector {
+                policy: &*self.policy,
+            },
+            expander: NodeExpander {
+                arena: &*self.arena,
+                applier: &*self.applier,
+                termination: &*self.termination,
+            },
+        };
+        let enqueuer = ChildEnqueuer {
+            queue: &*self.queue,
+            arena: &*self.arena,
+            policy: &*self.policy,
+        };
+        let child_ids = node_orch.expand_one(node_id, &self.base_req).await?;
+        enqueuer.enqueue_all(&child_ids).await
+    }
+}
@@ -21,11 +21,75 @@ impl<'a> NodeExpansionOrchestrator<'a> {
         // fetch
         let raw = self.provider.fetch_raw(&input, base_req).await?;
         // select
-        let selected = self.selector.select(&input, raw);
+        let selected = self.selector.select(raw);
         // expand
         let child_ids = self.expander.expand(&input, selected).await?;
         Ok(child_ids)
     }
 }
 
-// Unit test: mock planner→provider→selector→expander; assert composition order & outputs.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::SearchConfig,
+        domain::{Centipawns, FenKey, PlayRate, candidate_request::CandidateRequestBuilder},
+        orchestration::{PlyLimit, ShakmatyMoveApplier},
+        policy::SideSplitPolicy,
+        search::{
+            arena::{MemArena, NodeArenaStore},
+            build::make_node,
+        },
+        test_support::{StubPopularity, StubQuality},
+    };
+    use shakmaty::Color;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_expand_one_runs_every_stage() {
+        let arena = MemArena::new();
+        let root = arena
+            .push(make_node(None, &FenKey::starting_position(), None, 0))
+            .await;
+        let limit = PlyLimit::new(4);
+        let policy =
+            SideSplitPolicy::new(Color::White, Centipawns::from_int(50), PlayRate::new(0.0));
+        let cfg = SearchConfig::default();
+        let orch = NodeExpansionOrchestrator {
+            planner: ExpansionPlanner {
+                arena: &arena,
+                termination: &limit,
+            },
+            provider: ProviderDecisionEngine {
+                policy: &policy,
+                quality: Arc::new(StubQuality::fixed(&[
+                    ("d2d4", 25),
+                    ("e2e4", 30),
+                    ("a2a4", -90),
+                ])),
+                // Not asked at a my-side node.
+                popularity: Arc::new(StubPopularity::failing()),
+                termination: &limit,
+                cfg: &cfg,
+            },
+            selector: CandidateSelector { policy: &policy },
+            expander: NodeExpander {
+                arena: &arena,
+                applier: &ShakmatyMoveApplier,
+                termination: &limit,
+            },
+        };
+        let base_req = CandidateRequestBuilder::default().build().unwrap();
+
+        let child_ids = orch.expand_one(root, &base_req).await.unwrap();
+        let moves: Vec<_> = arena.all_nodes().await[1..]
+            .iter()
+            .map(|n| n.last_move_uci.as_ref().unwrap().to_uci())
+            .collect();
+        assert_eq!(child_ids, vec![1, 2]);
+        assert_eq!(moves, vec!["e2e4", "d2d4"]);
+        // Expanding again only finds transpositions to the existing children.
+        assert!(orch.expand_one(root, &base_req).await.unwrap().is_empty());
+        assert_eq!(arena.len().await, 3);
+    }
+}
@@ -0,0 +1,29 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::orchestration::{StopReason, TerminationPolicyPort};
+
+/// Stops the build after `max_calls` provider calls, cached answers included. Checked
+/// before each expansion, so expansions already in flight may finish past it.
+pub struct ApiCallBudget {
+    max_calls: usize,
+    calls: AtomicUsize,
+}
+
+impl ApiCallBudget {
+    pub fn new(max_calls: usize) -> Self {
+        Self {
+            max_calls,
+            calls: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl TerminationPolicyPort for ApiCallBudget {
+    fn exhausted(&self) -> Option<StopReason> {
+        (self.calls.load(Ordering::SeqCst) >= self.max_calls).then_some(StopReason::ApiCalls)
+    }
+
+    fn notify_provider_calls(&self, n: usize) {
+        self.calls.fetch_add(n, Ordering::SeqCst);
+    }
+}
@@ -1,20 +1,63 @@
 use crate::{
-    config::SearchConfig,
-    orchestration::{ExpansionInput, RawCandidates, SelectedCandidates},
+    orchestration::{RawCandidates, SelectedCandidates},
     policy::MovePolicy,
 };
 
+/// Third stage: filters and orders the raw candidates with the policy, then caps
+/// them at the request's `max_candidates`.
 pub struct CandidateSelector<'a> {
     pub policy: &'a dyn MovePolicy,
-    pub cfg: &'a SearchConfig,
 }
 
 impl<'a> CandidateSelector<'a> {
-    pub fn select(&self, input: &ExpansionInput, raw: RawCandidates) -> SelectedCandidates {
-        // is_my_side? → call policy.post_filter(is_my_side, raw.moves)
-        // truncate by cfg.{max_children_my_side|max_children_opp_side}
-        // return SelectedCandidates
+    pub fn select(&self, raw: RawCandidates) -> SelectedCandidates {
+        let mut moves = self
+            .policy
+            .post_filter(&raw.request, raw.is_my_side, raw.moves);
+        let surviving = moves.len();
+        moves.truncate(raw.request.max_candidates);
+        SelectedCandidates {
+            moves,
+            surviving,
+            is_my_side: raw.is_my_side,
+        }
     }
 }
 
-// Unit test: seed mixed candidates (eval/play) → verify stable order and cap applied.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::{Centipawns, Eval, PlayRate, candidate_request::CandidateRequestBuilder},
+        policy::SideSplitPolicy,
+        test_support::cand,
+    };
+    use shakmaty::Color;
+
+    #[test]
+    fn test_select_orders_filters_and_caps() {
+        let policy =
+            SideSplitPolicy::new(Color::White, Centipawns::from_int(50), PlayRate::new(0.0));
+        let mut request = CandidateRequestBuilder::default()
+            .max_candidates(2)
+            .build()
+            .unwrap();
+        policy.adjust(&mut request, true);
+        let raw = RawCandidates {
+            moves: vec![
+                cand("g1f3", Some(Eval::Cp(25)), None),
+                cand("a2a4", Some(Eval::Cp(-80)), None),
+                cand("e2e4", Some(Eval::Cp(30)), None),
+                cand("d2d4", Some(Eval::Cp(25)), None),
+            ],
+            request,
+            is_my_side: true,
+        };
+
+        let selected = CandidateSelector { policy: &policy }.select(raw);
+        let ucis: Vec<_> = selected.moves.iter().map(|c| c.uci.to_uci()).collect();
+        assert_eq!(ucis, vec!["e2e4", "d2d4"]);
+        assert_eq!(selected.surviving, 3);
+        assert!(selected.is_my_side);
+    }
+}
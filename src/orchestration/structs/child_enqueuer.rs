@@ -1,18 +1,88 @@
 use anyhow::Result;
+use tracing::debug;
 
-use crate::orchestration::WorkQueuePort;
+use crate::{orchestration::WorkQueuePort, policy::MovePolicy, search::arena::NodeArenaStore};
 
+/// Last stage: queues new children for expansion at the policy's priority.
 pub struct ChildEnqueuer<'a> {
     pub queue: &'a dyn WorkQueuePort,
+    pub arena: &'a dyn NodeArenaStore,
+    pub policy: &'a dyn MovePolicy,
 }
 
 impl<'a> ChildEnqueuer<'a> {
+    /// Once the queue is closed (the build is stopping) the rest are dropped.
     pub async fn enqueue_all(&self, child_ids: &[u64]) -> Result<()> {
         for &cid in child_ids {
-            self.queue.send(cid).await?;
+            let priority = match self.arena.get(cid).await {
+                Some(child) => self.policy.priority(&child),
+                None => anyhow::bail!("missing node {cid}"),
+            };
+            if self.queue.send_with_priority(cid, priority).await.is_err() {
+                debug!("Work queue closed, not enqueuing node id={}", cid);
+                break;
+            }
         }
         Ok(())
     }
 }
 
-// Unit test: feed [1,2,3]; mock queue recording order; assert FIFO and count.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::{Centipawns, FenKey, PlayRate},
+        policy::SideSplitPolicy,
+        search::{arena::MemArena, build::make_node},
+    };
+    use shakmaty::Color;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingQueue {
+        sent: Mutex<Vec<(u64, f32)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl WorkQueuePort for RecordingQueue {
+        async fn send(&self, id: u64) -> Result<()> {
+            self.send_with_priority(id, 0.0).await
+        }
+        async fn send_with_priority(&self, id: u64, priority: f32) -> Result<()> {
+            self.sent.lock().unwrap().push((id, priority));
+            Ok(())
+        }
+        async fn recv(&self) -> Option<u64> {
+            None
+        }
+        fn close(&self) {}
+        fn len(&self) -> usize {
+            self.sent.lock().unwrap().len()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_enqueues_in_order_with_priority() {
+        let arena = MemArena::new();
+        for reach in [0.5, 0.2, 0.3] {
+            let mut node = make_node(None, &FenKey::starting_position(), None, 1);
+            node.signals.reach = Some(reach);
+            arena.push(node).await;
+        }
+        let queue = RecordingQueue::default();
+        let policy =
+            SideSplitPolicy::new(Color::White, Centipawns::from_int(50), PlayRate::new(0.0));
+        let enqueuer = ChildEnqueuer {
+            queue: &queue,
+            arena: &arena,
+            policy: &policy,
+        };
+
+        enqueuer.enqueue_all(&[0, 1, 2]).await.unwrap();
+        assert_eq!(
+            *queue.sent.lock().unwrap(),
+            vec![(0, 0.5), (1, 0.2), (2, 0.3)]
+        );
+        assert!(enqueuer.enqueue_all(&[7]).await.is_err());
+    }
+}
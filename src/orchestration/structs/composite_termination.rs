@@ -0,0 +1,174 @@
+use std::time::Duration;
+
+use crate::{
+    config::SearchConfig,
+    orchestration::{
+        ApiCallBudget, NodeBudget, PlyLimit, StopReason, TerminationPolicyPort, WallClockBudget,
+    },
+};
+
+/// Combines termination policies: a node is expanded only if all of them agree, and
+/// the build stops as soon as any budget runs out.
+#[derive(Default)]
+pub struct CompositeTermination {
+    policies: Vec<Box<dyn TerminationPolicyPort>>,
+}
+
+impl CompositeTermination {
+    pub fn new(policies: Vec<Box<dyn TerminationPolicyPort>>) -> Self {
+        Self { policies }
+    }
+
+    pub fn with(mut self, policy: impl TerminationPolicyPort + 'static) -> Self {
+        self.policies.push(Box::new(policy));
+        self
+    }
+
+    /// The ply limit plus whichever of `max_total_nodes`, `max_seconds` and
+    /// `max_api_calls` are set.
+    pub fn from_config(cfg: &SearchConfig, max_plies: u32) -> Self {
+        let mut termination = Self::default().with(PlyLimit::new(max_plies));
+        if let Some(max_nodes) = cfg.max_total_nodes {
+            termination = termination.with(NodeBudget::new(max_nodes));
+        }
+        if let Some(secs) = cfg.max_seconds {
+            termination = termination.with(WallClockBudget::new(Duration::from_secs(secs)));
+        }
+        if let Some(max_calls) = cfg.max_api_calls {
+            termination = termination.with(ApiCallBudget::new(max_calls));
+        }
+        termination
+    }
+}
+
+impl TerminationPolicyPort for CompositeTermination {
+    fn should_expand(&self, current_ply: u32) -> bool {
+        self.policies.iter().all(|p| p.should_expand(current_ply))
+    }
+
+    fn exhausted(&self) -> Option<StopReason> {
+        self.policies.iter().find_map(|p| p.exhausted())
+    }
+
+    /// Each policy may lower the grant. The grant is the smallest of them, and the
+    /// policies that reserved more give the difference back, so every node budget ends
+    /// up holding exactly the nodes that will be created.
+    fn reserve_nodes(&self, wanted: usize) -> usize {
+        let grants: Vec<usize> = self
+            .policies
+            .iter()
+            .scan(wanted, |granted, p| {
+                *granted = p.reserve_nodes(*granted);
+                Some(*granted)
+            })
+            .collect();
+        let granted = grants.last().copied().unwrap_or(wanted);
+        for (p, reserved) in self.policies.iter().zip(grants) {
+            p.release_nodes(reserved - granted);
+        }
+        granted
+    }
+
+    fn release_nodes(&self, n: usize) {
+        for p in &self.policies {
+            p.release_nodes(n);
+        }
+    }
+
+    fn notify_provider_calls(&self, n: usize) {
+        for p in &self.policies {
+            p.notify_provider_calls(n);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orchestration::Interrupt;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_any_budget_stops_the_build() {
+        let termination = CompositeTermination::default()
+            .with(PlyLimit::new(4))
+            .with(NodeBudget::new(10))
+            .with(ApiCallBudget::new(3));
+        assert!(termination.should_expand(3));
+        assert!(!termination.should_expand(4));
+        assert_eq!(termination.exhausted(), None);
+
+        termination.notify_provider_calls(2);
+        assert_eq!(termination.exhausted(), None);
+        termination.notify_provider_calls(1);
+        assert_eq!(termination.exhausted(), Some(StopReason::ApiCalls));
+    }
+
+    #[test]
+    fn test_reserve_nodes_goes_through_every_budget() {
+        let termination = CompositeTermination::default()
+            .with(NodeBudget::new(10))
+            .with(NodeBudget::new(4));
+        assert_eq!(termination.reserve_nodes(3), 3);
+        assert_eq!(termination.reserve_nodes(3), 1);
+        assert_eq!(termination.exhausted(), Some(StopReason::NodeBudget));
+    }
+
+    /// A node budget the test can still read after handing it to the composite.
+    struct Shared(Arc<NodeBudget>);
+
+    impl TerminationPolicyPort for Shared {
+        fn reserve_nodes(&self, wanted: usize) -> usize {
+            self.0.reserve_nodes(wanted)
+        }
+        fn release_nodes(&self, n: usize) {
+            self.0.release_nodes(n);
+        }
+    }
+
+    #[test]
+    fn test_reserve_nodes_holds_only_the_smallest_grant() {
+        let larger = Arc::new(NodeBudget::new(10));
+        let smaller = Arc::new(NodeBudget::new(4));
+        let termination = CompositeTermination::default()
+            .with(Shared(Arc::clone(&larger)))
+            .with(Shared(Arc::clone(&smaller)));
+        assert_eq!(termination.reserve_nodes(6), 4);
+        assert_eq!(larger.created(), 4);
+        assert_eq!(smaller.created(), 4);
+        assert_eq!(termination.reserve_nodes(6), 0);
+        assert_eq!(larger.created(), 4);
+    }
+
+    #[test]
+    fn test_wall_clock_budget() {
+        let termination =
+            CompositeTermination::default().with(WallClockBudget::new(Duration::ZERO));
+        assert_eq!(termination.exhausted(), Some(StopReason::WallClock));
+        let termination =
+            CompositeTermination::default().with(WallClockBudget::new(Duration::from_secs(3600)));
+        assert_eq!(termination.exhausted(), None);
+    }
+
+    #[test]
+    fn test_interrupt_stops_once_triggered() {
+        let interrupt = Interrupt::new();
+        let termination = CompositeTermination::default().with(interrupt.clone());
+        assert_eq!(termination.exhausted(), None);
+        interrupt.trigger();
+        assert_eq!(termination.exhausted(), Some(StopReason::Interrupted));
+    }
+
+    #[test]
+    fn test_from_config_without_budgets_only_limits_plies() {
+        let cfg = SearchConfig {
+            max_total_nodes: None,
+            ..SearchConfig::default()
+        };
+        let termination = CompositeTermination::from_config(&cfg, 2);
+        assert!(!termination.should_expand(2));
+        assert_eq!(termination.reserve_nodes(1_000), 1_000);
+        termination.notify_provider_calls(1_000);
+        assert_eq!(termination.exhausted(), None);
+    }
+}
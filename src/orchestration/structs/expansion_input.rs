@@ -1,10 +1,11 @@
-use crate::domain::{FenKey, RepertoireNode};
+use crate::domain::{FenKey, RepertoireNode, Signals};
 
 /// Immutable snapshot required to expand a node.
 pub struct ExpansionInput {
     pub node_id: u64,
     pub fen_key: FenKey,
     pub ply_depth: u32,
+    pub signals: Signals,
 }
 
 impl ExpansionInput {
@@ -13,6 +14,12 @@ impl ExpansionInput {
             node_id: node.id,
             fen_key: node.fen_key.clone(),
             ply_depth: node.ply_depth,
+            signals: node.signals.clone(),
         }
     }
+
+    /// Probability of reaching the node; 1.0 if the search never set it.
+    pub fn reach(&self) -> f32 {
+        self.signals.reach.unwrap_or(1.0)
+    }
 }
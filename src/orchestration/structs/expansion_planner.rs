@@ -1,25 +1,63 @@
-use anyhow::Result;
-use dashmap::DashSet;
+use anyhow::{Result, anyhow};
+use tracing::debug;
 
 use crate::{
-    domain::FenKey,
     orchestration::{ExpansionInput, TerminationPolicyPort},
     search::arena::NodeArenaStore,
 };
 
+/// First stage: decides whether a dequeued node gets expanded at all.
 pub struct ExpansionPlanner<'a> {
     pub arena: &'a dyn NodeArenaStore,
-    pub seen: &'a DashSet<FenKey>,
     pub termination: &'a dyn TerminationPolicyPort,
 }
 
 impl<'a> ExpansionPlanner<'a> {
+    /// Snapshot of the node, or None if it is past the ply limit. A missing node is an
+    /// error. Each position is queued once (transpositions link to the existing
+    /// node), so no further deduplication is needed here.
     pub async fn plan(&self, node_id: u64) -> Result<Option<ExpansionInput>> {
-        // 1) read node (snapshot fen, ply)
-        // 2) termination.check(ply) → early return None
-        // 3) seen.insert(fen) → skip duplicates (return None)
-        // 4) return ExpansionInput if work should proceed
+        let node = self
+            .arena
+            .get(node_id)
+            .await
+            .ok_or_else(|| anyhow!("missing node {node_id}"))?;
+        if !self.termination.should_expand(node.ply_depth) {
+            debug!(
+                "Node id={} reached the ply limit, skipping expansion",
+                node_id
+            );
+            return Ok(None);
+        }
+        Ok(Some(ExpansionInput::new(&node)))
     }
 }
 
-// Unit test: mock arena with known node → expect Some(ExpansionInput). test termination and dedup paths return None.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::FenKey,
+        orchestration::PlyLimit,
+        search::{arena::MemArena, build::make_node},
+    };
+
+    #[tokio::test]
+    async fn test_plan_skips_deep_nodes() {
+        let arena = MemArena::new();
+        let start = FenKey::starting_position();
+        let root = arena.push(make_node(None, &start, None, 0)).await;
+        let deep = arena.push(make_node(Some(root), &start, None, 2)).await;
+        let planner = ExpansionPlanner {
+            arena: &arena,
+            termination: &PlyLimit::new(2),
+        };
+
+        let input = planner.plan(root).await.unwrap().expect("root is expanded");
+        assert_eq!(input.node_id, root);
+        assert_eq!(input.fen_key, start);
+        assert_eq!(input.reach(), 1.0);
+        assert!(planner.plan(deep).await.unwrap().is_none());
+        assert!(planner.plan(99).await.is_err());
+    }
+}
@@ -0,0 +1,34 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use crate::orchestration::{StopReason, TerminationPolicyPort};
+
+/// Stops the build once triggered, e.g. from a signal handler. Clones share the flag,
+/// so one can be handed to the build and another kept to trigger it. Expansions
+/// already in flight finish.
+#[derive(Clone, Default)]
+pub struct Interrupt {
+    triggered: Arc<AtomicBool>,
+}
+
+impl Interrupt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn trigger(&self) {
+        self.triggered.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.triggered.load(Ordering::SeqCst)
+    }
+}
+
+impl TerminationPolicyPort for Interrupt {
+    fn exhausted(&self) -> Option<StopReason> {
+        self.is_triggered().then_some(StopReason::Interrupted)
+    }
+}
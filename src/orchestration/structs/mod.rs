@@ -1,21 +1,35 @@
+pub mod api_call_budget;
 pub mod candidate_selector;
 pub mod child_enqueuer;
+pub mod composite_termination;
 pub mod expansion_input;
 pub mod expansion_planner;
+pub mod interrupt;
+pub mod node_budget;
 pub mod node_expander;
+pub mod ply_limit;
+pub mod priority_work_queue;
 pub mod provider_decision_engine;
 pub mod raw_candidates;
 pub mod selected_candidates;
 pub mod shakmaty_move_applier;
 pub mod tokio_mpsc_work_queue;
+pub mod wall_clock_budget;
 
+pub use api_call_budget::ApiCallBudget;
 pub use candidate_selector::CandidateSelector;
 pub use child_enqueuer::ChildEnqueuer;
+pub use composite_termination::CompositeTermination;
 pub use expansion_input::ExpansionInput;
 pub use expansion_planner::ExpansionPlanner;
+pub use interrupt::Interrupt;
+pub use node_budget::NodeBudget;
 pub use node_expander::NodeExpander;
+pub use ply_limit::PlyLimit;
+pub use priority_work_queue::PriorityWorkQueue;
 pub use provider_decision_engine::ProviderDecisionEngine;
 pub use raw_candidates::RawCandidates;
 pub use selected_candidates::SelectedCandidates;
 pub use shakmaty_move_applier::ShakmatyMoveApplier;
 pub use tokio_mpsc_work_queue::TokioMpscWorkQueue;
+pub use wall_clock_budget::WallClockBudget;
@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::orchestration::{StopReason, TerminationPolicyPort};
+
+/// Caps the number of nodes in the tree (`max_total_nodes`). Children are reserved
+/// before they are created, so the cap is never overshot.
+pub struct NodeBudget {
+    max_nodes: usize,
+    created: AtomicUsize,
+}
+
+impl NodeBudget {
+    pub fn new(max_nodes: usize) -> Self {
+        Self {
+            max_nodes,
+            created: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn created(&self) -> usize {
+        self.created.load(Ordering::SeqCst)
+    }
+}
+
+impl TerminationPolicyPort for NodeBudget {
+    fn exhausted(&self) -> Option<StopReason> {
+        (self.created() >= self.max_nodes).then_some(StopReason::NodeBudget)
+    }
+
+    fn reserve_nodes(&self, wanted: usize) -> usize {
+        let mut granted = 0;
+        let _ = self
+            .created
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |created| {
+                granted = wanted.min(self.max_nodes.saturating_sub(created));
+                Some(created + granted)
+            });
+        granted
+    }
+
+    fn release_nodes(&self, n: usize) {
+        self.created.fetch_sub(n, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserves_up_to_the_cap() {
+        let budget = NodeBudget::new(5);
+        assert_eq!(budget.reserve_nodes(1), 1);
+        assert_eq!(budget.exhausted(), None);
+        assert_eq!(budget.reserve_nodes(3), 3);
+        assert_eq!(budget.reserve_nodes(3), 1);
+        assert_eq!(budget.reserve_nodes(2), 0);
+        assert_eq!(budget.created(), 5);
+        assert_eq!(budget.exhausted(), Some(StopReason::NodeBudget));
+    }
+}
@@ -1,25 +1,209 @@
 use anyhow::Result;
+use tracing::debug;
 
 use crate::{
-    orchestration::{ExpansionInput, MoveApplierPort, SelectedCandidates},
+    domain::RepertoireNode,
+    orchestration::{ExpansionInput, MoveApplierPort, SelectedCandidates, TerminationPolicyPort},
     search::arena::NodeArenaStore,
 };
 
+/// Fourth stage: records the selection on the parent and creates a child node per
+/// selected move, as far as the node budget allows. Moves into a position already in
+/// the arena link to that node instead.
 pub struct NodeExpander<'a> {
     pub arena: &'a dyn NodeArenaStore,
     pub applier: &'a dyn MoveApplierPort,
+    pub termination: &'a dyn TerminationPolicyPort,
 }
 
 impl<'a> NodeExpander<'a> {
+    /// Returns the ids of the newly created children, in selection order; these are
+    /// the ones left to expand. Moves the applier rejects are skipped. A parent whose
+    /// children didn't all fit in the node budget stays in the frontier, so a resumed
+    /// build expands it again.
     pub async fn expand(
         &self,
         parent: &ExpansionInput,
         selected: SelectedCandidates,
     ) -> Result<Vec<u64>> {
-        // loop selected.moves:
-        //   applier.apply(input.fen_key, uci) → next_fen
-        //   arena.push(child) + arena.push_child(parent.id, child_id)
-        // collect child_ids
+        // A transposition may have linked the node, and raised its reach, since it
+        // was dequeued.
+        let reach = match self.arena.get(parent.node_id).await {
+            Some(node) => parent.reach().max(node.signals.reach.unwrap_or(1.0)),
+            None => parent.reach(),
+        };
+        let mut signals = parent.signals.clone();
+        signals.surviving_candidates = Some(selected.surviving as u32);
+        signals.reach = Some(reach);
+        self.arena.set_signals(parent.node_id, signals).await;
+
+        let mut child_ids = Vec::with_capacity(selected.moves.len());
+        let mut complete = true;
+        for c in selected.moves {
+            let Ok(next_fen) = self.applier.apply(&parent.fen_key, c.uci.clone()) else {
+                debug!(
+                    "Node id={} cannot play {}, skipping",
+                    parent.node_id,
+                    c.uci.to_uci()
+                );
+                continue;
+            };
+            // Transpositions only add a link, so they don't use up the node budget.
+            // (Two workers racing to the same new position may both reserve a node.)
+            let known = self.arena.find(&next_fen).await.is_some();
+            if !known && self.termination.reserve_nodes(1) == 0 {
+                complete = false;
+                continue;
+            }
+            // Only the opponent's choices make a line less likely; moves without a
+            // play rate don't change it.
+            let mut child_signals = c.signals;
+            child_signals.reach = Some(if selected.is_my_side {
+                reach
+            } else {
+                reach * child_signals.play_rate.map_or(1.0, |r| r.value())
+            });
+            let child = RepertoireNode {
+                id: 0,
+                parent: Some(parent.node_id),
+                fen_key: next_fen,
+                last_move_uci: Some(c.uci),
+                ply_depth: parent.ply_depth + 1,
+                children: Vec::new(),
+                signals: child_signals,
+                other_parents: Vec::new(),
+            };
+            let (child_id, created) = self.arena.push_or_link(child).await;
+            if created {
+                debug!("Node id={} child created: id={}", parent.node_id, child_id);
+                child_ids.push(child_id);
+            } else {
+                // The arena adds this path's reach to the existing node's.
+                debug!(
+                    "Node id={} transposes to node id={}",
+                    parent.node_id, child_id
+                );
+            }
+        }
+        if complete {
+            self.arena.mark_expanded(parent.node_id).await;
+        }
+        Ok(child_ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        orchestration::{NodeBudget, ShakmatyMoveApplier},
+        search::{arena::MemArena, build::make_node, util::apply_uci},
+        test_support::{black_to_move, cand},
+    };
+
+    #[tokio::test]
+    async fn test_expand_links_children_within_budget() {
+        let arena = MemArena::new();
+        let mut root = make_node(None, &black_to_move(), None, 3);
+        root.signals.reach = Some(0.5);
+        let root_id = arena.push(root).await;
+        let (elsewhere, _) = apply_uci(&black_to_move(), "e7e6").unwrap();
+        let known = arena.push(make_node(None, &elsewhere, None, 4)).await;
+        let parent = ExpansionInput::new(&arena.get(root_id).await.unwrap());
+        let selected = SelectedCandidates {
+            moves: vec![
+                cand("e7e5", None, Some(0.4)),
+                cand("a1a1", None, Some(0.3)), // illegal
+                cand("c7c5", None, Some(0.2)),
+                cand("e7e6", None, Some(0.1)),
+                cand("d7d6", None, Some(0.05)),
+            ],
+            surviving: 5,
+            is_my_side: false,
+        };
+        let expander = NodeExpander {
+            arena: &arena,
+            applier: &ShakmatyMoveApplier,
+            termination: &NodeBudget::new(2),
+        };
+
+        // e6 transposes and needs no budget; d6 is over it.
+        let child_ids = expander.expand(&parent, selected).await.unwrap();
+        assert_eq!(child_ids, vec![2, 3]);
+        let root = arena.get(root_id).await.unwrap();
+        assert_eq!(root.children, vec![2, 3, known]);
+        assert_eq!(root.signals.surviving_candidates, Some(5));
+        let child = arena.get(3).await.unwrap();
+        assert_eq!(child.parent, Some(root_id));
+        assert_eq!(child.ply_depth, 4);
+        assert_eq!(
+            child.fen_key,
+            apply_uci(&black_to_move(), "c7c5").unwrap().0
+        );
+        assert_eq!(child.signals.reach, Some(0.1));
+        let transposed = arena.get(known).await.unwrap();
+        assert_eq!(transposed.move_from(root_id).unwrap().to_uci(), "e7e6");
+        assert_eq!(arena.len().await, 4);
+        // d6 is still missing, so the root has to be expanded again on resume.
+        let (_, frontier) = arena.snapshot().await;
+        assert!(frontier.contains(&root_id));
+    }
+
+    #[tokio::test]
+    async fn test_transposition_adds_reach_up_to_one() {
+        let arena = MemArena::new();
+        let mut root = make_node(None, &black_to_move(), None, 3);
+        root.signals.reach = Some(0.5);
+        let root_id = arena.push(root).await;
+        let (e6, _) = apply_uci(&black_to_move(), "e7e6").unwrap();
+        let (c6, _) = apply_uci(&black_to_move(), "c7c6").unwrap();
+        let mut known = make_node(None, &e6, None, 4);
+        known.signals.reach = Some(0.2);
+        let known = arena.push(known).await;
+        let mut almost_certain = make_node(None, &c6, None, 4);
+        almost_certain.signals.reach = Some(0.9);
+        let almost_certain = arena.push(almost_certain).await;
+        let parent = ExpansionInput::new(&arena.get(root_id).await.unwrap());
+        let selected = || SelectedCandidates {
+            moves: vec![cand("e7e6", None, Some(0.2)), cand("c7c6", None, Some(0.4))],
+            surviving: 2,
+            is_my_side: false,
+        };
+        let expander = NodeExpander {
+            arena: &arena,
+            applier: &ShakmatyMoveApplier,
+            termination: &NodeBudget::new(0),
+        };
+
+        expander.expand(&parent, selected()).await.unwrap();
+        let reach = |node: RepertoireNode| node.signals.reach.unwrap();
+        assert!((reach(arena.get(known).await.unwrap()) - 0.3).abs() < 1e-6);
+        assert_eq!(reach(arena.get(almost_certain).await.unwrap()), 1.0);
+        // Linking the same parent again adds nothing.
+        expander.expand(&parent, selected()).await.unwrap();
+        assert!((reach(arena.get(known).await.unwrap()) - 0.3).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_expand_marks_parent_expanded_when_all_children_fit() {
+        let arena = MemArena::new();
+        let root_id = arena.push(make_node(None, &black_to_move(), None, 3)).await;
+        let parent = ExpansionInput::new(&arena.get(root_id).await.unwrap());
+        let selected = SelectedCandidates {
+            moves: vec![cand("e7e5", None, Some(0.6)), cand("a1a1", None, Some(0.3))],
+            surviving: 2,
+            is_my_side: false,
+        };
+        let expander = NodeExpander {
+            arena: &arena,
+            applier: &ShakmatyMoveApplier,
+            termination: &NodeBudget::new(1),
+        };
+
+        // The illegal move never becomes a child, so it doesn't hold the parent back.
+        let child_ids = expander.expand(&parent, selected).await.unwrap();
+        assert_eq!(child_ids, vec![1]);
+        let (_, frontier) = arena.snapshot().await;
+        assert_eq!(frontier, vec![1]);
     }
 }
-// Unit test: stub MoveApplierPort to return deterministic FENs; assert children created & linked.
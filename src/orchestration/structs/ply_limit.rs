@@ -0,0 +1,18 @@
+use crate::orchestration::TerminationPolicyPort;
+
+/// Leaves nodes at `max_plies` or deeper unexpanded; the rest of the build goes on.
+pub struct PlyLimit {
+    max_plies: u32,
+}
+
+impl PlyLimit {
+    pub fn new(max_plies: u32) -> Self {
+        Self { max_plies }
+    }
+}
+
+impl TerminationPolicyPort for PlyLimit {
+    fn should_expand(&self, current_ply: u32) -> bool {
+        current_ply < self.max_plies
+    }
+}
@@ -0,0 +1,162 @@
+use std::{cmp::Ordering, collections::BinaryHeap, sync::Mutex};
+
+use anyhow::anyhow;
+use tokio::sync::Notify;
+
+use crate::orchestration::WorkQueuePort;
+
+/// Work queue handing out the highest-priority node first, so a limited node or API
+/// budget goes to the most important lines (e.g. the most likely ones by reach
+/// probability). Equal priorities come out in the order they were sent; plain
+/// `send` queues at priority 0.
+#[derive(Default)]
+pub struct PriorityWorkQueue {
+    state: Mutex<QueueState>,
+    notify: Notify,
+}
+
+#[derive(Default)]
+struct QueueState {
+    heap: BinaryHeap<QueuedNode>,
+    next_seq: u64,
+    closed: bool,
+}
+
+struct QueuedNode {
+    priority: f32,
+    seq: u64,
+    node_id: u64,
+}
+
+impl Ord for QueuedNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .total_cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for QueuedNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for QueuedNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueuedNode {}
+
+impl PriorityWorkQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl WorkQueuePort for PriorityWorkQueue {
+    async fn send(&self, node_id: u64) -> anyhow::Result<()> {
+        self.send_with_priority(node_id, 0.0).await
+    }
+
+    async fn send_with_priority(&self, node_id: u64, priority: f32) -> anyhow::Result<()> {
+        {
+            let mut state = self.state.lock().expect("queue lock");
+            if state.closed {
+                return Err(anyhow!("work queue closed"));
+            }
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state.heap.push(QueuedNode {
+                priority,
+                seq,
+                node_id,
+            });
+        }
+        self.notify.notify_one();
+        Ok(())
+    }
+
+    /// Waits for the next node; after `close`, drains what is left and then returns None.
+    async fn recv(&self) -> Option<u64> {
+        loop {
+            let notified = self.notify.notified();
+            {
+                let mut state = self.state.lock().expect("queue lock");
+                if let Some(next) = state.heap.pop() {
+                    return Some(next.node_id);
+                }
+                if state.closed {
+                    return None;
+                }
+            }
+            notified.await;
+        }
+    }
+
+    fn close(&self) {
+        self.state.lock().expect("queue lock").closed = true;
+        self.notify.notify_waiters();
+    }
+
+    fn len(&self) -> usize {
+        self.state.lock().expect("queue lock").heap.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_highest_priority_first_and_fifo_ties() {
+        let queue = PriorityWorkQueue::new();
+        queue.send_with_priority(1, 0.02).await.unwrap();
+        queue.send_with_priority(2, 0.6).await.unwrap();
+        queue.send_with_priority(3, 0.3).await.unwrap();
+        queue.send_with_priority(4, 0.6).await.unwrap();
+        queue.send(5).await.unwrap();
+        assert_eq!(queue.len(), 5);
+
+        let mut order = Vec::new();
+        for _ in 0..5 {
+            order.push(queue.recv().await.unwrap());
+        }
+        assert_eq!(order, vec![2, 4, 3, 1, 5]);
+        assert!(queue.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_close_drains_then_ends() {
+        let queue = PriorityWorkQueue::new();
+        queue.send_with_priority(7, 0.5).await.unwrap();
+        queue.close();
+        assert!(queue.send(8).await.is_err());
+        assert_eq!(queue.recv().await, Some(7));
+        assert_eq!(queue.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_recv_waits_for_send_and_close() {
+        let queue = Arc::new(PriorityWorkQueue::new());
+        let consumer = {
+            let queue = Arc::clone(&queue);
+            tokio::spawn(async move {
+                let mut got = Vec::new();
+                while let Some(id) = queue.recv().await {
+                    got.push(id);
+                }
+                got
+            })
+        };
+        tokio::task::yield_now().await;
+        queue.send_with_priority(1, 0.9).await.unwrap();
+        tokio::task::yield_now().await;
+        queue.close();
+        assert_eq!(consumer.await.unwrap(), vec![1]);
+    }
+}
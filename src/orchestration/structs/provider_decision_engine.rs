@@ -1,28 +1,193 @@
 use std::sync::Arc;
 
+use tracing::{debug, warn};
+
 use crate::{
+    config::SearchConfig,
     domain::CandidateRequest,
-    orchestration::{ExpansionInput, RawCandidates},
-    policy::MovePolicy,
-    provider::{MovePopularity, MoveQuality},
+    orchestration::{ExpansionInput, RawCandidates, TerminationPolicyPort},
+    policy::{Decision, MovePolicy},
+    provider::{
+        MovePopularity, MoveQuality, normalize_hybrid, normalize_popularity, normalize_quality,
+    },
 };
 
+/// Second stage: shapes the request for a node and asks the provider(s) the policy
+/// picks for its side to move.
 pub struct ProviderDecisionEngine<'a> {
     pub policy: &'a dyn MovePolicy,
     pub quality: Arc<dyn MoveQuality>,
     pub popularity: Arc<dyn MovePopularity>,
+    pub termination: &'a dyn TerminationPolicyPort,
+    pub cfg: &'a SearchConfig,
 }
 
 impl<'a> ProviderDecisionEngine<'a> {
+    /// `base_req` supplies the settings shared by every node (e.g. `multipv`); the
+    /// position, reach and child cap come from `input`. Hybrid nodes fail only if
+    /// both providers do.
     pub async fn fetch_raw(
         &self,
         input: &ExpansionInput,
         base_req: &CandidateRequest,
     ) -> anyhow::Result<RawCandidates> {
-        // call policy.decide(stm), policy.adjust(req, is_my_side)
-        // await provider
-        // normalize_* → RawCandidates
+        let stm = input.fen_key.side_to_move().to_shakmaty();
+        let is_my_side = self.policy.is_my_side(stm);
+        let cap = if is_my_side {
+            self.cfg.max_children_my_side
+        } else {
+            self.cfg.max_children_opp_side
+        };
+        let mut req = base_req.clone();
+        req.fen_key = input.fen_key.clone();
+        req.reach = input.reach();
+        // An unset cap keeps every candidate the policy lets through.
+        req.max_candidates = cap.unwrap_or(usize::MAX);
+        // Windows are filled in by the policy's `adjust`.
+        self.policy.adjust(&mut req, is_my_side);
+
+        let decision = self.policy.decide(stm);
+        self.termination
+            .notify_provider_calls(if decision == Decision::Hybrid { 2 } else { 1 });
+        let nid = input.node_id;
+        let moves = match decision {
+            Decision::Quality => {
+                let evals = self
+                    .quality
+                    .evaluate(&req.fen_key, Some(req.multipv))
+                    .await?;
+                debug!("Quality evals for node id={}: {:?}", nid, evals);
+                normalize_quality(&req.fen_key, evals)
+            }
+            Decision::Popularity => {
+                let rows = self.popularity.sample(&req.fen_key).await?;
+                debug!("Popularity rows for node id={}: {:?}", nid, rows);
+                normalize_popularity(&req.fen_key, rows)
+            }
+            Decision::Hybrid => {
+                let (evals, rows) = tokio::join!(
+                    self.quality.evaluate(&req.fen_key, Some(req.multipv)),
+                    self.popularity.sample(&req.fen_key)
+                );
+                debug!(
+                    "Hybrid evals/rows for node id={}: {:?} / {:?}",
+                    nid, evals, rows
+                );
+                match (evals, rows) {
+                    (Err(e), Err(_)) => return Err(e),
+                    (evals, rows) => {
+                        let evals = evals.unwrap_or_else(|e| {
+                            warn!("quality failed for node id={}: {:#}", nid, e);
+                            Vec::new()
+                        });
+                        let rows = rows.unwrap_or_else(|e| {
+                            warn!("popularity failed for node id={}: {:#}", nid, e);
+                            Vec::new()
+                        });
+                        normalize_hybrid(&req.fen_key, evals, rows)
+                    }
+                }
+            }
+        };
+        Ok(RawCandidates {
+            moves,
+            request: req,
+            is_my_side,
+        })
     }
 }
 
-// Unit test: fake quality/popularity providers → assert the right one is called & req shaped.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::{Centipawns, FenKey, PlayRate, candidate_request::CandidateRequestBuilder},
+        orchestration::ApiCallBudget,
+        policy::SideSplitPolicy,
+        search::build::make_node,
+        test_support::{StubPopularity, StubQuality, black_to_move},
+    };
+    use shakmaty::Color;
+    use std::sync::atomic::Ordering;
+
+    #[tokio::test]
+    async fn test_fetch_raw_asks_the_provider_for_the_side_to_move() {
+        let policy =
+            SideSplitPolicy::new(Color::White, Centipawns::from_int(50), PlayRate::new(0.05));
+        let quality = Arc::new(StubQuality::fixed(&[("e2e4", 30)]));
+        let popularity = Arc::new(StubPopularity::fixed(&[("c7c5", 0.4)]));
+        let budget = ApiCallBudget::new(100);
+        let cfg = SearchConfig {
+            max_children_my_side: Some(2),
+            max_children_opp_side: Some(4),
+            ..SearchConfig::default()
+        };
+        let engine = ProviderDecisionEngine {
+            policy: &policy,
+            quality: quality.clone(),
+            popularity: popularity.clone(),
+            termination: &budget,
+            cfg: &cfg,
+        };
+        let base_req = CandidateRequestBuilder::default()
+            .multipv(3)
+            .build()
+            .unwrap();
+
+        let white = ExpansionInput::new(&make_node(None, &FenKey::starting_position(), None, 0));
+        let raw = engine.fetch_raw(&white, &base_req).await.unwrap();
+        assert!(raw.is_my_side);
+        assert_eq!(raw.request.max_candidates, 2);
+        assert_eq!(raw.request.cp_window, Centipawns::from_int(50));
+        assert_eq!(raw.moves[0].uci.to_uci(), "e2e4");
+        assert_eq!(*quality.multipvs.lock().unwrap(), vec![Some(3)]);
+
+        let fen = black_to_move();
+        let mut node = make_node(Some(0), &fen, None, 1);
+        node.signals.reach = Some(0.5);
+        let raw = engine
+            .fetch_raw(&ExpansionInput::new(&node), &base_req)
+            .await
+            .unwrap();
+        assert!(!raw.is_my_side);
+        assert_eq!(raw.request.fen_key, fen);
+        assert_eq!(raw.request.reach, 0.5);
+        assert_eq!(raw.request.max_candidates, 4);
+        assert_eq!(raw.request.min_play_rate, PlayRate::new(0.05));
+        assert_eq!(raw.moves[0].uci.to_uci(), "c7c5");
+        assert_eq!(popularity.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(quality.calls.load(Ordering::SeqCst), 1);
+
+        let budget = ApiCallBudget::new(2);
+        let engine = ProviderDecisionEngine {
+            termination: &budget,
+            ..engine
+        };
+        engine.fetch_raw(&white, &base_req).await.unwrap();
+        assert!(budget.exhausted().is_none());
+        engine.fetch_raw(&white, &base_req).await.unwrap();
+        assert!(budget.exhausted().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_raw_without_child_caps() {
+        let policy =
+            SideSplitPolicy::new(Color::White, Centipawns::from_int(50), PlayRate::new(0.05));
+        let cfg = SearchConfig {
+            max_children_my_side: None,
+            max_children_opp_side: None,
+            ..SearchConfig::default()
+        };
+        let engine = ProviderDecisionEngine {
+            policy: &policy,
+            quality: Arc::new(StubQuality::fixed(&[("e2e4", 30)])),
+            popularity: Arc::new(StubPopularity::fixed(&[("c7c5", 0.4)])),
+            termination: &ApiCallBudget::new(100),
+            cfg: &cfg,
+        };
+        let base_req = CandidateRequestBuilder::default().build().unwrap();
+        let root = ExpansionInput::new(&make_node(None, &FenKey::starting_position(), None, 0));
+        let raw = engine.fetch_raw(&root, &base_req).await.unwrap();
+        assert_eq!(raw.request.max_candidates, usize::MAX);
+    }
+}
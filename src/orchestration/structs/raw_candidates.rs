@@ -1,6 +1,9 @@
-use crate::domain::CandidateMove;
+use crate::domain::{CandidateMove, CandidateRequest};
 
 /// Result of fetching raw candidates from providers (unfiltered).
 pub struct RawCandidates {
     pub moves: Vec<CandidateMove>, // normalized (eval/play_rate present when known)
+    /// The request as shaped by the policy for this node.
+    pub request: CandidateRequest,
+    pub is_my_side: bool,
 }
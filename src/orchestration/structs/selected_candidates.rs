@@ -3,4 +3,7 @@ use crate::domain::CandidateMove;
 /// Result after policy filtering/capping and deterministic ordering.
 pub struct SelectedCandidates {
     pub moves: Vec<CandidateMove>,
+    /// Moves that passed the policy window, before the child cap.
+    pub surviving: usize,
+    pub is_my_side: bool,
 }
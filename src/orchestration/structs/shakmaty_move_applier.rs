@@ -1 +1,37 @@
-pub struct ShakmatyMoveApplier; // implements MoveApplierPort via shakmaty
+use anyhow::Result;
+
+use crate::{
+    domain::{FenKey, chess::UciMove},
+    orchestration::MoveApplierPort,
+    search::util::apply_uci,
+};
+
+/// Applies moves with shakmaty; illegal moves are an error.
+pub struct ShakmatyMoveApplier;
+
+impl MoveApplierPort for ShakmatyMoveApplier {
+    fn apply(&self, fen: &FenKey, uci: UciMove) -> Result<FenKey> {
+        apply_uci(fen, &uci.to_uci()).map(|(next_fen, _stm)| next_fen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{domain::PieceColor, test_support::BLACK_TO_MOVE};
+
+    #[test]
+    fn test_applies_legal_moves_only() {
+        let start = FenKey::starting_position();
+        let next = ShakmatyMoveApplier
+            .apply(&start, UciMove::from_uci("e2e4").unwrap())
+            .unwrap();
+        assert_eq!(next.fen_string(), BLACK_TO_MOVE);
+        assert_eq!(next.side_to_move(), PieceColor::Black);
+        assert!(
+            ShakmatyMoveApplier
+                .apply(&start, UciMove::from_uci("e2e5").unwrap())
+                .is_err()
+        );
+    }
+}
@@ -1,23 +1,80 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::sync::{
     Mutex,
-    mpsc::{Receiver, Sender},
+    mpsc::{self, UnboundedReceiver, UnboundedSender},
 };
 
 use crate::orchestration::WorkQueuePort;
 
 pub struct TokioMpscWorkQueue {
-    tx: Sender<u64>,
-    rx: Mutex<Receiver<u64>>,
+    tx: UnboundedSender<u64>,
+    rx: Mutex<UnboundedReceiver<u64>>,
+    queued: AtomicUsize,
 }
+
+impl TokioMpscWorkQueue {
+    /// FIFO queue. Unbounded, so workers never wait on `send` while the dispatcher
+    /// waits for a free worker slot.
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            tx,
+            rx: Mutex::new(rx),
+            queued: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl Default for TokioMpscWorkQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[async_trait::async_trait]
 impl WorkQueuePort for TokioMpscWorkQueue {
     async fn send(&self, id: u64) -> anyhow::Result<()> {
-        self.tx.send(id).await.map_err(Into::into)
+        // Count before sending so a fast receiver never sees the count below zero.
+        self.queued.fetch_add(1, Ordering::SeqCst);
+        self.tx.send(id).map_err(|e| {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            e.into()
+        })
     }
     async fn recv(&self) -> Option<u64> {
-        self.rx.lock().await.recv().await
+        let id = self.rx.lock().await.recv().await;
+        if id.is_some() {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+        }
+        id
     }
     fn close(&self) {
-        self.tx.close_channel();
+        if let Ok(mut rx) = self.rx.try_lock() {
+            rx.close();
+        }
+    }
+    fn len(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fifo_order_and_len() {
+        let queue = TokioMpscWorkQueue::new();
+        for id in [3, 1, 2] {
+            queue.send_with_priority(id, id as f32).await.unwrap();
+        }
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.recv().await, Some(3));
+        assert_eq!(queue.recv().await, Some(1));
+        assert_eq!(queue.len(), 1);
+        queue.close();
+        assert_eq!(queue.recv().await, Some(2));
+        assert_eq!(queue.recv().await, None);
+        assert!(queue.is_empty());
     }
 }
@@ -0,0 +1,24 @@
+use std::time::{Duration, Instant};
+
+use crate::orchestration::{StopReason, TerminationPolicyPort};
+
+/// Stops the build once `limit` has passed since the budget was created.
+pub struct WallClockBudget {
+    started: Instant,
+    limit: Duration,
+}
+
+impl WallClockBudget {
+    pub fn new(limit: Duration) -> Self {
+        Self {
+            started: Instant::now(),
+            limit,
+        }
+    }
+}
+
+impl TerminationPolicyPort for WallClockBudget {
+    fn exhausted(&self) -> Option<StopReason> {
+        (self.started.elapsed() >= self.limit).then_some(StopReason::WallClock)
+    }
+}
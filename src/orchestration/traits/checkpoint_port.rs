@@ -0,0 +1,7 @@
+use async_trait::async_trait;
+
+/// Persists a build in progress so it can be resumed after a crash or interrupt.
+#[async_trait]
+pub trait CheckpointPort: Send + Sync {
+    async fn save(&self) -> anyhow::Result<()>;
+}
@@ -1,7 +1,9 @@
+pub mod checkpoint_port;
 pub mod move_applier_port;
 pub mod termination_policy_port;
 pub mod work_queue_port;
 
+pub use checkpoint_port::CheckpointPort;
 pub use move_applier_port::MoveApplierPort;
-pub use termination_policy_port::{SimpleTerminationPolicy, TerminationPolicyPort};
+pub use termination_policy_port::{StopReason, TerminationPolicyPort};
 pub use work_queue_port::WorkQueuePort;
@@ -2,6 +2,7 @@ use anyhow::Result;
 
 use crate::domain::{FenKey, chess::UciMove};
 
-pub trait MoveApplierPort {
+/// Plays a move on a position, giving the position after it.
+pub trait MoveApplierPort: Send + Sync {
     fn apply(&self, fen: &FenKey, uci: UciMove) -> Result<FenKey>;
 }
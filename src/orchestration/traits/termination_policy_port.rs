@@ -1,11 +1,53 @@
-use async_trait::async_trait;
+use std::fmt;
 
-#[async_trait]
-pub trait TerminationPolicyPort: Send + Sync {
-    fn should_expand(&self, current_ply: u32, max_plies: u32) -> bool;
-    fn notify_children_created(&self, n: usize); // optional for global caps
+/// Why a build stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// Every queued node was expanded.
+    Completed,
+    /// `max_total_nodes` nodes were created.
+    NodeBudget,
+    /// The wall-clock budget ran out.
+    WallClock,
+    /// The provider-call budget ran out.
+    ApiCalls,
+    /// The user interrupted the build (Ctrl-C or SIGTERM).
+    Interrupted,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            StopReason::Completed => "completed",
+            StopReason::NodeBudget => "node budget exhausted",
+            StopReason::WallClock => "time budget exhausted",
+            StopReason::ApiCalls => "API call budget exhausted",
+            StopReason::Interrupted => "interrupted",
+        })
+    }
 }
 
-pub struct SimpleTerminationPolicy {/* counters, max_total_nodes */}
+/// Decides when a build stops growing the tree. Consulted by the orchestrator before
+/// each expansion; the default methods place no limit.
+pub trait TerminationPolicyPort: Send + Sync {
+    /// Whether a node at `current_ply` should be expanded; false skips only that node.
+    fn should_expand(&self, _current_ply: u32) -> bool {
+        true
+    }
+
+    /// The budget that has run out, if any; the whole build then stops.
+    fn exhausted(&self) -> Option<StopReason> {
+        None
+    }
+
+    /// Reserve room for `wanted` new nodes and return how many may be created.
+    fn reserve_nodes(&self, wanted: usize) -> usize {
+        wanted
+    }
 
-// Unit test: feed boundaries → expect allow/deny expansion; verify counter increments.
+    /// Give back `n` nodes reserved with `reserve_nodes` that will not be created.
+    fn release_nodes(&self, _n: usize) {}
+
+    /// Record `n` provider calls (engine or explorer lookups).
+    fn notify_provider_calls(&self, _n: usize) {}
+}
@@ -1,8 +1,18 @@
 use async_trait::async_trait;
 
 #[async_trait]
-pub trait WorkQueuePort {
+pub trait WorkQueuePort: Send + Sync {
     async fn send(&self, node_id: u64) -> anyhow::Result<()>;
+    /// Queue a node with a priority (higher first) for queues that order by it;
+    /// FIFO queues ignore the priority.
+    async fn send_with_priority(&self, node_id: u64, _priority: f32) -> anyhow::Result<()> {
+        self.send(node_id).await
+    }
     async fn recv(&self) -> Option<u64>;
     fn close(&self);
+    /// Nodes sent but not yet received.
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
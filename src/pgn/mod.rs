@@ -1,9 +1,13 @@
+pub mod pgn_reader;
 pub mod pgn_writer;
+pub mod polyglot_writer;
 pub mod repertoire_writer;
 pub mod san_converter;
 pub mod uci_str;
 
+pub use pgn_reader::{PgnGame, PgnReader};
 pub use pgn_writer::PgnWriter;
+pub use polyglot_writer::PolyglotWriter;
 pub use repertoire_writer::RepertoireWriter;
 pub use san_converter::{MockSanConverter, SanConverter};
 pub use uci_str::UciStr;
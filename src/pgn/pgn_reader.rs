@@ -0,0 +1,243 @@
+//! Minimal streaming PGN reader: splits a PGN database into games with their
+//! header tags and mainline SAN moves. Comments, NAGs and variations are skipped.
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, Lines},
+};
+
+/// One game from a PGN file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PgnGame {
+    pub headers: HashMap<String, String>,
+    /// Mainline moves in SAN, without move numbers or annotations.
+    pub moves: Vec<String>,
+}
+
+impl PgnGame {
+    /// Header value by tag name, ignoring placeholder values like `?` and `????.??.??`.
+    pub fn header(&self, tag: &str) -> Option<&str> {
+        self.headers
+            .get(tag)
+            .map(String::as_str)
+            .filter(|v| !v.is_empty() && !v.starts_with('?'))
+    }
+}
+
+/// Iterator over the games of a PGN source.
+pub struct PgnReader<R: BufRead> {
+    lines: Lines<R>,
+    pending_header: Option<String>,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            pending_header: None,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = std::io::Result<PgnGame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut game = PgnGame::default();
+        let mut movetext = String::new();
+        let mut seen_anything = false;
+        let mut in_comment = false;
+
+        if let Some(line) = self.pending_header.take() {
+            parse_header(&line, &mut game.headers);
+            seen_anything = true;
+        }
+
+        loop {
+            let line = match self.lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(e)) => return Some(Err(e)),
+                None => break,
+            };
+            let trimmed = line.trim();
+            if !in_comment && trimmed.starts_with('[') {
+                if !movetext.trim().is_empty() {
+                    // A header after movetext starts the next game.
+                    self.pending_header = Some(trimmed.to_string());
+                    break;
+                }
+                parse_header(trimmed, &mut game.headers);
+                seen_anything = true;
+                continue;
+            }
+            if trimmed.starts_with('%') {
+                continue;
+            }
+            in_comment = update_comment_state(trimmed, in_comment);
+            movetext.push_str(trimmed);
+            movetext.push('\n');
+            seen_anything |= !trimmed.is_empty();
+        }
+
+        if !seen_anything {
+            return None;
+        }
+        game.moves = mainline_moves(&movetext);
+        Some(Ok(game))
+    }
+}
+
+/// Parse a `[Tag "value"]` line into `headers`; malformed lines are ignored.
+fn parse_header(line: &str, headers: &mut HashMap<String, String>) {
+    let Some(inner) = line
+        .strip_prefix('[')
+        .and_then(|l| l.trim_end().strip_suffix(']'))
+    else {
+        return;
+    };
+    let Some((tag, rest)) = inner.split_once(char::is_whitespace) else {
+        return;
+    };
+    let value = rest
+        .trim()
+        .trim_start_matches('"')
+        .trim_end_matches('"')
+        .replace("\\\"", "\"");
+    headers.insert(tag.to_string(), value);
+}
+
+/// Whether a `{ ... }` comment is still open at the end of `line`.
+fn update_comment_state(line: &str, mut in_comment: bool) -> bool {
+    for c in line.chars() {
+        match c {
+            '{' => in_comment = true,
+            '}' => in_comment = false,
+            ';' if !in_comment => break,
+            _ => {}
+        }
+    }
+    in_comment
+}
+
+/// Extract the mainline SAN tokens from movetext.
+fn mainline_moves(movetext: &str) -> Vec<String> {
+    let mut moves = Vec::new();
+    let mut token = String::new();
+    let mut chars = movetext.chars();
+    let mut variation_depth = 0usize;
+
+    let mut flush = |token: &mut String, depth: usize| {
+        if depth == 0
+            && let Some(san) = san_token(token)
+        {
+            moves.push(san);
+        }
+        token.clear();
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                flush(&mut token, variation_depth);
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+            }
+            ';' => {
+                flush(&mut token, variation_depth);
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '(' => {
+                flush(&mut token, variation_depth);
+                variation_depth += 1;
+            }
+            ')' => {
+                flush(&mut token, variation_depth);
+                variation_depth = variation_depth.saturating_sub(1);
+            }
+            c if c.is_whitespace() => flush(&mut token, variation_depth),
+            c => token.push(c),
+        }
+    }
+    flush(&mut token, variation_depth);
+    moves
+}
+
+/// Strip move numbers and annotations from a token; `None` if nothing playable remains.
+fn san_token(token: &str) -> Option<String> {
+    if matches!(token, "*" | "1-0" | "0-1" | "1/2-1/2") || token.starts_with('$') {
+        return None;
+    }
+    // "12." / "12..." prefixes, possibly glued to the move ("12.e4").
+    let token = token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+    let token = token.trim_end_matches(['!', '?']);
+    if token.is_empty() {
+        return None;
+    }
+    Some(token.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWO_GAMES: &str = r#"[Event "Club ch"]
+[White "A"]
+[Black "B"]
+[WhiteElo "1500"]
+[Date "2021.03.??"]
+[Result "1-0"]
+
+1. e4 {best by test} e5 2. Nf3 (2. f4 exf4 (2... d5) 3. Nf3) 2... Nc6 $1
+3. Bb5!? a6 ; the Morphy defence
+4. Ba4 1-0
+
+[Event "Club ch"]
+[Result "1/2-1/2"]
+[TimeControl "-"]
+
+1.d4 d5 2.c4 { a comment
+spanning [lines] } e6 1/2-1/2
+"#;
+
+    fn read_all(text: &str) -> Vec<PgnGame> {
+        PgnReader::new(text.as_bytes())
+            .collect::<std::io::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_reads_headers_and_mainline() {
+        let games = read_all(TWO_GAMES);
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].header("WhiteElo"), Some("1500"));
+        assert_eq!(games[0].header("Date"), Some("2021.03.??"));
+        assert_eq!(games[0].header("BlackElo"), None);
+        assert_eq!(
+            games[0].moves,
+            vec!["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Ba4"]
+        );
+        assert_eq!(games[1].moves, vec!["d4", "d5", "c4", "e6"]);
+        assert_eq!(games[1].header("TimeControl"), Some("-"));
+    }
+
+    #[test]
+    fn test_placeholder_headers_are_missing() {
+        let games = read_all("[WhiteElo \"?\"]\n[Date \"????.??.??\"]\n\n1. e4 *\n");
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].header("WhiteElo"), None);
+        assert_eq!(games[0].header("Date"), None);
+        assert_eq!(games[0].moves, vec!["e4"]);
+    }
+
+    #[test]
+    fn test_empty_input_has_no_games() {
+        assert!(read_all("\n\n").is_empty());
+    }
+}
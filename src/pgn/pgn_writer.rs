@@ -1,139 +1,171 @@
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::{PieceColor, RepertoireNode, chess::UciMove, fen_key::FenKey};
+    use crate::{
+        domain::{ParentLink, RepertoireNode, chess::UciMove, fen_key::FenKey},
+        pgn::MockSanConverter,
+        search::util::apply_uci,
+        test_support::BLACK_TO_MOVE,
+    };
 
-    /// Helper to build a node with children
-    fn node(
-        id: u64,
-        parent: Option<u64>,
-        fen: &str,
-        color: PieceColor,
-        uci: Option<UciMove>,
-        ply: u32,
-        children: Vec<u64>,
-    ) -> RepertoireNode {
+    /// A node reached by the UCI moves of `line` from the starting position.
+    fn node(id: u64, parent: Option<u64>, line: &str, children: Vec<u64>) -> RepertoireNode {
+        let mut fen_key = FenKey::starting_position();
+        let mut last_move_uci = None;
+        let moves: Vec<&str> = line.split_whitespace().collect();
+        for uci in &moves {
+            fen_key = apply_uci(&fen_key, uci).unwrap().0;
+            last_move_uci = UciMove::from_uci(uci).ok();
+        }
         RepertoireNode {
             id,
             parent,
-            fen_key: FenKey::new(fen.to_string(), color),
-            last_move_uci: uci,
-            ply_depth: ply,
+            fen_key,
+            last_move_uci,
+            ply_depth: moves.len() as u32,
             children,
             signals: Default::default(),
+            other_parents: Vec::new(),
         }
     }
 
+    /// The last line of the PGN: the moves.
+    fn movetext(pgn: &str) -> &str {
+        pgn.lines().last().unwrap()
+    }
+
     #[test]
     fn test_single_mainline() {
         // 1. e4 e5 2. Nf3 Nc6
-        let n0 = node(0, None, "startpos", PieceColor::White, None, 0, vec![1]);
-        let n1 = node(
-            1,
-            Some(0),
-            "fen1",
-            PieceColor::Black,
-            Some(UciMove::from_uci("e2e4").unwrap()),
-            1,
-            vec![2],
-        );
-        let n2 = node(
-            2,
-            Some(1),
-            "fen2",
-            PieceColor::White,
-            Some(UciMove::from_uci("e7e5").unwrap()),
-            2,
-            vec![3],
-        );
-        let n3 = node(
-            3,
-            Some(2),
-            "fen3",
-            PieceColor::Black,
-            Some(UciMove::from_uci("g1f3").unwrap()),
-            3,
-            vec![4],
-        );
-        let n4 = node(
-            4,
-            Some(3),
-            "fen4",
-            PieceColor::White,
-            Some(UciMove::from_uci("b8c6").unwrap()),
-            4,
-            vec![],
-        );
-        let nodes = vec![n0.clone(), n1, n2, n3, n4];
+        let nodes = vec![
+            node(0, None, "", vec![1]),
+            node(1, Some(0), "e2e4", vec![2]),
+            node(2, Some(1), "e2e4 e7e5", vec![3]),
+            node(3, Some(2), "e2e4 e7e5 g1f3", vec![4]),
+            node(4, Some(3), "e2e4 e7e5 g1f3 b8c6", vec![]),
+        ];
         let writer = PgnWriter;
-        let pgn = writer.write_with_nodes(&n0, &nodes).unwrap();
-        assert!(pgn.contains("1. e2e4 2. e7e5 3. g1f3 4. b8c6"));
+        let pgn = writer.write_with_nodes(&nodes[0], &nodes).unwrap();
+        assert_eq!(movetext(&pgn), "1. e2e4 e7e5 2. g1f3 b8c6 *");
     }
 
     #[test]
     fn test_variations() {
         // 1. e4 (1. d4)
-        let n0 = node(0, None, "startpos", PieceColor::White, None, 0, vec![1, 2]);
-        let n1 = node(
-            1,
-            Some(0),
-            "fen1",
-            PieceColor::Black,
-            Some(UciMove::from_uci("e2e4").unwrap()),
-            1,
-            vec![],
+        let nodes = vec![
+            node(0, None, "", vec![1, 2]),
+            node(1, Some(0), "e2e4", vec![]),
+            node(2, Some(0), "d2d4", vec![]),
+        ];
+        let writer = PgnWriter;
+        let pgn = writer.write_with_nodes(&nodes[0], &nodes).unwrap();
+        assert_eq!(movetext(&pgn), "1. e2e4 (1. d2d4) *");
+    }
+
+    #[test]
+    fn test_black_variation_numbering() {
+        // 1. e4 e5 (1... c5 2. Nf3) 2. Nf3
+        let nodes = vec![
+            node(0, None, "", vec![1]),
+            node(1, Some(0), "e2e4", vec![2, 3]),
+            node(2, Some(1), "e2e4 e7e5", vec![4]),
+            node(3, Some(1), "e2e4 c7c5", vec![5]),
+            node(4, Some(2), "e2e4 e7e5 g1f3", vec![]),
+            node(5, Some(3), "e2e4 c7c5 g1f3", vec![]),
+        ];
+        let pgn = PgnWriter.write_with_nodes(&nodes[0], &nodes).unwrap();
+        assert_eq!(movetext(&pgn), "1. e2e4 e7e5 (1... c7c5 2. g1f3) 2. g1f3 *");
+    }
+
+    #[test]
+    fn test_transposition_comment() {
+        // 1. e4 e5 2. Nf3, and 1. Nf3 e5 2. e4 reaching the same position.
+        let mut nodes = vec![
+            node(0, None, "", vec![1, 4]),
+            node(1, Some(0), "e2e4", vec![2]),
+            node(2, Some(1), "e2e4 e7e5", vec![3]),
+            node(3, Some(2), "e2e4 e7e5 g1f3", vec![]),
+            node(4, Some(0), "g1f3", vec![5]),
+            node(5, Some(4), "g1f3 e7e5", vec![3]),
+        ];
+        nodes[3].other_parents.push(ParentLink {
+            parent: 5,
+            uci: UciMove::from_uci("e2e4").unwrap(),
+            play_rate: None,
+        });
+        let pgn = PgnWriter.write_with_nodes(&nodes[0], &nodes).unwrap();
+        assert_eq!(
+            movetext(&pgn),
+            "1. e2e4 (1. g1f3 e7e5 2. e2e4 {transposes to 1. e2e4 e7e5 2. g1f3}) \
+             1... e7e5 2. g1f3 *"
         );
-        let n2 = node(
-            2,
-            Some(0),
-            "fen2",
-            PieceColor::Black,
-            Some(UciMove::from_uci("d2d4").unwrap()),
-            1,
-            vec![],
+        let pgn = PgnWriter
+            .write_with_nodes_and_san(&nodes[0], &nodes, &MockSanConverter)
+            .unwrap();
+        assert_eq!(
+            movetext(&pgn),
+            "1. e4 (1. Nf3 e5 2. e4 {transposes to 1. e4 e5 2. Nf3}) 1... e5 2. Nf3 *"
+        );
+    }
+
+    #[test]
+    fn test_pending_nodes_are_marked() {
+        // 1. e4 e5, where the build stopped before expanding 1... e5.
+        let mut nodes = vec![
+            node(0, None, "", vec![1]),
+            node(1, Some(0), "e2e4", vec![2]),
+            node(2, Some(1), "e2e4 e7e5", vec![]),
+        ];
+        let pgn = PgnWriter.write_with_nodes(&nodes[0], &nodes).unwrap();
+        assert!(!pgn.contains("{not expanded}"));
+        nodes[2].signals.pending = true;
+        let pgn = PgnWriter.write_with_nodes(&nodes[0], &nodes).unwrap();
+        assert_eq!(movetext(&pgn), "1. e2e4 e7e5 {not expanded} *");
+        let pgn = PgnWriter
+            .write_with_nodes_and_san(&nodes[0], &nodes, &MockSanConverter)
+            .unwrap();
+        assert_eq!(movetext(&pgn), "1. e4 e5 {not expanded} *");
+
+        // 1. e4 only got 1... e5 before the node budget ran out.
+        nodes[1].signals.pending = true;
+        let pgn = PgnWriter.write_with_nodes(&nodes[0], &nodes).unwrap();
+        assert_eq!(
+            movetext(&pgn),
+            "1. e2e4 {not fully expanded} 1... e7e5 {not expanded} *"
         );
-        let nodes = vec![n0.clone(), n1, n2];
-        let writer = PgnWriter;
-        let pgn = writer.write_with_nodes(&n0, &nodes).unwrap();
-        assert!(pgn.contains("1. e2e4 (1. d2d4)"));
     }
 
     #[test]
     fn test_empty_tree() {
-        let n0 = node(0, None, "startpos", PieceColor::White, None, 0, vec![]);
+        let n0 = node(0, None, "", vec![]);
         let writer = PgnWriter;
-        let pgn = writer.write_with_nodes(&n0, &[n0.clone()]).unwrap();
-        assert!(pgn.contains("*") && !pgn.contains("1."));
+        let pgn = writer
+            .write_with_nodes(&n0, std::slice::from_ref(&n0))
+            .unwrap();
+        assert_eq!(movetext(&pgn), "*");
     }
 
     #[test]
     fn test_non_starting_fen() {
-        let n0 = node(
-            0,
-            None,
-            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1",
-            PieceColor::Black,
-            None,
-            0,
-            vec![],
-        );
+        let n0 = node(0, None, "e2e4", vec![]);
         let writer = PgnWriter;
-        let pgn = writer.write_with_nodes(&n0, &[n0.clone()]).unwrap();
-        assert!(pgn.contains("[FEN \"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1\"]"));
+        let pgn = writer
+            .write_with_nodes(&n0, std::slice::from_ref(&n0))
+            .unwrap();
+        assert!(pgn.contains(&format!("[FEN \"{BLACK_TO_MOVE}\"]")));
     }
 
     #[test]
     fn test_question_mark_for_missing_move() {
-        let n0 = node(0, None, "startpos", PieceColor::White, None, 0, vec![1]);
-        let n1 = node(1, Some(0), "fen1", PieceColor::Black, None, 1, vec![]);
-        let nodes = vec![n0.clone(), n1];
+        let mut nodes = vec![node(0, None, "", vec![1]), node(1, Some(0), "e2e4", vec![])];
+        nodes[1].last_move_uci = None;
         let writer = PgnWriter;
-        let pgn = writer.write_with_nodes(&n0, &nodes).unwrap();
-        assert!(pgn.contains("1. ?"));
+        let pgn = writer.write_with_nodes(&nodes[0], &nodes).unwrap();
+        assert_eq!(movetext(&pgn), "1. ? *");
     }
 }
 use crate::{
-    domain::RepertoireNode,
+    domain::{RepertoireNode, chess::UciMove},
     pgn::{RepertoireWriter, SanConverter},
 };
 
@@ -143,133 +175,173 @@ use crate::{
 pub struct PgnWriter;
 
 impl PgnWriter {
-    /// Recursively writes moves from the repertoire tree in PGN format.
-    /// Returns a tuple: (PGN move string, number of plies written)
+    /// Writes the tree under `root` in PGN, with UCI moves: each node's first child
+    /// continues the line and the others become variations.
     pub fn write_with_nodes(
         &self,
         root: &RepertoireNode,
         nodes: &[RepertoireNode],
     ) -> anyhow::Result<String> {
-        let mut pgn = String::from("[Event \"Repertoire\"]\n");
-        if root.fen_key.fen_string != "startpos" {
-            pgn += &format!("[FEN \"{}\"]\n", root.fen_key.fen_string);
-        }
-        pgn += "\n";
-        let (moves, _) = self.write_moves(root, nodes, root.ply_depth);
-        pgn += &moves;
-        pgn += " *\n";
-        Ok(pgn)
-    }
-
-    #[allow(clippy::only_used_in_recursion)]
-    fn write_moves(
-        &self,
-        node: &RepertoireNode,
-        nodes: &[RepertoireNode],
-        mut ply: u32,
-    ) -> (String, u32) {
-        let mut pgn = String::new();
-        let mut current = node;
-        while !current.children.is_empty() {
-            // Mainline: first child
-            let child_id = current.children[0];
-            let child = nodes.iter().find(|n| n.id == child_id);
-            if let Some(child) = child {
-                ply += 1;
-                // Output ply number and move
-                if current.fen_key.side_to_move.is_white() {
-                    pgn += &format!("{}.", ply.div_ceil(2));
-                }
-                if let Some(ref uci) = child.last_move_uci {
-                    pgn += &format!(" {}", uci.to_uci());
-                } else {
-                    pgn += " ?";
-                }
-                // Handle variations (other children)
-                if current.children.len() > 1 {
-                    for &var_id in &current.children[1..] {
-                        let var_node = nodes.iter().find(|n| n.id == var_id);
-                        if let Some(var_node) = var_node {
-                            let (var_pgn, _) = self.write_moves(var_node, nodes, ply);
-                            pgn += &format!(" ({})", var_pgn.trim());
-                        }
-                    }
-                }
-                current = child;
-            } else {
-                // Child not found, output ? and break
-                pgn += " ?";
-                break;
-            }
-        }
-        (pgn, ply)
+        Ok(write_pgn(root, nodes, &|uci, _| uci.to_uci()))
     }
 
+    /// Like `write_with_nodes`, with the moves converted to SAN.
     pub fn write_with_nodes_and_san<C: SanConverter>(
         &self,
         root: &RepertoireNode,
         nodes: &[RepertoireNode],
         san_converter: &C,
     ) -> anyhow::Result<String> {
-        let mut pgn = String::from("[Event \"Repertoire\"]\n");
-        if root.fen_key.fen_string != "startpos" {
-            pgn += &format!("[FEN \"{}\"]\n", root.fen_key.fen_string);
-        }
-        pgn += "\n";
-        let (moves, _) = self.write_moves_with_san(root, nodes, root.ply_depth, san_converter);
-        pgn += &moves;
-        pgn += " *\n";
-        Ok(pgn)
+        Ok(write_pgn(root, nodes, &|uci, node| {
+            san_converter.uci_to_san(uci, node.fen_key.fen_string())
+        }))
     }
+}
 
-    #[allow(clippy::only_used_in_recursion)]
-    fn write_moves_with_san<C: SanConverter>(
-        &self,
-        node: &RepertoireNode,
-        nodes: &[RepertoireNode],
-        mut ply: u32,
-        san_converter: &C,
-    ) -> (String, u32) {
-        let mut pgn = String::new();
-        let mut current = node;
-        while !current.children.is_empty() {
-            let child_id = current.children[0];
-            let child = nodes.iter().find(|n| n.id == child_id);
-            if let Some(child) = child {
-                ply += 1;
-                if current.fen_key.side_to_move.is_white() {
-                    pgn += &format!("{}.", ply.div_ceil(2));
-                }
-                if let Some(ref uci) = child.last_move_uci {
-                    let san = san_converter.uci_to_san(uci, &child.fen_key.fen_string);
-                    pgn += &format!(" {}", san);
-                } else {
-                    pgn += " ?";
-                }
-                if current.children.len() > 1 {
-                    for &var_id in &current.children[1..] {
-                        let var_node = nodes.iter().find(|n| n.id == var_id);
-                        if let Some(var_node) = var_node {
-                            let (var_pgn, _) =
-                                self.write_moves_with_san(var_node, nodes, ply, san_converter);
-                            pgn += &format!(" ({})", var_pgn.trim());
-                        }
-                    }
-                }
-                current = child;
+/// Formats a move, given the node it leads to.
+type FormatMove<'a> = dyn Fn(&UciMove, &RepertoireNode) -> String + 'a;
+
+/// Tags, then the movetext, e.g. "1. e2e4 e7e5 (1... c7c5) 2. g1f3 *".
+fn write_pgn(root: &RepertoireNode, nodes: &[RepertoireNode], format_move: &FormatMove) -> String {
+    let mut pgn = String::from("[Event \"Repertoire\"]\n");
+    if !root.fen_key.is_starting_position() {
+        pgn += &format!("[FEN \"{}\"]\n", root.fen_key.fen_string());
+    }
+    pgn += "\n";
+    let mut movetext = Vec::new();
+    write_line(root, nodes, true, format_move, &mut movetext);
+    movetext.push("*".to_string());
+    pgn += &movetext.join(" ");
+    pgn += "\n";
+    pgn
+}
+
+/// Appends the moves after `node` to `out`, one token per move number, move,
+/// variation or comment. A Black move gets its own number ("3...") at the start of a
+/// line and after a variation or comment; `number_black` says whether the first one
+/// needs it.
+fn write_line(
+    node: &RepertoireNode,
+    nodes: &[RepertoireNode],
+    mut number_black: bool,
+    format_move: &FormatMove,
+    out: &mut Vec<String>,
+) {
+    let find = |id: u64| nodes.iter().find(|n| n.id == id);
+    let mut current = node;
+    loop {
+        let Some(&child_id) = current.children.first() else {
+            // The build stopped before it got to expand this line any further.
+            if current.signals.pending {
+                out.push("{not expanded}".to_string());
+            }
+            break;
+        };
+        // Some of its moves got in before the build stopped, others are missing.
+        if current.signals.pending {
+            out.push("{not fully expanded}".to_string());
+            number_black = true;
+        }
+        let Some(child) = find(child_id) else {
+            out.push("?".to_string());
+            break;
+        };
+        push_move(current, child, number_black, format_move, out);
+        number_black = false;
+        for &var_id in &current.children[1..] {
+            let Some(var_node) = find(var_id) else {
+                continue;
+            };
+            let mut variation = Vec::new();
+            push_move(current, var_node, true, format_move, &mut variation);
+            if var_node.transposes_from(current.id) {
+                let line = line_to(var_node, nodes, format_move);
+                variation.push(format!("{{transposes to {line}}}"));
             } else {
-                pgn += " ?";
-                break;
+                write_line(var_node, nodes, false, format_move, &mut variation);
             }
+            out.push(format!("({})", variation.join(" ")));
+            number_black = true;
+        }
+        // The line goes on where the position was first reached.
+        if child.transposes_from(current.id) {
+            let line = line_to(child, nodes, format_move);
+            out.push(format!("{{transposes to {line}}}"));
+            break;
         }
-        (pgn, ply)
+        current = child;
+    }
+}
+
+/// Appends the move from `parent` to `child`, preceded by its number when White
+/// moves, or when Black does and `number_black` is set.
+fn push_move(
+    parent: &RepertoireNode,
+    child: &RepertoireNode,
+    number_black: bool,
+    format_move: &FormatMove,
+    out: &mut Vec<String>,
+) {
+    let move_number = (parent.ply_depth + 1).div_ceil(2);
+    if parent.fen_key.side_to_move().is_white() {
+        out.push(format!("{move_number}."));
+    } else if number_black {
+        out.push(format!("{move_number}..."));
     }
+    out.push(match edge_move(parent, child) {
+        Some(uci) => format_move(uci, child),
+        None => "?".to_string(),
+    });
+}
+
+/// The move from `parent` to `child`; a child that doesn't list `parent` falls back
+/// to the move it was first reached by.
+fn edge_move<'a>(parent: &RepertoireNode, child: &'a RepertoireNode) -> Option<&'a UciMove> {
+    child.move_from(parent.id).or(child.last_move_uci.as_ref())
+}
+
+/// The moves from the root to `target` through first parents, numbered like the
+/// movetext, e.g. "1. e2e4 e7e5 2. g1f3". Names where a transposed line continues.
+fn line_to(target: &RepertoireNode, nodes: &[RepertoireNode], format_move: &FormatMove) -> String {
+    if target.is_root() {
+        return "the starting position".to_string();
+    }
+    let mut path = vec![target];
+    while let Some(parent_id) = path[path.len() - 1].parent {
+        match nodes.iter().find(|n| n.id == parent_id) {
+            Some(parent) if !parent.is_root() => path.push(parent),
+            _ => break,
+        }
+    }
+    let mut line = Vec::new();
+    for (i, node) in path.iter().rev().enumerate() {
+        let move_number = node.ply_depth.div_ceil(2);
+        // The side to move after the move is the other side.
+        if !node.fen_key.side_to_move().is_white() {
+            line.push(format!("{move_number}."));
+        } else if i == 0 {
+            line.push(format!("{move_number}..."));
+        }
+        line.push(match &node.last_move_uci {
+            Some(uci) => format_move(uci, node),
+            None => "?".to_string(),
+        });
+    }
+    line.join(" ")
 }
 
 impl RepertoireWriter for PgnWriter {
     /// Writes the repertoire tree to PGN format using only the root node (legacy interface).
     /// For full traversal, use write_with_nodes.
     fn write(&self, root: &RepertoireNode) -> anyhow::Result<String> {
-        self.write_with_nodes(root, &[root.clone()])
+        self.write_with_nodes(root, std::slice::from_ref(root))
+    }
+
+    fn write_tree(
+        &self,
+        root: &RepertoireNode,
+        nodes: &[RepertoireNode],
+    ) -> anyhow::Result<Vec<u8>> {
+        Ok(self.write_with_nodes(root, nodes)?.into_bytes())
     }
 }
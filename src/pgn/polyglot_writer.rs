@@ -0,0 +1,333 @@
+//! Exports the repertoire tree as a Polyglot opening book (`.bin`).
+//!
+//! Every edge of the tree becomes one 16-byte entry keyed by the Zobrist hash of
+//! the position it is played from. Moves for our side get `MY_MOVE_WEIGHT`, so a
+//! GUI or bot always plays them; opponent moves are weighted by their play rate
+//! so a book-driven sparring partner answers the way real opponents do.
+
+use anyhow::{Context, Result, anyhow};
+use shakmaty::{Chess, Position, uci::Uci};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{
+    domain::{PieceColor, RepertoireNode},
+    pgn::RepertoireWriter,
+    provider::polyglot::{PolyglotEntry, encode_move, polyglot_key},
+};
+
+/// Weight of every move we play ourselves.
+pub const MY_MOVE_WEIGHT: u16 = u16::MAX;
+/// Opponent weights are the play rate scaled to this, so 100% is 10000.
+const PLAY_RATE_SCALE: f32 = 10_000.0;
+
+#[derive(Debug, Clone)]
+pub struct PolyglotWriter {
+    my_side: PieceColor,
+}
+
+impl PolyglotWriter {
+    pub fn new(my_side: PieceColor) -> Self {
+        Self { my_side }
+    }
+
+    /// Book weight for the move `mover` plays from `parent` into `child`. Opponent
+    /// moves without a play rate, or too rare to register, still get weight 1 so they
+    /// stay in the book.
+    fn weight(&self, mover: shakmaty::Color, parent: u64, child: &RepertoireNode) -> u16 {
+        if mover == self.my_side.to_shakmaty() {
+            return MY_MOVE_WEIGHT;
+        }
+        let scaled = child
+            .play_rate_from(parent)
+            .map_or(0.0, |rate| (rate.value() * PLAY_RATE_SCALE).round());
+        (scaled as u16).max(1)
+    }
+
+    /// Book entries for the tree under `root`, sorted by key and then by
+    /// descending weight. A move reached through several move orders is stored once.
+    pub fn entries(
+        &self,
+        root: &RepertoireNode,
+        nodes: &[RepertoireNode],
+    ) -> Result<Vec<PolyglotEntry>> {
+        let by_id: HashMap<u64, &RepertoireNode> = nodes.iter().map(|n| (n.id, n)).collect();
+        let mut weights: BTreeMap<(u64, u16), u16> = BTreeMap::new();
+        let mut stack: Vec<(&RepertoireNode, Chess)> = vec![(root, root.fen_key.position())];
+        let mut visited = HashSet::from([root.id]);
+
+        while let Some((node, pos)) = stack.pop() {
+            let key = polyglot_key(&pos);
+            for child_id in &node.children {
+                let Some(&child) = by_id.get(child_id) else {
+                    continue;
+                };
+                let uci = child
+                    .move_from(node.id)
+                    .or(child.last_move_uci.as_ref())
+                    .ok_or_else(|| anyhow!("node {} has no move", child.id))?
+                    .to_uci();
+                let mv = uci
+                    .parse::<Uci>()?
+                    .to_move(&pos)
+                    .with_context(|| format!("illegal move {uci} in node {}", child.id))?;
+                let weight = weights.entry((key, encode_move(&mv))).or_default();
+                *weight = (*weight).max(self.weight(pos.turn(), node.id, child));
+
+                // A transposed position is walked once, which also ends repetition cycles.
+                if visited.insert(child.id) {
+                    let mut next = pos.clone();
+                    next.play_unchecked(&mv);
+                    stack.push((child, next));
+                }
+            }
+        }
+
+        let mut entries: Vec<PolyglotEntry> = weights
+            .into_iter()
+            .map(|((key, mv), weight)| PolyglotEntry {
+                key,
+                mv,
+                weight,
+                learn: 0,
+            })
+            .collect();
+        entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.weight.cmp(&a.weight)));
+        Ok(entries)
+    }
+}
+
+impl RepertoireWriter for PolyglotWriter {
+    fn write_tree(&self, root: &RepertoireNode, nodes: &[RepertoireNode]) -> Result<Vec<u8>> {
+        Ok(self
+            .entries(root, nodes)?
+            .iter()
+            .flat_map(PolyglotEntry::to_bytes)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::{FenKey, ParentLink, PlayRate, Signals, chess::UciMove},
+        provider::polyglot::PolyglotBook,
+    };
+
+    /// Builds a tree node by node, deriving each FEN by playing the move.
+    struct Tree {
+        nodes: Vec<RepertoireNode>,
+        positions: Vec<Chess>,
+    }
+
+    impl Tree {
+        fn new(fen: &str) -> Self {
+            let fen_key = FenKey::parse(fen).unwrap();
+            let pos = fen_key.position();
+            let root = RepertoireNode {
+                id: 0,
+                parent: None,
+                fen_key,
+                last_move_uci: None,
+                ply_depth: 0,
+                children: vec![],
+                signals: Signals::default(),
+                other_parents: vec![],
+            };
+            Self {
+                nodes: vec![root],
+                positions: vec![pos],
+            }
+        }
+
+        fn add(&mut self, parent: u64, uci: &str, play_rate: Option<f32>) -> u64 {
+            let id = self.nodes.len() as u64;
+            let mut pos = self.positions[parent as usize].clone();
+            let mv = uci.parse::<Uci>().unwrap().to_move(&pos).unwrap();
+            pos.play_unchecked(&mv);
+            self.nodes.push(RepertoireNode {
+                id,
+                parent: Some(parent),
+                fen_key: FenKey::from_position(&pos),
+                last_move_uci: Some(UciMove::from_uci(uci).unwrap()),
+                ply_depth: self.nodes[parent as usize].ply_depth + 1,
+                children: vec![],
+                signals: Signals {
+                    play_rate: play_rate.map(PlayRate::new),
+                    ..Default::default()
+                },
+                other_parents: vec![],
+            });
+            self.positions.push(pos);
+            self.nodes[parent as usize].children.push(id);
+            id
+        }
+
+        /// Links `parent` to the existing node `target` by transposition.
+        fn link(&mut self, parent: u64, uci: &str, play_rate: Option<f32>, target: u64) {
+            self.nodes[target as usize].other_parents.push(ParentLink {
+                parent,
+                uci: UciMove::from_uci(uci).unwrap(),
+                play_rate: play_rate.map(PlayRate::new),
+            });
+            self.nodes[parent as usize].children.push(target);
+        }
+
+        fn fen_key(&self, id: u64) -> &FenKey {
+            &self.nodes[id as usize].fen_key
+        }
+    }
+
+    fn moves(book: &PolyglotBook, fen: &FenKey) -> Vec<(String, u16)> {
+        book.weighted_moves(fen)
+            .unwrap()
+            .into_iter()
+            .map(|(uci, weight, _)| (uci.to_uci(), weight))
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip_with_reader() {
+        // 1. e4 (e5 | c5 | rare a6) 2. Nf3
+        let mut tree = Tree::new("startpos");
+        let e4 = tree.add(0, "e2e4", None);
+        let e5 = tree.add(e4, "e7e5", Some(0.55));
+        tree.add(e4, "c7c5", Some(0.3));
+        tree.add(e4, "a7a6", Some(0.00001));
+        tree.add(e5, "g1f3", None);
+
+        let writer = PolyglotWriter::new(PieceColor::White);
+        let bytes = writer.write_tree(&tree.nodes[0], &tree.nodes).unwrap();
+        assert_eq!(bytes.len(), 5 * 16);
+        let book = PolyglotBook::from_bytes(&bytes).unwrap();
+
+        assert_eq!(
+            moves(&book, &FenKey::starting_position()),
+            vec![("e2e4".to_string(), MY_MOVE_WEIGHT)]
+        );
+        assert_eq!(
+            moves(&book, tree.fen_key(e4)),
+            vec![
+                ("e7e5".to_string(), 5500),
+                ("c7c5".to_string(), 3000),
+                ("a7a6".to_string(), 1),
+            ]
+        );
+        assert_eq!(
+            moves(&book, tree.fen_key(e5)),
+            vec![("g1f3".to_string(), MY_MOVE_WEIGHT)]
+        );
+    }
+
+    #[test]
+    fn test_castling_and_promotion_round_trip() {
+        let mut tree = Tree::new("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        let castle = tree.add(0, "e1c1", None);
+        tree.add(castle, "e8g8", Some(1.0));
+        tree.add(0, "b7a8q", None);
+        tree.add(0, "b7b8n", None);
+
+        let writer = PolyglotWriter::new(PieceColor::White);
+        let entries = writer.entries(&tree.nodes[0], &tree.nodes).unwrap();
+        // Polyglot castling is king-takes-rook: e1a1 is from e1 (4, 0) to a1 (0, 0).
+        assert!(entries.iter().any(|e| e.mv == 4 << 6));
+        // Promotion piece in bits 12-14, queen = 4, knight = 1.
+        assert!(entries.iter().any(|e| e.mv >> 12 == 4));
+        assert!(entries.iter().any(|e| e.mv >> 12 == 1));
+
+        let bytes = writer.write_tree(&tree.nodes[0], &tree.nodes).unwrap();
+        let book = PolyglotBook::from_bytes(&bytes).unwrap();
+        let mut root_moves: Vec<String> = moves(&book, tree.fen_key(0))
+            .into_iter()
+            .map(|(uci, _)| uci)
+            .collect();
+        root_moves.sort();
+        assert_eq!(root_moves, vec!["b7a8q", "b7b8n", "e1c1"]);
+        assert_eq!(
+            moves(&book, tree.fen_key(castle)),
+            vec![("e8g8".to_string(), 10_000)]
+        );
+    }
+
+    #[test]
+    fn test_entries_sorted_and_transpositions_merged() {
+        // 1. d4 d5 2. Nf3 and 1. Nf3 d5 2. d4 reach the same position.
+        let mut tree = Tree::new("startpos");
+        let d4 = tree.add(0, "d2d4", None);
+        let d4d5 = tree.add(d4, "d7d5", Some(0.5));
+        let a = tree.add(d4d5, "g1f3", None);
+        let nf3 = tree.add(0, "g1f3", None);
+        let nf3d5 = tree.add(nf3, "d7d5", Some(0.4));
+        let b = tree.add(nf3d5, "d2d4", None);
+        tree.add(a, "g8f6", Some(0.2));
+        tree.add(b, "g8f6", Some(0.6));
+
+        let writer = PolyglotWriter::new(PieceColor::White);
+        let entries = writer.entries(&tree.nodes[0], &tree.nodes).unwrap();
+        assert!(entries.windows(2).all(|w| w[0].key <= w[1].key));
+        assert_eq!(entries.len(), 7);
+
+        let book =
+            PolyglotBook::from_bytes(&writer.write_tree(&tree.nodes[0], &tree.nodes).unwrap())
+                .unwrap();
+        assert_eq!(
+            moves(&book, tree.fen_key(a)),
+            vec![("g8f6".to_string(), 6000)]
+        );
+    }
+
+    #[test]
+    fn test_linked_transpositions_and_cycles() {
+        // 1. d4 d5 2. Nf3 Nf6, with 1. Nf3 d5 2. d4 linked into it, and
+        // 1. Nf3 Nf6 2. Ng1 Ng8 back to the start.
+        let mut tree = Tree::new("startpos");
+        let d4 = tree.add(0, "d2d4", None);
+        let d4d5 = tree.add(d4, "d7d5", Some(0.5));
+        let a = tree.add(d4d5, "g1f3", None);
+        tree.add(a, "g8f6", Some(0.2));
+        let nf3 = tree.add(0, "g1f3", None);
+        let nf3d5 = tree.add(nf3, "d7d5", Some(0.4));
+        tree.link(nf3d5, "d2d4", None, a);
+        let nf6 = tree.add(nf3, "g8f6", Some(0.3));
+        let ng1 = tree.add(nf6, "f3g1", None);
+        tree.link(ng1, "f6g8", Some(0.9), 0);
+
+        let writer = PolyglotWriter::new(PieceColor::White);
+        let entries = writer.entries(&tree.nodes[0], &tree.nodes).unwrap();
+        assert_eq!(entries.len(), 10);
+        let book =
+            PolyglotBook::from_bytes(&writer.write_tree(&tree.nodes[0], &tree.nodes).unwrap())
+                .unwrap();
+        assert_eq!(
+            moves(&book, tree.fen_key(nf3d5)),
+            vec![("d2d4".to_string(), MY_MOVE_WEIGHT)]
+        );
+        assert_eq!(moves(&book, tree.fen_key(ng1)).len(), 1);
+    }
+
+    #[test]
+    fn test_opponent_transposition_keeps_its_play_rate() {
+        // 1. d4 Nf6 2. c4 e6, and 1. d4 e6 2. c4 Nf6 linked into it.
+        let mut tree = Tree::new("startpos");
+        let d4 = tree.add(0, "d2d4", None);
+        let nf6 = tree.add(d4, "g8f6", Some(0.5));
+        let c4 = tree.add(nf6, "c2c4", None);
+        let e6 = tree.add(c4, "e7e6", Some(0.3));
+        let d4e6 = tree.add(d4, "e7e6", Some(0.2));
+        let c4b = tree.add(d4e6, "c2c4", None);
+        tree.link(c4b, "g8f6", Some(0.6), e6);
+
+        let writer = PolyglotWriter::new(PieceColor::White);
+        let book =
+            PolyglotBook::from_bytes(&writer.write_tree(&tree.nodes[0], &tree.nodes).unwrap())
+                .unwrap();
+        assert_eq!(
+            moves(&book, tree.fen_key(c4)),
+            vec![("e7e6".to_string(), 3000)]
+        );
+        assert_eq!(
+            moves(&book, tree.fen_key(c4b)),
+            vec![("g8f6".to_string(), 6000)]
+        );
+    }
+}
@@ -1,7 +1,14 @@
 use crate::domain::RepertoireNode;
-use anyhow::Result;
+use anyhow::{Context, Result};
 
 /// Writer interface for alternate outputs later (JSON, DB, etc.)
 pub trait RepertoireWriter {
-    fn write(&self, root: &RepertoireNode) -> Result<String>;
+    /// Serialize the tree under `root`, looking children up in `nodes`.
+    fn write_tree(&self, root: &RepertoireNode, nodes: &[RepertoireNode]) -> Result<Vec<u8>>;
+
+    /// Text form of the root alone; fails for binary formats.
+    fn write(&self, root: &RepertoireNode) -> Result<String> {
+        String::from_utf8(self.write_tree(root, std::slice::from_ref(root))?)
+            .context("output format is binary; use write_tree")
+    }
 }
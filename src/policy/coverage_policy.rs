@@ -0,0 +1,138 @@
+use shakmaty::Color;
+use std::cmp::Ordering;
+
+use crate::{
+    domain::{CandidateMove, CandidateRequest, RepertoireNode},
+    policy::{Decision, MovePolicy},
+    provider::types::CandidateMoves,
+};
+
+/// Wraps a policy and replaces its opponent-side selection with probability coverage.
+///
+/// Opponent replies are taken most popular first until they cover `coverage` of the
+/// play-rate mass, with no fixed child cap. A reply is dropped, ending the line, when
+/// the chance of reaching it (`req.reach` times its play rate) is below `min_reach`.
+/// My-side nodes are left to the inner policy.
+pub struct CoveragePolicy<P> {
+    inner: P,
+    coverage: f32,
+    min_reach: f32,
+}
+
+impl<P: MovePolicy> CoveragePolicy<P> {
+    pub fn new(inner: P, coverage: f32, min_reach: f32) -> Self {
+        Self {
+            inner,
+            coverage,
+            min_reach,
+        }
+    }
+}
+
+impl<P: MovePolicy> MovePolicy for CoveragePolicy<P> {
+    fn decide(&self, stm: Color) -> Decision {
+        self.inner.decide(stm)
+    }
+    fn is_my_side(&self, stm: Color) -> bool {
+        self.inner.is_my_side(stm)
+    }
+    fn priority(&self, node: &RepertoireNode) -> f32 {
+        self.inner.priority(node)
+    }
+    fn adjust(&self, req: &mut CandidateRequest, is_my_side: bool) {
+        self.inner.adjust(req, is_my_side);
+        if !is_my_side {
+            // Coverage decides how many replies to keep.
+            req.max_candidates = usize::MAX;
+        }
+    }
+
+    fn post_filter(
+        &self,
+        req: &CandidateRequest,
+        is_my_side: bool,
+        mut cands: CandidateMoves,
+    ) -> CandidateMoves {
+        if is_my_side {
+            return self.inner.post_filter(req, is_my_side, cands);
+        }
+        let rate = |c: &CandidateMove| c.signals.play_rate.map(|r| r.value());
+        cands.retain(|c| rate(c).is_some());
+        cands.sort_by(|a, b| {
+            rate(b)
+                .partial_cmp(&rate(a))
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.uci.to_uci().cmp(&b.uci.to_uci()))
+        });
+
+        let mut covered = 0.0;
+        let mut kept = Vec::new();
+        for c in cands {
+            let p = rate(&c).unwrap_or(0.0);
+            if covered >= self.coverage || req.reach * p < self.min_reach {
+                break;
+            }
+            covered += p;
+            kept.push(c);
+        }
+        kept
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::{Centipawns, PlayRate, candidate_request::CandidateRequestBuilder},
+        policy::SideSplitPolicy,
+        test_support::{black_to_move, cand},
+    };
+
+    fn policy() -> CoveragePolicy<SideSplitPolicy> {
+        let inner =
+            SideSplitPolicy::new(Color::White, Centipawns::from_int(50), PlayRate::new(0.05));
+        CoveragePolicy::new(inner, 0.9, 0.02)
+    }
+
+    fn select(policy: &CoveragePolicy<SideSplitPolicy>, reach: f32) -> Vec<String> {
+        let fen = black_to_move();
+        let mut req = CandidateRequestBuilder::default()
+            .fen_key(fen)
+            .max_candidates(3)
+            .reach(reach)
+            .build()
+            .unwrap();
+        policy.adjust(&mut req, false);
+        assert_eq!(req.max_candidates, usize::MAX);
+        let cands = vec![
+            cand("d7d5", None, Some(0.08)),
+            cand("c7c5", None, Some(0.35)),
+            cand("e7e5", None, Some(0.30)),
+            cand("e7e6", None, Some(0.12)),
+            cand("c7c6", None, Some(0.10)),
+            cand("g7g6", None, Some(0.05)),
+            cand("a7a6", None, None),
+        ];
+        policy
+            .post_filter(&req, false, cands)
+            .iter()
+            .map(|c| c.uci.to_uci())
+            .collect()
+    }
+
+    #[test]
+    fn test_expands_replies_until_mass_is_covered() {
+        // 35 + 30 + 12 + 10 = 87%, so d5 is needed to pass 90%; g6 is not.
+        assert_eq!(
+            select(&policy(), 1.0),
+            vec!["c7c5", "e7e5", "e7e6", "c7c6", "d7d5"]
+        );
+    }
+
+    #[test]
+    fn test_prunes_replies_below_min_reach() {
+        // At 15% reach, only replies played more than 13.3% of the time stay above 2%.
+        assert_eq!(select(&policy(), 0.15), vec!["c7c5", "e7e5"]);
+        assert!(select(&policy(), 0.01).is_empty());
+    }
+}
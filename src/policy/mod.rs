@@ -1,12 +1,15 @@
+pub mod coverage_policy;
 pub mod decision;
 pub mod split_side_policy;
 
+pub use coverage_policy::CoveragePolicy;
 pub use decision::Decision;
 pub use split_side_policy::SideSplitPolicy;
 
-use std::cmp::Ordering;
+use std::{cmp::Ordering, sync::Arc};
 
-use crate::domain::{CandidateRequest, Centipawns, PlayRate};
+use crate::config::PolicyConfig;
+use crate::domain::{CandidateMove, CandidateRequest, PieceColor, PlayRate, RepertoireNode};
 use crate::provider::types::CandidateMoves;
 use shakmaty::Color;
 
@@ -15,33 +18,298 @@ pub trait MovePolicy: Send + Sync {
     /// Decide role (attacker/defender) for current side to move.
     fn decide(&self, stm: Color) -> Decision;
 
+    /// True if `stm` is the side this policy builds the repertoire for. Defaults to
+    /// treating quality nodes as ours.
+    fn is_my_side(&self, stm: Color) -> bool {
+        matches!(self.decide(stm), Decision::Quality)
+    }
+
+    /// Expansion priority of a newly created node for priority work queues; higher
+    /// goes first. Defaults to the node's reach probability.
+    fn priority(&self, node: &RepertoireNode) -> f32 {
+        node.signals.reach.unwrap_or(1.0)
+    }
+
     /// Adjust request (e.g. multipv) based on role and side.
     /// is_my_side is true if stm matches the side this policy is for.
     fn adjust(&self, req: &mut CandidateRequest, is_my_side: bool);
 
     /// Post-filter candidate moves (e.g. sort, trim) before returning to orchestrator.
     /// Candidates have signals from all providers merged in, so can be sorted/filtered.
-    /// Default implementation sorts by eval_cp desc, then play_rate desc, then UCI asc
-    fn post_filter(&self, mut cands: CandidateMoves) -> CandidateMoves {
-        // Stable ordering: primary → secondary → UCI for determinism
-        cands.sort_by(|a, b| {
-            let pa_eval = a.signals.eval_cp.unwrap_or(Centipawns::from_int(-10000));
-            let pb_eval = b.signals.eval_cp.unwrap_or(Centipawns::from_int(-10000));
-
-            match pa_eval.partial_cmp(&pb_eval).unwrap_or(Ordering::Equal) {
-                Ordering::Equal => {
-                    let pa_play = a.signals.play_rate.unwrap_or(PlayRate::new(-1.0));
-                    let pb_play = b.signals.play_rate.unwrap_or(PlayRate::new(-1.0));
-                    let str_a = format!("{}{}", a.uci.from.to_coords(), a.uci.to.to_coords());
-                    let str_b = format!("{}{}", b.uci.from.to_coords(), b.uci.to.to_coords());
-                    match pa_play.compare(&pb_play) {
-                        Ordering::Equal => str_a.cmp(&str_b),
-                        other => other,
-                    }
+    /// `req` is the request as shaped by `adjust`.
+    /// Default implementation sorts with `sort_candidates` for the side to move, then
+    /// drops moves outside the request's windows with `apply_window`. At hybrid nodes
+    /// on my side, the survivors are then ranked with `sort_practical`.
+    fn post_filter(
+        &self,
+        req: &CandidateRequest,
+        is_my_side: bool,
+        cands: CandidateMoves,
+    ) -> CandidateMoves {
+        let hybrid = self.decide(req.fen_key.side_to_move().to_shakmaty()) == Decision::Hybrid;
+        sort_and_window(req, is_my_side, hybrid, cands, |cands| {
+            apply_window(req, is_my_side, cands)
+        })
+    }
+}
+
+/// The shape of every `post_filter`: sort with `sort_candidates` for the side to move,
+/// keep what `window` lets through, then rank the survivors with `sort_practical` at
+/// hybrid nodes on my side.
+pub fn sort_and_window(
+    req: &CandidateRequest,
+    is_my_side: bool,
+    hybrid: bool,
+    cands: CandidateMoves,
+    window: impl FnOnce(CandidateMoves) -> CandidateMoves,
+) -> CandidateMoves {
+    let stm = req.fen_key.side_to_move().to_shakmaty();
+    let cands = window(sort_candidates(stm, cands));
+    if is_my_side && hybrid {
+        sort_practical(cands)
+    } else {
+        cands
+    }
+}
+
+/// Build the policy selected by `cfg.mode` for `my_side`.
+pub fn build_policy(cfg: &PolicyConfig, my_side: Color) -> anyhow::Result<Arc<dyn MovePolicy>> {
+    let mut split = SideSplitPolicy::new(my_side, cfg.cp_window, cfg.min_play_rate);
+    if let Some(max_loss) = cfg.max_score_loss {
+        split = split.with_score_window(max_loss, cfg.win_model());
+    }
+    if cfg.hybrid_my_side {
+        split = split.with_hybrid_my_side();
+    }
+    match cfg.mode.as_deref().unwrap_or("side_split") {
+        "side_split" => Ok(Arc::new(split)),
+        "coverage" => Ok(Arc::new(CoveragePolicy::new(
+            split,
+            cfg.coverage(),
+            cfg.min_reach(),
+        ))),
+        other => anyhow::bail!("unknown policy mode: {other}"),
+    }
+}
+
+/// Drops candidates outside the request's windows. Expects `cands` sorted best first.
+///
+/// On my side, keeps moves whose eval is within `cp_window` of the best move's from
+/// the mover's perspective (mates count as `Eval::cp_equivalent`); unevaluated moves
+/// are dropped unless nothing was evaluated. On the opponent's side, drops moves
+/// played less often than `min_play_rate`; moves without a play rate are kept.
+pub fn apply_window(
+    req: &CandidateRequest,
+    is_my_side: bool,
+    mut cands: CandidateMoves,
+) -> CandidateMoves {
+    if !is_my_side {
+        let floor = req.min_play_rate.value();
+        cands.retain(|c| c.signals.play_rate.is_none_or(|rate| rate.value() >= floor));
+        return cands;
+    }
+    let side = req.fen_key.side_to_move();
+    let Some(best) = cands.first().and_then(|c| c.signals.eval) else {
+        return cands;
+    };
+    let best_cp = i64::from(best.for_side(side).cp_equivalent());
+    let window = req.cp_window.value() as i64;
+    cands.retain(|c| {
+        c.signals
+            .eval
+            .is_some_and(|e| best_cp - i64::from(e.for_side(side).cp_equivalent()) <= window)
+    });
+    cands
+}
+
+/// Sorts candidates best first for the mover: eval desc from `stm`'s perspective,
+/// unevaluated moves last, then play_rate desc, then UCI asc.
+pub fn sort_candidates(stm: Color, mut cands: CandidateMoves) -> CandidateMoves {
+    let side = PieceColor::from_shakmaty(stm);
+    // Stable ordering: primary → secondary → UCI for determinism
+    cands.sort_by(|a, b| {
+        let pa_eval = a.signals.eval.map(|e| e.for_side(side));
+        let pb_eval = b.signals.eval.map(|e| e.for_side(side));
+
+        match pb_eval.cmp(&pa_eval) {
+            Ordering::Equal => {
+                let pa_play = a.signals.play_rate.unwrap_or(PlayRate::new(-1.0));
+                let pb_play = b.signals.play_rate.unwrap_or(PlayRate::new(-1.0));
+                let str_a = format!("{}{}", a.uci.from.to_coords(), a.uci.to.to_coords());
+                let str_b = format!("{}{}", b.uci.from.to_coords(), b.uci.to.to_coords());
+                match pb_play.compare(&pa_play) {
+                    Ordering::Equal => str_a.cmp(&str_b),
+                    other => other,
                 }
-                other => other,
             }
-        });
-        cands
+            other => other,
+        }
+    });
+    cands
+}
+
+/// Ranks candidates by how well they score in practice: expected score from game
+/// results desc, then play_rate desc; moves without game data go last. The sort is
+/// stable, so ties keep their previous (engine) order.
+pub fn sort_practical(mut cands: CandidateMoves) -> CandidateMoves {
+    cands.sort_by(|a, b| {
+        let score = |c: &CandidateMove| c.signals.expected_score.unwrap_or(-1.0);
+        let play = |c: &CandidateMove| c.signals.play_rate.unwrap_or(PlayRate::new(-1.0));
+        score(b)
+            .total_cmp(&score(a))
+            .then_with(|| play(b).compare(&play(a)))
+    });
+    cands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::{Centipawns, Eval, FenKey, candidate_request::CandidateRequestBuilder},
+        test_support::{BLACK_TO_MOVE, cand},
+    };
+
+    fn order(stm: Color, cands: CandidateMoves) -> Vec<String> {
+        uci_list(sort_candidates(stm, cands))
+    }
+
+    fn uci_list(cands: CandidateMoves) -> Vec<String> {
+        cands.iter().map(|c| c.uci.to_uci()).collect()
+    }
+
+    fn request(fen: &str, cp_window: i32, min_play_rate: f32) -> CandidateRequest {
+        CandidateRequestBuilder::default()
+            .fen_key(FenKey::parse(fen).unwrap())
+            .cp_window(Centipawns::from_int(cp_window))
+            .min_play_rate(PlayRate::new(min_play_rate))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_sort_best_first_for_the_mover() {
+        let cands = || {
+            vec![
+                cand("a2a3", Some(Eval::Cp(20)), None),
+                cand("b2b3", None, None),
+                cand("c2c3", Some(Eval::Mate(-3)), None),
+                cand("d2d3", Some(Eval::Cp(3000)), None),
+                cand("e2e3", Some(Eval::Mate(2)), None),
+            ]
+        };
+        assert_eq!(
+            order(Color::White, cands()),
+            vec!["e2e3", "d2d3", "a2a3", "c2c3", "b2b3"]
+        );
+        assert_eq!(
+            order(Color::Black, cands()),
+            vec!["c2c3", "a2a3", "d2d3", "e2e3", "b2b3"]
+        );
+    }
+
+    #[test]
+    fn test_sort_breaks_ties_by_play_rate() {
+        let cands = vec![
+            cand("a7a6", None, Some(0.1)),
+            cand("c7c5", None, Some(0.5)),
+            cand("e7e5", None, Some(0.3)),
+        ];
+        assert_eq!(order(Color::Black, cands), vec!["c7c5", "e7e5", "a7a6"]);
+    }
+
+    #[test]
+    fn test_cp_window_keeps_moves_near_the_best() {
+        let policy =
+            SideSplitPolicy::new(Color::Black, Centipawns::from_int(50), PlayRate::new(0.05));
+        // Black to move: -40 is best, -10 within 50cp, +30 outside, unevaluated dropped.
+        let req = request(BLACK_TO_MOVE, 50, 0.05);
+        let cands = vec![
+            cand("e7e5", Some(Eval::Cp(-10)), None),
+            cand("c7c5", Some(Eval::Cp(-40)), None),
+            cand("a7a6", Some(Eval::Cp(30)), None),
+            cand("h7h5", None, None),
+        ];
+        assert_eq!(
+            uci_list(policy.post_filter(&req, true, cands)),
+            vec!["c7c5", "e7e5"]
+        );
+    }
+
+    #[test]
+    fn test_cp_window_keeps_only_mates_when_mating() {
+        let req = request(BLACK_TO_MOVE, 50, 0.05);
+        let cands = vec![
+            cand("e7e5", Some(Eval::Cp(-900)), None),
+            cand("d8h4", Some(Eval::Mate(-1)), None),
+            cand("d8g5", Some(Eval::Mate(-3)), None),
+        ];
+        assert_eq!(
+            uci_list(apply_window(
+                &req,
+                true,
+                sort_candidates(Color::Black, cands)
+            )),
+            vec!["d8h4", "d8g5"]
+        );
+    }
+
+    #[test]
+    fn test_play_rate_floor_for_opponent() {
+        let req = request(BLACK_TO_MOVE, 50, 0.05);
+        let cands = vec![
+            cand("e7e5", None, Some(0.6)),
+            cand("a7a6", None, Some(0.01)),
+            cand("c7c5", None, Some(0.05)),
+            cand("h7h5", None, None),
+        ];
+        assert_eq!(
+            uci_list(apply_window(&req, false, cands)),
+            vec!["e7e5", "c7c5", "h7h5"]
+        );
+    }
+
+    #[test]
+    fn test_hybrid_picks_most_practical_engine_acceptable_move() {
+        let policy =
+            SideSplitPolicy::new(Color::Black, Centipawns::from_int(50), PlayRate::new(0.05))
+                .with_hybrid_my_side();
+        let mut req = request(BLACK_TO_MOVE, 0, 0.0);
+        policy.adjust(&mut req, true);
+        let with_games = |uci, cp, score: f32, rate| {
+            let mut c = cand(uci, Some(Eval::Cp(cp)), Some(rate));
+            c.signals.expected_score = Some(score);
+            c
+        };
+        let cands = vec![
+            with_games("c7c5", -30, 0.48, 0.3),
+            with_games("e7e5", -20, 0.45, 0.5),
+            // Scores best in practice but is outside the engine window.
+            with_games("a7a6", 60, 0.60, 0.1),
+            cand("d7d6", Some(Eval::Cp(0)), None),
+        ];
+        assert_eq!(
+            uci_list(policy.post_filter(&req, true, cands)),
+            vec!["c7c5", "e7e5", "d7d6"]
+        );
+    }
+
+    #[test]
+    fn test_build_policy_by_mode() {
+        let cfg = |mode: &str| {
+            PolicyConfig::builder()
+                .my_side(None)
+                .cp_window(Centipawns::from_int(50))
+                .min_play_rate(PlayRate::new(0.05))
+                .mode(Some(mode.to_string()))
+                .build()
+                .unwrap()
+        };
+        let policy = build_policy(&cfg("coverage"), Color::White).unwrap();
+        let mut req = request(BLACK_TO_MOVE, 0, 0.0);
+        policy.adjust(&mut req, false);
+        assert_eq!(req.max_candidates, usize::MAX);
+        assert!(build_policy(&cfg("side_split"), Color::White).is_ok());
+        assert!(build_policy(&cfg("greedy"), Color::White).is_err());
     }
 }
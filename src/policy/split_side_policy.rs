@@ -1,15 +1,19 @@
 use shakmaty::Color;
 
 use crate::{
-    domain::{CandidateRequest, Centipawns, PlayRate},
-    policy::{Decision, MovePolicy},
+    domain::{CandidateRequest, Centipawns, PieceColor, PlayRate, WinModel},
+    policy::{Decision, MovePolicy, apply_window, sort_and_window},
+    provider::types::CandidateMoves,
 };
 
-/// Default: my side → quality (engine); opponent → popularity (explorer)
+/// Default: my side → quality (engine), or hybrid when enabled; opponent → popularity (explorer)
 pub struct SideSplitPolicy {
     my_side: Color,
     cp_window: Centipawns,
     min_play_rate: PlayRate,
+    max_score_loss: Option<f32>,
+    win_model: WinModel,
+    hybrid_my_side: bool,
 }
 
 impl SideSplitPolicy {
@@ -18,18 +22,62 @@ impl SideSplitPolicy {
             my_side,
             cp_window,
             min_play_rate,
+            max_score_loss: None,
+            win_model: WinModel::default(),
+            hybrid_my_side: false,
         }
     }
+
+    /// Keep only my-side moves whose expected score under `win_model` is within
+    /// `max_score_loss` of the best move's.
+    pub fn with_score_window(mut self, max_score_loss: f32, win_model: WinModel) -> Self {
+        self.max_score_loss = Some(max_score_loss);
+        self.win_model = win_model;
+        self
+    }
+
+    /// Expand my-side nodes with both engine and explorer data, choosing the most
+    /// practical of the engine-acceptable moves.
+    pub fn with_hybrid_my_side(mut self) -> Self {
+        self.hybrid_my_side = true;
+        self
+    }
+
+    /// Drops sorted candidates that give up more than `max_loss` expected score
+    /// against the first one. Unevaluated moves are dropped too unless nothing was
+    /// evaluated.
+    fn score_window(
+        &self,
+        stm: PieceColor,
+        max_loss: f32,
+        mut cands: CandidateMoves,
+    ) -> CandidateMoves {
+        let Some(best) = cands.first().and_then(|c| c.signals.eval) else {
+            return cands;
+        };
+        let best_score = self.win_model.expected_score_for(best, stm);
+        cands.retain(|c| {
+            c.signals
+                .eval
+                .is_some_and(|e| best_score - self.win_model.expected_score_for(e, stm) <= max_loss)
+        });
+        cands
+    }
 }
 
 impl MovePolicy for SideSplitPolicy {
     fn decide(&self, stm: Color) -> Decision {
-        if stm == self.my_side {
-            Decision::Quality
-        } else {
+        if stm != self.my_side {
             Decision::Popularity
+        } else if self.hybrid_my_side {
+            Decision::Hybrid
+        } else {
+            Decision::Quality
         }
     }
+    fn is_my_side(&self, stm: Color) -> bool {
+        stm == self.my_side
+    }
     fn adjust(&self, req: &mut CandidateRequest, is_my_side: bool) {
         if is_my_side {
             req.cp_window = self.cp_window;
@@ -37,4 +85,100 @@ impl MovePolicy for SideSplitPolicy {
             req.min_play_rate = self.min_play_rate;
         }
     }
+
+    /// Sorts and applies the request's windows as usual, except that on my side a
+    /// score window replaces `cp_window`. Hybrid my-side nodes are then ranked by
+    /// `sort_practical`.
+    fn post_filter(
+        &self,
+        req: &CandidateRequest,
+        is_my_side: bool,
+        cands: CandidateMoves,
+    ) -> CandidateMoves {
+        sort_and_window(
+            req,
+            is_my_side,
+            self.hybrid_my_side,
+            cands,
+            |cands| match self.max_score_loss.filter(|_| is_my_side) {
+                Some(max_loss) => self.score_window(req.fen_key.side_to_move(), max_loss, cands),
+                None => apply_window(req, is_my_side, cands),
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::{Eval, FenKey, candidate_request::CandidateRequestBuilder},
+        test_support::{black_to_move, cand},
+    };
+
+    fn policy() -> SideSplitPolicy {
+        SideSplitPolicy::new(Color::White, Centipawns::from_int(50), PlayRate::new(0.05))
+            .with_score_window(0.03, WinModel::lichess())
+    }
+
+    fn kept(policy: &SideSplitPolicy, stm: Color, cands: CandidateMoves) -> Vec<String> {
+        let side = PieceColor::from_shakmaty(stm);
+        let fen = match side {
+            PieceColor::White => FenKey::starting_position(),
+            PieceColor::Black => black_to_move(),
+        };
+        let mut req = CandidateRequestBuilder::default()
+            .fen_key(fen)
+            .build()
+            .unwrap();
+        let is_my_side = stm == policy.my_side;
+        policy.adjust(&mut req, is_my_side);
+        policy
+            .post_filter(&req, is_my_side, cands)
+            .iter()
+            .map(|c| c.uci.to_uci())
+            .collect()
+    }
+
+    #[test]
+    fn test_score_window_is_wide_when_winning() {
+        // 50cp behind the best costs under 2% expected score at +600...
+        let cands = vec![
+            cand("a2a3", Some(Eval::Cp(550)), None),
+            cand("b2b3", Some(Eval::Cp(600)), None),
+            cand("c2c3", None, None),
+        ];
+        assert_eq!(kept(&policy(), Color::White, cands), vec!["b2b3", "a2a3"]);
+        // ...but over 4% near equality.
+        let cands = vec![
+            cand("a2a3", Some(Eval::Cp(50)), None),
+            cand("b2b3", Some(Eval::Cp(100)), None),
+        ];
+        assert_eq!(kept(&policy(), Color::White, cands), vec!["b2b3"]);
+    }
+
+    #[test]
+    fn test_score_window_uses_my_perspective() {
+        let policy =
+            SideSplitPolicy::new(Color::Black, Centipawns::from_int(50), PlayRate::new(0.05))
+                .with_score_window(0.05, WinModel::lichess());
+        let cands = vec![
+            cand("a7a6", Some(Eval::Cp(30)), None),
+            cand("c7c5", Some(Eval::Cp(-20)), None),
+            cand("h7h5", Some(Eval::Cp(150)), None),
+            cand("e7e5", Some(Eval::Mate(-9)), None),
+        ];
+        assert_eq!(kept(&policy, Color::Black, cands), vec!["e7e5"]);
+    }
+
+    #[test]
+    fn test_score_window_leaves_opponent_and_unevaluated_moves() {
+        let cands = vec![
+            cand("e7e5", Some(Eval::Cp(300)), None),
+            cand("c7c5", Some(Eval::Cp(0)), None),
+        ];
+        assert_eq!(kept(&policy(), Color::Black, cands), vec!["c7c5", "e7e5"]);
+        let cands = vec![cand("e2e4", None, None), cand("d2d4", None, None)];
+        assert_eq!(kept(&policy(), Color::White, cands), vec!["d2d4", "e2e4"]);
+    }
 }
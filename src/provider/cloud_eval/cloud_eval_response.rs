@@ -0,0 +1,151 @@
+//! Response model for Lichess `/api/cloud-eval`.
+//!
+//! A hit looks like
+//! `{"fen": "...", "knodes": 13683, "depth": 22, "pvs": [{"moves": "e2e4 e7e5 g1f3", "cp": 18}]}`,
+//! where each PV carries either `cp` or `mate`, both from White's perspective.
+//! Positions the cloud has never analysed come back as HTTP 404.
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{Eval, EvalLine, chess::UciMove};
+
+/// Outcome of a cloud-eval lookup.
+#[derive(Debug, Clone)]
+pub enum CloudEval {
+    /// The position is in the cloud database.
+    Found(CloudEvalResponse),
+    /// The endpoint answered 404: nobody has analysed this position yet.
+    NotInCloud,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CloudEvalResponse {
+    #[serde(default)]
+    pub fen: Option<String>,
+    #[serde(default)]
+    pub knodes: u64,
+    pub depth: u32,
+    #[serde(default)]
+    pub pvs: Vec<CloudEvalPv>,
+}
+
+/// One principal variation: space-separated UCI moves plus a centipawn or mate score.
+/// The database dump names the moves field `line` instead of `moves`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CloudEvalPv {
+    #[serde(alias = "line")]
+    pub moves: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cp: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mate: Option<i32>,
+}
+
+impl CloudEvalPv {
+    /// Score of this PV, from White's perspective.
+    pub fn eval(&self) -> Result<Eval> {
+        match (self.cp, self.mate) {
+            (_, Some(mate)) => Ok(Eval::Mate(mate)),
+            (Some(cp), None) => Ok(Eval::Cp(cp)),
+            (None, None) => Err(anyhow!("cloud-eval PV has neither cp nor mate")),
+        }
+    }
+
+    /// Parse the PV moves.
+    pub fn moves(&self) -> Result<Vec<UciMove>> {
+        self.moves
+            .split_whitespace()
+            .map(|m| UciMove::from_uci(m).map_err(|_| anyhow!("cloud-eval returned bad UCI '{m}'")))
+            .collect()
+    }
+
+    /// Convert into an `EvalLine` whose move is the first move of the PV.
+    pub fn to_eval_line(&self, depth: u8) -> Result<EvalLine> {
+        let pv = self.moves()?;
+        let uci = pv
+            .first()
+            .cloned()
+            .ok_or_else(|| anyhow!("cloud-eval PV has no moves"))?;
+        Ok(EvalLine {
+            uci,
+            eval: self.eval()?,
+            depth,
+            pv,
+            source: None,
+        })
+    }
+}
+
+impl CloudEvalResponse {
+    /// Convert every PV into an `EvalLine`, all sharing the response depth.
+    pub fn into_eval_lines(self) -> Result<Vec<EvalLine>> {
+        let depth = u8::try_from(self.depth).unwrap_or(u8::MAX);
+        self.pvs.iter().map(|pv| pv.to_eval_line(depth)).collect()
+    }
+}
+
+impl CloudEval {
+    /// Lines for a hit; a position that is not in the cloud has none.
+    pub fn into_eval_lines(self) -> Result<Vec<EvalLine>> {
+        match self {
+            CloudEval::Found(resp) => resp.into_eval_lines(),
+            CloudEval::NotInCloud => Ok(Vec::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = r#"{
+        "fen": "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+        "knodes": 1013482,
+        "depth": 41,
+        "pvs": [
+            {"moves": "f1b5 g8f6 e1g1 f6e4", "cp": 28},
+            {"moves": "d2d4 e5d4 f3d4", "cp": 12},
+            {"moves": "h7h8q", "mate": -3}
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_real_response() {
+        let resp: CloudEvalResponse = serde_json::from_str(FIXTURE).unwrap();
+        assert_eq!(resp.depth, 41);
+        assert_eq!(resp.knodes, 1013482);
+        assert_eq!(resp.pvs.len(), 3);
+        assert_eq!(resp.pvs[2].mate, Some(-3));
+        assert_eq!(resp.pvs[2].cp, None);
+    }
+
+    #[test]
+    fn test_into_eval_lines_keeps_pv_and_maps_mate() {
+        let resp: CloudEvalResponse = serde_json::from_str(FIXTURE).unwrap();
+        let lines = resp.into_eval_lines().unwrap();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].uci.to_uci(), "f1b5");
+        assert_eq!(lines[0].eval, Eval::Cp(28));
+        assert_eq!(lines[0].depth, 41);
+        let pv: Vec<String> = lines[0].pv.iter().map(UciMove::to_uci).collect();
+        assert_eq!(pv, vec!["f1b5", "g8f6", "e1g1", "f6e4"]);
+        assert_eq!(lines[2].eval, Eval::Mate(-3));
+        assert!(lines[2].eval < lines[1].eval);
+    }
+
+    #[test]
+    fn test_pv_without_score_is_an_error() {
+        let pv = CloudEvalPv {
+            moves: "e2e4".to_string(),
+            cp: None,
+            mate: None,
+        };
+        assert!(pv.to_eval_line(20).is_err());
+    }
+
+    #[test]
+    fn test_not_in_cloud_has_no_lines() {
+        assert!(CloudEval::NotInCloud.into_eval_lines().unwrap().is_empty());
+    }
+}
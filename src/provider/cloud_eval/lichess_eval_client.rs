@@ -2,11 +2,15 @@
 //! Talks to /api/cloud-eval and returns MultiPV lines.
 
 use async_trait::async_trait;
+use reqwest::StatusCode;
 
 use crate::{
     config::QualityConfig,
     domain::{EvalLine, FenKey},
-    provider::{MoveQuality, QualityCaps},
+    provider::{
+        MoveQuality, QualityCaps,
+        cloud_eval::{CloudEval, CloudEvalResponse},
+    },
 };
 
 pub fn build_lichess_eval_client(
@@ -37,6 +41,14 @@ impl Default for LichessEvalClient {
     }
 }
 
+impl LichessEvalClient {
+    /// Typed lookup that distinguishes "not in cloud" from real failures.
+    pub async fn lookup(&self, fen: &FenKey, multipv: Option<usize>) -> anyhow::Result<CloudEval> {
+        let pv = multipv.unwrap_or(self.multi_pv);
+        lichess_eval_api_call(&self.base_url, fen, pv).await
+    }
+}
+
 #[async_trait]
 impl MoveQuality for LichessEvalClient {
     async fn evaluate(
@@ -44,10 +56,9 @@ impl MoveQuality for LichessEvalClient {
         fen: &FenKey,
         multipv: Option<usize>,
     ) -> anyhow::Result<Vec<EvalLine>> {
-        // Uses `multipv` if provided, else defaults to self.multi_pv
-        let pv = multipv.unwrap_or(self.multi_pv);
-
-        lichess_eval_api_call(&self.base_url, fen, pv).await
+        // Uses `multipv` if provided, else defaults to self.multi_pv.
+        // Positions missing from the cloud yield no lines rather than an error.
+        self.lookup(fen, multipv).await?.into_eval_lines()
     }
 
     fn caps(&self) -> QualityCaps {
@@ -61,22 +72,19 @@ async fn lichess_eval_api_call(
     url: &str,
     fen: &FenKey,
     multipv: usize,
-) -> anyhow::Result<Vec<EvalLine>> {
+) -> anyhow::Result<CloudEval> {
     let request_url = build_lichess_eval_url(url, fen, multipv);
-    let eval = reqwest::get(&request_url)
-        .await?
-        .json::<Vec<EvalLine>>()
-        .await?;
-    Ok(eval)
+    let resp = reqwest::get(&request_url).await?;
+    if resp.status() == StatusCode::NOT_FOUND {
+        return Ok(CloudEval::NotInCloud);
+    }
+    let eval = resp.error_for_status()?.json::<CloudEvalResponse>().await?;
+    Ok(CloudEval::Found(eval))
 }
 
-/// Encode fen for URL query param, special-case "startpos" for the starting position.
+/// Encode fen for URL query param; the FEN is normalized first.
 fn fen_query_param(fen: &FenKey) -> String {
-    if fen.fen_string == "startpos" {
-        "?fen=startpos".to_string()
-    } else {
-        format!("?fen={}", urlencoding::encode(&fen.fen_string))
-    }
+    format!("?fen={}", urlencoding::encode(&fen.normalized_fen()))
 }
 
 /// Encode the multiPv query param.
@@ -94,33 +102,80 @@ fn build_lichess_eval_url(base_url: &str, fen: &FenKey, multipv: usize) -> Strin
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::infra::http::stub_server::StubServer;
+
+    fn client_for(server: &StubServer) -> LichessEvalClient {
+        build_lichess_eval_client(
+            &format!("{}/api/cloud-eval", server.url()),
+            3,
+            QualityConfig::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_parses_cloud_response() {
+        let server = StubServer::start(
+            200,
+            r#"{"fen": "x", "knodes": 100, "depth": 30, "pvs": [{"moves": "e2e4 e7e5", "cp": 20}, {"moves": "d2d4", "mate": 4}]}"#,
+        );
+        let lines = client_for(&server)
+            .evaluate(&FenKey::starting_position(), Some(2))
+            .await
+            .unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].uci.to_uci(), "e2e4");
+        assert_eq!(lines[0].pv.len(), 2);
+        assert_eq!(lines[0].depth, 30);
+        assert_eq!(lines[1].eval, crate::domain::Eval::Mate(4));
+        assert!(server.paths()[0].ends_with("&multiPv=2"));
+    }
+
+    #[tokio::test]
+    async fn test_not_in_cloud_is_typed_no_data() {
+        let server = StubServer::start(
+            404,
+            r#"{"error": "No cloud evaluation available for that position"}"#,
+        );
+        let client = client_for(&server);
+        let fen = FenKey::starting_position();
+        assert!(matches!(
+            client.lookup(&fen, None).await.unwrap(),
+            CloudEval::NotInCloud
+        ));
+        assert!(client.evaluate(&fen, None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_server_error_is_still_an_error() {
+        let server = StubServer::start(500, "{}");
+        let res = client_for(&server)
+            .evaluate(&FenKey::starting_position(), None)
+            .await;
+        assert!(res.is_err());
+    }
 
     #[tokio::test]
     async fn test_build_lichess_eval_url() {
         let base_url = "https://lichess.org/api/cloud-eval";
-        let fen = FenKey {
-            fen_string: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(),
-            side_to_move: crate::domain::PieceColor::White,
-        };
+        let fen =
+            FenKey::parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
         let multipv = 3;
         let url = build_lichess_eval_url(base_url, &fen, multipv);
-        assert_eq!(url, "https://lichess.org/api/cloud-eval?fen=rnbqkbnr%2Fpppppppp%2F8%2F8%2F8%2F8%2FPPPPPPPP%2FRNBQKBNR%20w%20KQkq%20-%200%201&multiPv=3");
+        assert_eq!(
+            url,
+            "https://lichess.org/api/cloud-eval?fen=rnbqkbnr%2Fpppppppp%2F8%2F8%2F8%2F8%2FPPPPPPPP%2FRNBQKBNR%20w%20KQkq%20-%200%201&multiPv=3"
+        );
     }
 
     #[tokio::test]
     async fn test_fen_query_param() {
-        let fen1 = FenKey {
-            fen_string: "startpos".to_string(),
-            side_to_move: crate::domain::PieceColor::White,
-        };
-        assert_eq!(fen_query_param(&fen1), "?fen=startpos");
-        let fen2 = FenKey {
-            fen_string: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(),
-            side_to_move: crate::domain::PieceColor::White,
-        };
+        let fen1 = FenKey::parse("startpos").unwrap();
+        let fen2 =
+            FenKey::parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
         assert_eq!(
             fen_query_param(&fen2),
             "?fen=rnbqkbnr%2Fpppppppp%2F8%2F8%2F8%2F8%2FPPPPPPPP%2FRNBQKBNR%20w%20KQkq%20-%200%201"
         );
+        assert_eq!(fen_query_param(&fen1), fen_query_param(&fen2));
     }
 }
@@ -1,3 +1,5 @@
+pub mod cloud_eval_response;
 pub mod lichess_eval_client;
 
+pub use cloud_eval_response::{CloudEval, CloudEvalPv, CloudEvalResponse};
 pub use lichess_eval_client::{build_lichess_eval_client, LichessEvalClient};
@@ -0,0 +1,250 @@
+//! Composite quality provider that asks its sources in order, e.g. evaldb → cloud →
+//! local engine, and keeps the first answer that is deep enough.
+//!
+//! A source is skipped when it has no lines for the position, when its lines are
+//! shallower than `min_depth`, or when it fails. If no source is good enough the
+//! deepest shallow answer is used; only when every source failed is the error returned.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+use crate::{
+    domain::{EvalLine, FenKey},
+    provider::{MoveQuality, QualityCaps},
+};
+
+pub struct FallbackQuality {
+    sources: Vec<(String, Arc<dyn MoveQuality>)>,
+    min_depth: Option<u8>,
+}
+
+impl FallbackQuality {
+    /// `sources` are tried in order; each is recorded under its name in the lines it answers.
+    pub fn new(sources: Vec<(String, Arc<dyn MoveQuality>)>, min_depth: Option<u8>) -> Self {
+        Self { sources, min_depth }
+    }
+}
+
+fn depth_of(lines: &[EvalLine]) -> u8 {
+    lines.iter().map(|l| l.depth).max().unwrap_or(0)
+}
+
+#[async_trait]
+impl MoveQuality for FallbackQuality {
+    async fn evaluate(
+        &self,
+        fen: &FenKey,
+        multipv: Option<usize>,
+    ) -> anyhow::Result<Vec<EvalLine>> {
+        let mut shallow: Option<Vec<EvalLine>> = None;
+        let mut last_err = None;
+        let mut errors = 0;
+
+        for (name, source) in &self.sources {
+            let mut lines = match source.evaluate(fen, multipv).await {
+                Ok(lines) if lines.is_empty() => {
+                    debug!(
+                        "quality source '{name}' has no data for {}",
+                        fen.fen_string()
+                    );
+                    continue;
+                }
+                Ok(lines) => lines,
+                Err(e) => {
+                    warn!(
+                        "quality source '{name}' failed for {}: {e:#}",
+                        fen.fen_string()
+                    );
+                    last_err = Some(e);
+                    errors += 1;
+                    continue;
+                }
+            };
+            for line in &mut lines {
+                line.source = Some(name.clone());
+            }
+            if self.min_depth.is_none_or(|min| depth_of(&lines) >= min) {
+                return Ok(lines);
+            }
+            debug!(
+                "quality source '{name}' too shallow for {}",
+                fen.fen_string()
+            );
+            if shallow
+                .as_ref()
+                .is_none_or(|best| depth_of(&lines) > depth_of(best))
+            {
+                shallow = Some(lines);
+            }
+        }
+
+        if let Some(lines) = shallow {
+            return Ok(lines);
+        }
+        match last_err {
+            Some(e) if errors == self.sources.len() => {
+                Err(e.context("every quality source failed"))
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    fn caps(&self) -> QualityCaps {
+        QualityCaps {
+            max_multipv: self
+                .sources
+                .iter()
+                .map(|(_, s)| s.caps().max_multipv)
+                .max()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{AppConfig, QualityConfig},
+        domain::{Eval, chess::UciMove},
+        infra::build_infra,
+        provider::build_quality,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    enum Answer {
+        Depth(u8),
+        NoData,
+        Fail,
+    }
+
+    struct Scripted {
+        answer: Answer,
+        calls: AtomicUsize,
+    }
+
+    fn scripted(answer: Answer) -> Arc<Scripted> {
+        Arc::new(Scripted {
+            answer,
+            calls: AtomicUsize::new(0),
+        })
+    }
+
+    #[async_trait]
+    impl MoveQuality for Scripted {
+        async fn evaluate(
+            &self,
+            _fen: &FenKey,
+            _multipv: Option<usize>,
+        ) -> anyhow::Result<Vec<EvalLine>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match self.answer {
+                Answer::Depth(depth) => Ok(vec![EvalLine {
+                    uci: UciMove::from_uci("e2e4").unwrap(),
+                    eval: Eval::Cp(30),
+                    depth,
+                    pv: vec![],
+                    source: None,
+                }]),
+                Answer::NoData => Ok(vec![]),
+                Answer::Fail => anyhow::bail!("503 Service Unavailable"),
+            }
+        }
+
+        fn caps(&self) -> QualityCaps {
+            QualityCaps { max_multipv: 5 }
+        }
+    }
+
+    fn chain(sources: &[(&str, &Arc<Scripted>)], min_depth: Option<u8>) -> FallbackQuality {
+        FallbackQuality::new(
+            sources
+                .iter()
+                .map(|(name, s)| (name.to_string(), Arc::clone(*s) as Arc<dyn MoveQuality>))
+                .collect(),
+            min_depth,
+        )
+    }
+
+    async fn answer(chain: &FallbackQuality) -> anyhow::Result<Vec<EvalLine>> {
+        chain.evaluate(&FenKey::starting_position(), None).await
+    }
+
+    #[tokio::test]
+    async fn test_falls_through_no_data_and_errors() {
+        let evaldb = scripted(Answer::NoData);
+        let cloud = scripted(Answer::Fail);
+        let uci = scripted(Answer::Depth(20));
+        let lines = answer(&chain(
+            &[("evaldb", &evaldb), ("cloud", &cloud), ("uci", &uci)],
+            None,
+        ))
+        .await
+        .unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].source.as_deref(), Some("uci"));
+        assert_eq!(uci.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_first_deep_answer_wins() {
+        let evaldb = scripted(Answer::Depth(40));
+        let uci = scripted(Answer::Depth(20));
+        let lines = answer(&chain(&[("evaldb", &evaldb), ("uci", &uci)], Some(30)))
+            .await
+            .unwrap();
+        assert_eq!(lines[0].source.as_deref(), Some("evaldb"));
+        assert_eq!(uci.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_shallow_answers_fall_through_but_are_kept() {
+        let shallow = scripted(Answer::Depth(12));
+        let deeper = scripted(Answer::Depth(18));
+        let deep = scripted(Answer::Depth(30));
+        let both = chain(&[("cloud", &shallow), ("uci", &deep)], Some(24));
+        assert_eq!(answer(&both).await.unwrap()[0].depth, 30);
+
+        // Nothing reaches min_depth: the deepest shallow answer is better than none.
+        let none_deep = chain(&[("cloud", &shallow), ("evaldb", &deeper)], Some(24));
+        let lines = answer(&none_deep).await.unwrap();
+        assert_eq!(lines[0].source.as_deref(), Some("evaldb"));
+        assert_eq!(lines[0].depth, 18);
+    }
+
+    #[tokio::test]
+    async fn test_error_only_when_every_source_fails() {
+        let fail = scripted(Answer::Fail);
+        let no_data = scripted(Answer::NoData);
+        assert!(
+            answer(&chain(&[("cloud", &fail), ("uci", &fail)], None))
+                .await
+                .is_err()
+        );
+        assert!(
+            answer(&chain(&[("cloud", &fail), ("evaldb", &no_data)], None))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_build_quality_chain_from_config() {
+        let infra =
+            build_infra(&AppConfig::load("src/config/default_config.toml").unwrap()).unwrap();
+        let cfg = |chain: &[&str]| {
+            QualityConfig::builder()
+                .source("chain".to_string())
+                .chain(chain.iter().map(|s| s.to_string()).collect())
+                .build()
+                .unwrap()
+        };
+        let quality = build_quality(&cfg(&["cloud", "cloud"]), &infra).unwrap();
+        assert_eq!(quality.caps().max_multipv, 5);
+        assert!(build_quality(&cfg(&[]), &infra).is_err());
+        assert!(build_quality(&cfg(&["cloud", "chain"]), &infra).is_err());
+        assert!(build_quality(&cfg(&["cloud", "nope"]), &infra).is_err());
+    }
+}
@@ -0,0 +1,353 @@
+//! Composite popularity provider that blends several sources, e.g. 70% Lichess rapid
+//! games with 30% of a local club index.
+//!
+//! All sources are queried concurrently and their rows merged by move. With
+//! `MergeBy::Weight` each source contributes its play rates scaled by its weight;
+//! with `MergeBy::Games` the game counts are pooled. Either way the merged play rates
+//! are renormalized to sum to one, and every row keeps the games seen per source.
+//! Results are summed and average ratings weighted by game count.
+//! A source that fails is left out of the blend; only when all fail is it an error.
+
+use anyhow::{Context, bail};
+use async_trait::async_trait;
+use std::{collections::HashMap, sync::Arc};
+use tokio::task::JoinSet;
+use tracing::warn;
+
+use crate::{
+    domain::{FenKey, PlayRate, PopularityRow, Wdl, chess::UciMove},
+    provider::{MovePopularity, PopularityCaps},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeBy {
+    /// Blend play rates by the configured source weights.
+    Weight,
+    /// Pool game counts, so sources with more games count for more.
+    Games,
+}
+
+impl MergeBy {
+    /// Parse `popularity.merge_by`; unset means `Weight`.
+    pub fn parse(s: Option<&str>) -> anyhow::Result<Self> {
+        match s {
+            None | Some("weight") => Ok(MergeBy::Weight),
+            Some("games") => Ok(MergeBy::Games),
+            Some(other) => bail!("unknown popularity.merge_by '{other}'"),
+        }
+    }
+}
+
+pub struct MergedSource {
+    pub name: String,
+    pub weight: f32,
+    pub provider: Arc<dyn MovePopularity>,
+}
+
+pub struct MergedPopularity {
+    sources: Vec<MergedSource>,
+    merge_by: MergeBy,
+}
+
+impl MergedPopularity {
+    pub fn new(sources: Vec<MergedSource>, merge_by: MergeBy) -> Self {
+        Self { sources, merge_by }
+    }
+
+    /// Merge per-source rows; `answers` is parallel to `self.sources`.
+    fn merge(&self, answers: &[Vec<PopularityRow>]) -> Vec<PopularityRow> {
+        struct Merged {
+            uci: UciMove,
+            score: f64,
+            games: u32,
+            wdl: Option<Wdl>,
+            rating_sum: u64,
+            rated_games: u64,
+            games_by_source: Vec<(String, u32)>,
+        }
+        let mut by_move: HashMap<String, Merged> = HashMap::new();
+
+        for (source, rows) in self.sources.iter().zip(answers) {
+            for row in rows {
+                let score = match self.merge_by {
+                    MergeBy::Weight => f64::from(source.weight) * f64::from(row.play_rate.value()),
+                    MergeBy::Games => f64::from(row.games),
+                };
+                let merged = by_move.entry(row.uci.to_uci()).or_insert_with(|| Merged {
+                    uci: row.uci.clone(),
+                    score: 0.0,
+                    games: 0,
+                    wdl: None,
+                    rating_sum: 0,
+                    rated_games: 0,
+                    games_by_source: Vec::new(),
+                });
+                merged.score += score;
+                merged.games = merged.games.saturating_add(row.games);
+                if let Some(wdl) = &row.wdl {
+                    merged.wdl.get_or_insert_default().add(wdl);
+                }
+                if let Some(rating) = row.avg_rating {
+                    merged.rating_sum += u64::from(rating) * u64::from(row.games);
+                    merged.rated_games += u64::from(row.games);
+                }
+                merged
+                    .games_by_source
+                    .push((source.name.clone(), row.games));
+            }
+        }
+
+        let total: f64 = by_move.values().map(|m| m.score).sum();
+        let mut rows: Vec<PopularityRow> = by_move
+            .into_values()
+            .map(|m| PopularityRow {
+                uci: m.uci,
+                play_rate: PlayRate::new(if total > 0.0 {
+                    (m.score / total) as f32
+                } else {
+                    0.0
+                }),
+                games: m.games,
+                wdl: m.wdl,
+                avg_rating: (m.rated_games > 0).then(|| (m.rating_sum / m.rated_games) as u32),
+                games_by_source: m.games_by_source,
+            })
+            .collect();
+        rows.sort_by(|a, b| {
+            b.play_rate
+                .compare(&a.play_rate)
+                .then_with(|| a.uci.to_uci().cmp(&b.uci.to_uci()))
+        });
+        rows
+    }
+}
+
+#[async_trait]
+impl MovePopularity for MergedPopularity {
+    async fn sample(&self, fen: &FenKey) -> anyhow::Result<Vec<PopularityRow>> {
+        let mut tasks = JoinSet::new();
+        for (i, source) in self.sources.iter().enumerate() {
+            let provider = Arc::clone(&source.provider);
+            let fen = fen.clone();
+            tasks.spawn(async move { (i, provider.sample(&fen).await) });
+        }
+
+        let mut answers = vec![Vec::new(); self.sources.len()];
+        let mut last_err = None;
+        let mut errors = 0;
+        while let Some(joined) = tasks.join_next().await {
+            let (i, result) = joined.context("popularity source panicked")?;
+            match result {
+                Ok(rows) => answers[i] = rows,
+                Err(e) => {
+                    warn!(
+                        "popularity source '{}' failed for {}: {e:#}",
+                        self.sources[i].name,
+                        fen.fen_string()
+                    );
+                    last_err = Some(e);
+                    errors += 1;
+                }
+            }
+        }
+        if let Some(e) = last_err
+            && errors == self.sources.len()
+        {
+            return Err(e.context("every popularity source failed"));
+        }
+        Ok(self.merge(&answers))
+    }
+
+    fn caps(&self) -> PopularityCaps {
+        PopularityCaps {
+            supports_filters: self
+                .sources
+                .iter()
+                .all(|s| s.provider.caps().supports_filters),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{AppConfig, PopularityConfig},
+        infra::build_infra,
+        provider::build_popularity,
+    };
+
+    /// Rows for the given moves, White winning every game, all played at one rating.
+    struct Fixed(Option<Vec<(&'static str, u32)>>, u32);
+
+    #[async_trait]
+    impl MovePopularity for Fixed {
+        async fn sample(&self, _fen: &FenKey) -> anyhow::Result<Vec<PopularityRow>> {
+            let Some(moves) = &self.0 else {
+                bail!("explorer returned 429");
+            };
+            let total: u32 = moves.iter().map(|(_, g)| g).sum();
+            Ok(moves
+                .iter()
+                .map(|&(uci, games)| PopularityRow {
+                    uci: UciMove::from_uci(uci).unwrap(),
+                    play_rate: PlayRate::new(games as f32 / total as f32),
+                    games,
+                    wdl: Some(Wdl::new(u64::from(games), 0, 0)),
+                    avg_rating: Some(self.1),
+                    games_by_source: Vec::new(),
+                })
+                .collect())
+        }
+
+        fn caps(&self) -> PopularityCaps {
+            PopularityCaps {
+                supports_filters: true,
+            }
+        }
+    }
+
+    fn source(name: &str, weight: f32, moves: Option<Vec<(&'static str, u32)>>) -> MergedSource {
+        MergedSource {
+            name: name.to_string(),
+            weight,
+            provider: Arc::new(Fixed(moves, if name == "club" { 1500 } else { 1800 })),
+        }
+    }
+
+    /// Lichess: e5 60%, c5 40% of 1000 games. Club: c5 75%, e6 25% of 20 games.
+    fn lichess_and_club(merge_by: MergeBy) -> MergedPopularity {
+        MergedPopularity::new(
+            vec![
+                source("lichess", 0.7, Some(vec![("e7e5", 600), ("c7c5", 400)])),
+                source("club", 0.3, Some(vec![("c7c5", 15), ("e7e6", 5)])),
+            ],
+            merge_by,
+        )
+    }
+
+    async fn rates(popularity: &MergedPopularity) -> Vec<(String, f32, u32)> {
+        popularity
+            .sample(&FenKey::starting_position())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| (r.uci.to_uci(), r.play_rate.value(), r.games))
+            .collect()
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-4, "{actual} != {expected}");
+    }
+
+    #[tokio::test]
+    async fn test_weighted_merge() {
+        let rows = rates(&lichess_and_club(MergeBy::Weight)).await;
+        assert_eq!(rows.len(), 3);
+        // c5: 0.7 * 0.4 + 0.3 * 0.75
+        assert_eq!(rows[0].0, "c7c5");
+        assert_close(rows[0].1, 0.505);
+        assert_eq!(rows[0].2, 415);
+        assert_close(rows[1].1, 0.42);
+        assert_close(rows[2].1, 0.075);
+        assert_close(rows.iter().map(|r| r.1).sum(), 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_games_weighted_merge() {
+        let rows = rates(&lichess_and_club(MergeBy::Games)).await;
+        assert_eq!(rows[0].0, "e7e5");
+        assert_close(rows[0].1, 600.0 / 1020.0);
+        assert_close(rows[1].1, 415.0 / 1020.0);
+    }
+
+    #[tokio::test]
+    async fn test_games_by_source_are_kept() {
+        let rows = lichess_and_club(MergeBy::Weight)
+            .sample(&FenKey::starting_position())
+            .await
+            .unwrap();
+        let c5 = rows.iter().find(|r| r.uci.to_uci() == "c7c5").unwrap();
+        let mut by_source = c5.games_by_source.clone();
+        by_source.sort();
+        assert_eq!(
+            by_source,
+            vec![("club".to_string(), 15), ("lichess".to_string(), 400)]
+        );
+        assert_eq!(c5.wdl, Some(Wdl::new(415, 0, 0)));
+        // (400 * 1800 + 15 * 1500) / 415
+        assert_eq!(c5.avg_rating, Some(1789));
+    }
+
+    #[tokio::test]
+    async fn test_missing_or_failing_source_is_left_out() {
+        let popularity = MergedPopularity::new(
+            vec![
+                source("lichess", 0.7, None),
+                source("club", 0.3, Some(vec![("c7c5", 3), ("e7e6", 1)])),
+            ],
+            MergeBy::Weight,
+        );
+        let rows = rates(&popularity).await;
+        assert_close(rows[0].1, 0.75);
+
+        let all_down = MergedPopularity::new(
+            vec![source("a", 1.0, None), source("b", 1.0, None)],
+            MergeBy::Weight,
+        );
+        assert!(all_down.sample(&FenKey::starting_position()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_build_from_config() {
+        let fixture = format!(
+            "{}/tests/fixtures/club_games.pgn",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let toml = format!(
+            r#"
+            source = "merge"
+            base_url = "unused"
+            speed = "all"
+            min_rating = 0
+            max_rating = 4000
+            since_year = 1900
+            variant = "standard"
+            merge_by = "games"
+
+            [[merge]]
+            source = "pgn"
+            name = "club"
+            pgn_paths = ["{fixture}"]
+
+            [[merge]]
+            source = "pgn"
+            name = "club-again"
+            weight = 3
+            pgn_paths = ["{fixture}"]
+            "#
+        );
+        let cfg: PopularityConfig = toml::from_str(&toml).unwrap();
+        assert_eq!(cfg.merge.len(), 2);
+        assert_eq!(cfg.merge[1].weight(), 3.0);
+
+        let infra =
+            build_infra(&AppConfig::load("src/config/default_config.toml").unwrap()).unwrap();
+        let merged = build_popularity(&cfg, &infra).unwrap();
+        let rows = merged.sample(&FenKey::starting_position()).await.unwrap();
+        let single = build_popularity(&cfg.merge[0].apply(&cfg), &infra)
+            .unwrap()
+            .sample(&FenKey::starting_position())
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), single.len());
+        assert_eq!(rows[0].games, 2 * single[0].games);
+        assert_eq!(rows[0].games_by_source.len(), 2);
+
+        let mut bad = cfg.clone();
+        bad.merge_by = Some("vibes".to_string());
+        assert!(build_popularity(&bad, &infra).is_err());
+        bad.merge.clear();
+        assert!(build_popularity(&bad, &infra).is_err());
+    }
+}
@@ -0,0 +1,5 @@
+pub mod fallback_quality;
+pub mod merged_popularity;
+
+pub use fallback_quality::FallbackQuality;
+pub use merged_popularity::{MergeBy, MergedPopularity, MergedSource};
@@ -0,0 +1,127 @@
+//! Offline quality provider answering from an imported Lichess cloud-eval dump.
+//! Positions missing from the database yield no lines, like the cloud API's 404.
+
+use anyhow::Context;
+use async_trait::async_trait;
+use std::path::Path;
+
+use crate::{
+    config::QualityConfig,
+    domain::{EvalLine, FenKey},
+    infra::keyed_file::KeyedFile,
+    provider::{
+        MoveQuality, QualityCaps,
+        eval_db::{DumpEval, EVAL_DB_MAGIC},
+    },
+};
+
+#[derive(Debug)]
+pub struct EvalDbClient {
+    cfg: QualityConfig,
+    store: KeyedFile,
+}
+
+impl EvalDbClient {
+    /// Open `cfg.evaldb_path`; fails if it is unset or not an imported eval database.
+    pub fn from_config(cfg: QualityConfig) -> anyhow::Result<Self> {
+        let path = cfg
+            .evaldb_path
+            .clone()
+            .context("quality.evaldb_path must be set for source = \"evaldb\"")?;
+        let store = KeyedFile::open(Path::new(&path), EVAL_DB_MAGIC)?;
+        Ok(Self { cfg, store })
+    }
+}
+
+#[async_trait]
+impl MoveQuality for EvalDbClient {
+    async fn evaluate(
+        &self,
+        fen: &FenKey,
+        multipv: Option<usize>,
+    ) -> anyhow::Result<Vec<EvalLine>> {
+        let pv = multipv.unwrap_or(self.cfg.multi_pv);
+        let Some(blob) = self.store.get(fen.key().0)? else {
+            return Ok(Vec::new());
+        };
+        let evals: Vec<DumpEval> = serde_json::from_slice(blob)?;
+        match DumpEval::select(&evals, pv) {
+            Some(eval) => eval.to_eval_lines(pv),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn caps(&self) -> QualityCaps {
+        QualityCaps {
+            max_multipv: self.cfg.multi_pv,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{domain::Eval, provider::eval_db::import_eval_dump, test_support::black_to_move};
+
+    const DUMP: &str = r#"{"fen":"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -","evals":[{"pvs":[{"cp":18,"line":"e2e4 e7e5"}],"knodes":900000,"depth":45},{"pvs":[{"cp":20,"line":"e2e4 c7c5"},{"cp":15,"line":"d2d4 d7d5"},{"cp":12,"line":"g1f3 d7d5"}],"knodes":300000,"depth":32}]}
+not json at all
+{"fen":"8/8/8/4k3/8/8/8/4K3 w - -","evals":[{"pvs":[{"cp":0,"line":"e1e2"}],"knodes":10,"depth":60}]}
+{"fen":"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq -","evals":[{"pvs":[{"mate":-12,"line":"c7c5"}],"knodes":10,"depth":30}]}
+"#;
+
+    fn client(dir: &Path) -> EvalDbClient {
+        let path = dir.join("evals.db");
+        let summary = import_eval_dump(DUMP.as_bytes(), &path, Some(10)).unwrap();
+        assert_eq!(summary.read, 4);
+        assert_eq!(summary.imported, 2);
+        assert_eq!(summary.skipped, 2);
+        let cfg = QualityConfig::builder()
+            .source("evaldb".to_string())
+            .multi_pv(3)
+            .evaldb_path(Some(path.to_string_lossy().into_owned()))
+            .build()
+            .unwrap();
+        EvalDbClient::from_config(cfg).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_picks_deepest_eval_for_multipv() {
+        let dir = tempfile::tempdir().unwrap();
+        let client = client(dir.path());
+        let start = FenKey::starting_position();
+
+        let one = client.evaluate(&start, Some(1)).await.unwrap();
+        assert_eq!(one.len(), 1);
+        assert_eq!(one[0].depth, 45);
+        assert_eq!(one[0].eval, Eval::Cp(18));
+
+        let three = client.evaluate(&start, None).await.unwrap();
+        assert_eq!(three.len(), 3);
+        assert_eq!(three[0].depth, 32);
+        assert_eq!(three[1].uci.to_uci(), "d2d4");
+    }
+
+    #[tokio::test]
+    async fn test_lookup_ignores_move_counters_and_maps_mate() {
+        let dir = tempfile::tempdir().unwrap();
+        let client = client(dir.path());
+        let after_e4 = black_to_move();
+        let lines = client.evaluate(&after_e4, None).await.unwrap();
+        assert_eq!(lines[0].eval, Eval::Mate(-12));
+    }
+
+    #[tokio::test]
+    async fn test_missing_position_has_no_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let client = client(dir.path());
+        // Imported with min_pieces = 10, so the bare-kings position was skipped.
+        let kings = FenKey::parse("8/8/8/4k3/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(client.evaluate(&kings, None).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_evaldb_path_is_required() {
+        let cfg = QualityConfig::builder().build().unwrap();
+        assert!(EvalDbClient::from_config(cfg).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! Lichess cloud-eval database dump (`lichess_db_eval.jsonl`) and its importer.
+//!
+//! Each line looks like
+//! `{"fen": "<fen without move counters>", "evals": [{"pvs": [{"cp": 311, "line": "f7g7 e6e7"}], "knodes": 206765, "depth": 36}]}`.
+//! A position can carry several evals, typically a deep one with few PVs and
+//! shallower ones with more.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use shakmaty::{CastlingMode, Chess, Position, fen::Fen};
+use std::{io::BufRead, path::Path};
+use tracing::warn;
+
+use crate::{
+    domain::{EvalLine, PositionKey},
+    infra::keyed_file::KeyedFileWriter,
+    provider::cloud_eval::CloudEvalPv,
+};
+
+/// File magic of an imported eval database.
+pub const EVAL_DB_MAGIC: [u8; 4] = *b"RGEV";
+
+/// One line of the dump.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvalDumpRecord {
+    pub fen: String,
+    pub evals: Vec<DumpEval>,
+}
+
+/// One stored analysis of a position.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DumpEval {
+    pub pvs: Vec<CloudEvalPv>,
+    #[serde(default)]
+    pub knodes: u64,
+    pub depth: u32,
+}
+
+impl DumpEval {
+    /// The deepest eval with at least `multipv` PVs; if none has that many, the
+    /// deepest of those with the most PVs.
+    pub fn select(evals: &[DumpEval], multipv: usize) -> Option<&DumpEval> {
+        evals
+            .iter()
+            .filter(|e| e.pvs.len() >= multipv)
+            .max_by_key(|e| e.depth)
+            .or_else(|| evals.iter().max_by_key(|e| (e.pvs.len(), e.depth)))
+    }
+
+    /// The first `multipv` PVs as `EvalLine`s at this eval's depth.
+    pub fn to_eval_lines(&self, multipv: usize) -> Result<Vec<EvalLine>> {
+        let depth = u8::try_from(self.depth).unwrap_or(u8::MAX);
+        self.pvs
+            .iter()
+            .take(multipv)
+            .map(|pv| pv.to_eval_line(depth))
+            .collect()
+    }
+}
+
+/// Counts reported by `import_eval_dump`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub read: u64,
+    pub imported: u64,
+    /// Lines that could not be parsed, or positions below `min_pieces`.
+    pub skipped: u64,
+}
+
+/// Import a dump into an eval database at `out`. Positions with fewer than
+/// `min_pieces` pieces on the board are left out, which keeps an opening-only
+/// database small.
+pub fn import_eval_dump(
+    reader: impl BufRead,
+    out: &Path,
+    min_pieces: Option<usize>,
+) -> Result<ImportSummary> {
+    let mut writer = KeyedFileWriter::create(out, EVAL_DB_MAGIC)?;
+    let mut summary = ImportSummary::default();
+    for line in reader.lines() {
+        let line = line.context("reading eval dump")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        summary.read += 1;
+        let Some((pos, evals)) = parse_record(&line) else {
+            warn!("skipping unreadable eval dump line {}", summary.read);
+            summary.skipped += 1;
+            continue;
+        };
+        if min_pieces.is_some_and(|min| pos.board().occupied().count() < min) {
+            summary.skipped += 1;
+            continue;
+        }
+        writer.append(PositionKey::of(&pos).0, &serde_json::to_vec(&evals)?)?;
+        summary.imported += 1;
+    }
+    writer.finish()?;
+    Ok(summary)
+}
+
+fn parse_record(line: &str) -> Option<(Chess, Vec<DumpEval>)> {
+    let record: EvalDumpRecord = serde_json::from_str(line).ok()?;
+    let pos = record
+        .fen
+        .parse::<Fen>()
+        .ok()?
+        .into_position(CastlingMode::Standard)
+        .ok()?;
+    Some((pos, record.evals))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(depth: u32, pvs: usize) -> DumpEval {
+        DumpEval {
+            pvs: (0..pvs)
+                .map(|i| CloudEvalPv {
+                    moves: "e2e4".to_string(),
+                    cp: Some(i as i32),
+                    mate: None,
+                })
+                .collect(),
+            knodes: 0,
+            depth,
+        }
+    }
+
+    #[test]
+    fn test_select_prefers_deepest_with_enough_pvs() {
+        let evals = vec![eval(40, 1), eval(30, 3), eval(25, 5)];
+        assert_eq!(DumpEval::select(&evals, 1).unwrap().depth, 40);
+        assert_eq!(DumpEval::select(&evals, 3).unwrap().depth, 30);
+        assert_eq!(DumpEval::select(&evals, 5).unwrap().depth, 25);
+        assert_eq!(DumpEval::select(&evals, 8).unwrap().depth, 25);
+        assert!(DumpEval::select(&[], 1).is_none());
+    }
+
+    #[test]
+    fn test_parse_dump_line() {
+        let line = r#"{"fen":"7r/1p3k2/p1bPR3/5p2/2B2P1p/8/PP4P1/3K4 b - -","evals":[{"pvs":[{"cp":311,"line":"f7g7 e6e7 h8d8"},{"mate":-4,"line":"c6e4"}],"knodes":206765,"depth":36}]}"#;
+        let (pos, evals) = parse_record(line).unwrap();
+        assert_eq!(pos.board().occupied().count(), 15);
+        let lines = evals[0].to_eval_lines(5).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].uci.to_uci(), "f7g7");
+        assert_eq!(lines[0].depth, 36);
+        assert_eq!(lines[0].pv.len(), 3);
+        assert!(parse_record("{\"fen\": \"garbage\", \"evals\": []}").is_none());
+    }
+}
@@ -0,0 +1,5 @@
+pub mod eval_db_client;
+pub mod eval_dump;
+
+pub use eval_db_client::EvalDbClient;
+pub use eval_dump::{DumpEval, EVAL_DB_MAGIC, EvalDumpRecord, ImportSummary, import_eval_dump};
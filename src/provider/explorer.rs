@@ -1,10 +1,20 @@
+//! Lichess Opening Explorer provider (opponent popularity).
+//! Talks to the explorer `/lichess` endpoint and turns per-move result counts into play rates.
+
 use crate::{
     config::PopularityConfig,
-    domain::{FenKey, PopularityRow},
-    infra::Infra,
+    domain::{FenKey, PlayRate, PopularityRow, Wdl, chess::UciMove},
+    infra::{Infra, cache::KvCache},
     provider::{MovePopularity, PopularityCaps},
 };
+use anyhow::anyhow;
 use async_trait::async_trait;
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Lower bounds of the rating bands the explorer accepts in its `ratings` parameter.
+pub(crate) const EXPLORER_RATING_BANDS: [u32; 9] =
+    [0, 1000, 1200, 1400, 1600, 1800, 2000, 2200, 2500];
 
 /// Lichess Opening Explorer popularity provider.
 #[derive(Debug, Clone)]
@@ -24,14 +34,42 @@ impl Explorer {
     pub fn new(cfg: PopularityConfig, infra: Infra) -> Self {
         Self { cfg, infra }
     }
+
+    /// Fetch the raw explorer JSON for `url`, going through the shared cache,
+    /// single-flight coalescer and explorer rate limiter.
+    async fn fetch(&self, url: String) -> anyhow::Result<Arc<serde_json::Value>> {
+        if let Some(hit) = self.infra.cache_fen.get(&url).await {
+            return Ok(hit);
+        }
+        let http = self.infra.http.clone();
+        let rate = self.infra.rate_explorer.clone();
+        let cache = Arc::clone(&self.infra.cache_fen);
+        self.infra
+            .single
+            .run(url, move |url| async move {
+                rate.acquire().await;
+                let body = http
+                    .get(&url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<serde_json::Value>()
+                    .await?;
+                let body = Arc::new(body);
+                cache.put(url, Arc::clone(&body)).await;
+                Ok(body)
+            })
+            .await
+    }
 }
 
 #[async_trait]
 impl MovePopularity for Explorer {
-    async fn sample(&self, _fen: &FenKey) -> anyhow::Result<Vec<PopularityRow>> {
-        // TODO: use infra.http + infra.rate_explorer + infra.cache to fetch & parse
-        // Compute frequencies server-side or return counts & let orchestrator compute rate.
-        anyhow::bail!("not implemented: explorer HTTP call")
+    async fn sample(&self, fen: &FenKey) -> anyhow::Result<Vec<PopularityRow>> {
+        let url = build_explorer_url(&self.cfg, fen);
+        let body = self.fetch(url).await?;
+        let resp = ExplorerResponse::deserialize(&*body)?;
+        resp.into_rows()
     }
     fn caps(&self) -> PopularityCaps {
         PopularityCaps {
@@ -39,3 +77,262 @@ impl MovePopularity for Explorer {
         }
     }
 }
+
+/// Subset of the explorer response we rely on.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExplorerResponse {
+    #[serde(default)]
+    pub white: u64,
+    #[serde(default)]
+    pub draws: u64,
+    #[serde(default)]
+    pub black: u64,
+    #[serde(default)]
+    pub moves: Vec<ExplorerMove>,
+}
+
+/// One move row of the explorer response, with result counts from White's perspective.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExplorerMove {
+    pub uci: String,
+    pub white: u64,
+    pub draws: u64,
+    pub black: u64,
+    #[serde(default, rename = "averageRating")]
+    pub average_rating: Option<u32>,
+}
+
+impl ExplorerMove {
+    pub fn games(&self) -> u64 {
+        self.white + self.draws + self.black
+    }
+}
+
+impl ExplorerResponse {
+    /// Total games in the position. Falls back to the sum over listed moves when
+    /// the explorer omits the position totals.
+    pub fn total_games(&self) -> u64 {
+        let total = self.white + self.draws + self.black;
+        if total > 0 {
+            total
+        } else {
+            self.moves.iter().map(ExplorerMove::games).sum()
+        }
+    }
+
+    /// Convert into popularity rows; play rate is each move's share of all games in the position.
+    pub fn into_rows(self) -> anyhow::Result<Vec<PopularityRow>> {
+        let total = self.total_games();
+        self.moves
+            .into_iter()
+            .map(|m| {
+                let uci = UciMove::from_uci(&m.uci)
+                    .map_err(|_| anyhow!("explorer returned bad UCI '{}'", m.uci))?;
+                let games = m.games();
+                let play_rate = if total > 0 {
+                    games as f32 / total as f32
+                } else {
+                    0.0
+                };
+                Ok(PopularityRow {
+                    uci,
+                    play_rate: PlayRate::new(play_rate),
+                    games: u32::try_from(games).unwrap_or(u32::MAX),
+                    wdl: Some(Wdl::new(m.white, m.draws, m.black)),
+                    avg_rating: m.average_rating,
+                    games_by_source: Vec::new(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Rating bands overlapping `[min_rating, max_rating]`.
+pub(crate) fn rating_bands(min_rating: u32, max_rating: u32) -> Vec<u32> {
+    EXPLORER_RATING_BANDS
+        .iter()
+        .enumerate()
+        .filter(|&(i, &lo)| {
+            let hi = EXPLORER_RATING_BANDS
+                .get(i + 1)
+                .copied()
+                .unwrap_or(u32::MAX);
+            lo <= max_rating && hi > min_rating
+        })
+        .map(|(_, &lo)| lo)
+        .collect()
+}
+
+/// Encode fen for URL query param. The FEN is normalized, so transpositions share a
+/// URL and with it the cache entry.
+fn fen_query_param(fen: &FenKey) -> String {
+    format!("&fen={}", urlencoding::encode(&fen.normalized_fen()))
+}
+
+/// Encode the speeds query param; "all" means no speed filter.
+fn speeds_query_param(speed: &str) -> String {
+    if speed.is_empty() || speed == "all" {
+        String::new()
+    } else {
+        format!("&speeds={}", urlencoding::encode(speed))
+    }
+}
+
+/// Encode the ratings query param; omitted when the range matches no band.
+fn ratings_query_param(min_rating: u32, max_rating: u32) -> String {
+    let bands = rating_bands(min_rating, max_rating);
+    if bands.is_empty() {
+        return String::new();
+    }
+    let bands: Vec<String> = bands.iter().map(u32::to_string).collect();
+    format!("&ratings={}", urlencoding::encode(&bands.join(",")))
+}
+
+/// Given the popularity filters and a FEN, return the full URL for the explorer API call.
+fn build_explorer_url(cfg: &PopularityConfig, fen: &FenKey) -> String {
+    format!(
+        "{}?variant={}{}{}{}&since={}-01",
+        cfg.base_url,
+        urlencoding::encode(&cfg.variant),
+        fen_query_param(fen),
+        speeds_query_param(&cfg.speed),
+        ratings_query_param(cfg.min_rating, cfg.max_rating),
+        cfg.since_year
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::load_default_config,
+        infra::{build_infra, http::stub_server::StubServer},
+    };
+
+    /// Trimmed recording of `GET /lichess?fen=startpos`.
+    const STARTPOS_FIXTURE: &str = r#"{
+        "white": 600, "draws": 100, "black": 300,
+        "moves": [
+            {"uci": "e2e4", "san": "e4", "averageRating": 1650, "white": 300, "draws": 50, "black": 150, "game": null},
+            {"uci": "d2d4", "san": "d4", "averageRating": 1700, "white": 200, "draws": 40, "black": 110, "game": null},
+            {"uci": "g1f3", "san": "Nf3", "averageRating": 1720, "white": 100, "draws": 10, "black": 40, "game": null}
+        ],
+        "topGames": [],
+        "opening": null
+    }"#;
+
+    fn explorer_for(base_url: String) -> Explorer {
+        let cfg = PopularityConfig::builder()
+            .base_url(base_url)
+            .build()
+            .unwrap();
+        let infra = build_infra(&load_default_config().unwrap()).unwrap();
+        Explorer::new(cfg, infra)
+    }
+
+    #[test]
+    fn test_parse_fixture_into_rows() {
+        let resp: ExplorerResponse = serde_json::from_str(STARTPOS_FIXTURE).unwrap();
+        let rows = resp.into_rows().unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].uci.to_uci(), "e2e4");
+        assert_eq!(rows[0].games, 500);
+        assert_eq!(rows[0].wdl, Some(Wdl::new(300, 50, 150)));
+        assert_eq!(rows[0].avg_rating, Some(1650));
+        assert!((rows[0].play_rate.value() - 0.5).abs() < 1e-6);
+        assert!((rows[1].play_rate.value() - 0.35).abs() < 1e-6);
+        assert!((rows[2].play_rate.value() - 0.15).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_total_falls_back_to_move_sum() {
+        let resp: ExplorerResponse = serde_json::from_str(
+            r#"{"moves": [{"uci": "e7e5", "white": 1, "draws": 1, "black": 2}]}"#,
+        )
+        .unwrap();
+        assert_eq!(resp.total_games(), 4);
+        let rows = resp.into_rows().unwrap();
+        assert_eq!(rows[0].play_rate, PlayRate::new(1.0));
+    }
+
+    #[test]
+    fn test_empty_position_has_no_rows() {
+        let resp: ExplorerResponse =
+            serde_json::from_str(r#"{"white": 0, "draws": 0, "black": 0, "moves": []}"#).unwrap();
+        assert!(resp.into_rows().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rating_bands() {
+        assert_eq!(
+            rating_bands(800, 2000),
+            vec![0, 1000, 1200, 1400, 1600, 1800, 2000]
+        );
+        assert_eq!(rating_bands(1600, 1999), vec![1600, 1800]);
+        assert_eq!(rating_bands(2600, 3000), vec![2500]);
+        assert!(rating_bands(2000, 1000).is_empty());
+    }
+
+    #[test]
+    fn test_build_explorer_url() {
+        let cfg = PopularityConfig::builder()
+            .base_url("https://explorer.lichess.ovh/lichess".to_string())
+            .speed("blitz,rapid".to_string())
+            .min_rating(1600)
+            .max_rating(1999)
+            .since_year(2020)
+            .build()
+            .unwrap();
+        let fen = FenKey::starting_position();
+        assert_eq!(
+            build_explorer_url(&cfg, &fen),
+            "https://explorer.lichess.ovh/lichess?variant=standard&fen=rnbqkbnr%2Fpppppppp%2F8%2F8%2F8%2F8%2FPPPPPPPP%2FRNBQKBNR%20w%20KQkq%20-%200%201&speeds=blitz%2Crapid&ratings=1600%2C1800&since=2020-01"
+        );
+    }
+
+    #[test]
+    fn test_speed_all_is_omitted() {
+        assert_eq!(speeds_query_param("all"), "");
+        assert_eq!(speeds_query_param("rapid"), "&speeds=rapid");
+    }
+
+    #[tokio::test]
+    async fn test_sample_against_stub_server_uses_cache() {
+        let server = StubServer::start(200, STARTPOS_FIXTURE);
+        let explorer = explorer_for(format!("{}/lichess", server.url()));
+        let fen = FenKey::starting_position();
+
+        let rows = explorer.sample(&fen).await.unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].uci.to_uci(), "e2e4");
+
+        // Second lookup of the same position is served from cache_fen.
+        let again = explorer.sample(&fen).await.unwrap();
+        assert_eq!(again.len(), 3);
+        assert_eq!(server.hits(), 1);
+        assert!(server.paths()[0].starts_with("/lichess?variant=standard&fen="));
+    }
+
+    #[tokio::test]
+    async fn test_sample_concurrent_requests_are_coalesced() {
+        let server = StubServer::start(200, STARTPOS_FIXTURE);
+        let explorer = explorer_for(format!("{}/lichess", server.url()));
+        let fen = FenKey::starting_position();
+
+        let (a, b) = tokio::join!(explorer.sample(&fen), explorer.sample(&fen));
+        assert_eq!(a.unwrap().len(), 3);
+        assert_eq!(b.unwrap().len(), 3);
+        assert_eq!(server.hits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sample_http_error_is_reported() {
+        let server = StubServer::start(429, "{}");
+        let explorer = explorer_for(format!("{}/lichess", server.url()));
+        let err = explorer
+            .sample(&FenKey::starting_position())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("429"));
+    }
+}
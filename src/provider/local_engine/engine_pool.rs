@@ -0,0 +1,158 @@
+//! Pool of UCI engine processes so concurrent quality requests do not serialize
+//! on a single engine.
+//!
+//! Each slot keeps the last position it analysed. A request prefers a slot whose
+//! last position is the same or one move earlier, so the engine's hash table stays
+//! useful; any other slot gets `ucinewgame` first. Engines that crash or stop
+//! responding are discarded and restarted on the next attempt.
+
+use anyhow::{Result, anyhow};
+use shakmaty::Position;
+use std::{sync::Mutex, time::Duration};
+use tokio::sync::Semaphore;
+use tracing::warn;
+
+use crate::{
+    domain::{EvalLine, FenKey},
+    provider::local_engine::{SearchLimits, UciEngine},
+};
+
+/// How to start and drive each engine in the pool.
+#[derive(Debug, Clone)]
+pub struct EngineSettings {
+    pub path: String,
+    pub hash_mb: Option<usize>,
+    pub threads: Option<usize>,
+    pub limits: SearchLimits,
+    /// Per-request wall-clock budget; the search is stopped when it runs out.
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Default)]
+struct EngineSlot {
+    engine: Option<UciEngine>,
+    last_fen: Option<FenKey>,
+}
+
+pub struct EnginePool {
+    settings: EngineSettings,
+    idle: Mutex<Vec<EngineSlot>>,
+    permits: Semaphore,
+}
+
+impl EnginePool {
+    /// Create a pool of `size` engines. Processes are started lazily on first use.
+    pub fn new(settings: EngineSettings, size: usize) -> Self {
+        let size = size.max(1);
+        Self {
+            settings,
+            idle: Mutex::new((0..size).map(|_| EngineSlot::default()).collect()),
+            permits: Semaphore::new(size),
+        }
+    }
+
+    /// Analyse `fen` on a free engine, waiting for one if all are busy.
+    pub async fn analyse(&self, fen: &FenKey, multipv: usize) -> Result<Vec<EvalLine>> {
+        let _permit = self.permits.acquire().await?;
+        let mut slot = self.checkout(fen);
+        let res = self.analyse_in_slot(&mut slot, fen, multipv).await;
+        self.idle.lock().unwrap().push(slot);
+        res
+    }
+
+    /// Take an idle slot, preferring one whose engine just analysed a related position.
+    /// A slot lost to a cancelled request is replaced by a fresh one.
+    fn checkout(&self, fen: &FenKey) -> EngineSlot {
+        let mut idle = self.idle.lock().unwrap();
+        let related = idle.iter().position(|slot| {
+            slot.last_fen
+                .as_ref()
+                .is_some_and(|prev| follows_from(prev, fen))
+        });
+        match related {
+            Some(i) => idle.swap_remove(i),
+            None => idle.pop().unwrap_or_default(),
+        }
+    }
+
+    /// Run the search, restarting the engine once if it fails.
+    async fn analyse_in_slot(
+        &self,
+        slot: &mut EngineSlot,
+        fen: &FenKey,
+        multipv: usize,
+    ) -> Result<Vec<EvalLine>> {
+        let mut last_err = None;
+        for _attempt in 0..2 {
+            if slot.engine.is_none() {
+                let s = &self.settings;
+                slot.engine = Some(UciEngine::spawn(&s.path, s.hash_mb, s.threads).await?);
+                slot.last_fen = None;
+            }
+            let related = slot
+                .last_fen
+                .as_ref()
+                .is_some_and(|prev| follows_from(prev, fen));
+            let engine = slot.engine.as_mut().expect("engine started above");
+            let res = async {
+                if !related {
+                    engine.new_game().await?;
+                }
+                engine
+                    .analyse(fen, multipv, &self.settings.limits, self.settings.timeout)
+                    .await
+            }
+            .await;
+            match res {
+                Ok(lines) => {
+                    slot.last_fen = Some(fen.clone());
+                    return Ok(lines);
+                }
+                Err(e) => {
+                    warn!(
+                        "UCI engine failed on {}: {:#}; restarting",
+                        fen.fen_string(),
+                        e
+                    );
+                    slot.engine = None;
+                    slot.last_fen = None;
+                    last_err = Some(e);
+                }
+            }
+        }
+        Err(last_err.unwrap_or_else(|| anyhow!("UCI engine failed")))
+    }
+}
+
+/// True if `next` is `prev` or reachable from it by one legal move.
+fn follows_from(prev: &FenKey, next: &FenKey) -> bool {
+    if prev == next {
+        return true;
+    }
+    let (prev, next) = (prev.position(), next.position());
+    prev.legal_moves().iter().any(|m| {
+        let mut after = prev.clone();
+        after.play_unchecked(m);
+        after.board() == next.board() && after.turn() == next.turn()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::black_to_move;
+
+    #[test]
+    fn test_follows_from() {
+        let start = FenKey::starting_position();
+        let after_e4 = black_to_move();
+        let after_e4_e5 =
+            FenKey::parse("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2").unwrap();
+        assert!(follows_from(&start, &start));
+        assert!(follows_from(&start, &after_e4));
+        assert!(follows_from(&FenKey::starting_position(), &after_e4));
+        assert!(follows_from(&after_e4, &after_e4_e5));
+        assert!(!follows_from(&start, &after_e4_e5));
+        assert!(!follows_from(&after_e4, &start));
+    }
+}
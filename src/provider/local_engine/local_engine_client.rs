@@ -0,0 +1,202 @@
+//! Local UCI engine quality provider (e.g. Stockfish as a subprocess).
+//! Requests are spread over a pool of engines that are started lazily and
+//! restarted when they crash or stop responding.
+
+use anyhow::Context;
+use async_trait::async_trait;
+use std::time::Duration;
+
+use crate::{
+    config::QualityConfig,
+    domain::{EvalLine, FenKey},
+    provider::{
+        MoveQuality, QualityCaps,
+        local_engine::{EnginePool, EngineSettings, SearchLimits},
+    },
+};
+
+pub struct LocalEngineClient {
+    cfg: QualityConfig,
+    pool: EnginePool,
+}
+
+impl LocalEngineClient {
+    /// Create a client for `cfg.engine_path`; fails if no engine is configured.
+    pub fn new(cfg: QualityConfig) -> anyhow::Result<Self> {
+        let path = cfg
+            .engine_path
+            .clone()
+            .context("quality.engine_path must be set for source = \"uci\"")?;
+        let settings = EngineSettings {
+            path,
+            hash_mb: cfg.engine_hash_mb,
+            threads: cfg.engine_threads,
+            limits: SearchLimits {
+                depth: cfg.engine_depth,
+                movetime_ms: cfg.engine_movetime_ms,
+            },
+            timeout: cfg.engine_timeout_ms.map(Duration::from_millis),
+        };
+        let pool = EnginePool::new(settings, cfg.engine_pool_size.unwrap_or(1));
+        Ok(Self { cfg, pool })
+    }
+}
+
+#[async_trait]
+impl MoveQuality for LocalEngineClient {
+    async fn evaluate(
+        &self,
+        fen: &FenKey,
+        multipv: Option<usize>,
+    ) -> anyhow::Result<Vec<EvalLine>> {
+        let pv = multipv.unwrap_or(self.cfg.multi_pv);
+        self.pool.analyse(fen, pv).await
+    }
+
+    fn caps(&self) -> QualityCaps {
+        QualityCaps {
+            max_multipv: self.cfg.multi_pv,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{domain::Eval, test_support::black_to_move};
+
+    fn fake_engine_path() -> String {
+        format!(
+            "{}/tests/fixtures/fake_uci_engine.sh",
+            env!("CARGO_MANIFEST_DIR")
+        )
+    }
+
+    fn client(multi_pv: usize) -> LocalEngineClient {
+        pooled_client(multi_pv, 1, None)
+    }
+
+    fn pooled_client(multi_pv: usize, size: usize, timeout_ms: Option<u64>) -> LocalEngineClient {
+        let cfg = QualityConfig::builder()
+            .source("uci".to_string())
+            .multi_pv(multi_pv)
+            .engine_path(Some(fake_engine_path()))
+            .engine_depth(Some(12))
+            .engine_hash_mb(Some(16))
+            .engine_threads(Some(1))
+            .engine_pool_size(Some(size))
+            .engine_timeout_ms(timeout_ms)
+            .build()
+            .unwrap();
+        LocalEngineClient::new(cfg).unwrap()
+    }
+
+    /// A position the fake engine treats specially (see the script).
+    fn fake_fen(name: &str) -> FenKey {
+        let fen = match name {
+            "slow" => "8/8/8/4k3/8/8/8/4K3 w - - 0 1",
+            "deaf" => "8/8/8/4k3/8/8/8/3QK3 w - - 0 1",
+            "crash" => "8/8/8/4k3/8/8/8/3RK3 w - - 0 1",
+            _ => unreachable!("no special position '{name}'"),
+        };
+        FenKey::parse(fen).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_with_fake_engine() {
+        let client = client(3);
+        let lines = client
+            .evaluate(&FenKey::starting_position(), None)
+            .await
+            .unwrap();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].uci.to_uci(), "e2e4");
+        // The lowerbound line at depth 13 is ignored in favour of the exact depth-12 score.
+        assert_eq!(lines[0].eval, Eval::Cp(35));
+        assert_eq!(lines[0].depth, 12);
+        assert_eq!(lines[0].pv.len(), 3);
+        assert_eq!(lines[1].uci.to_uci(), "d2d4");
+        assert_eq!(lines[2].eval, Eval::Mate(5));
+    }
+
+    #[tokio::test]
+    async fn test_multipv_is_applied_per_request() {
+        let client = client(3);
+        let fen = FenKey::starting_position();
+        assert_eq!(client.evaluate(&fen, Some(1)).await.unwrap().len(), 1);
+        assert_eq!(client.evaluate(&fen, Some(2)).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_black_to_move_scores_are_white_positive() {
+        let client = client(1);
+        let fen = black_to_move();
+        let lines = client.evaluate(&fen, None).await.unwrap();
+        assert_eq!(lines[0].eval, Eval::Cp(-35));
+    }
+
+    #[tokio::test]
+    async fn test_missing_engine_binary_is_an_error() {
+        let cfg = QualityConfig::builder()
+            .engine_path(Some("/nonexistent/stockfish".to_string()))
+            .build()
+            .unwrap();
+        let client = LocalEngineClient::new(cfg).unwrap();
+        assert!(
+            client
+                .evaluate(&FenKey::starting_position(), None)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pool_serves_concurrent_requests() {
+        let client = pooled_client(2, 2, None);
+        let start = FenKey::starting_position();
+        let after_e4 = black_to_move();
+        let (a, b) = tokio::join!(
+            client.evaluate(&start, None),
+            client.evaluate(&after_e4, None)
+        );
+        assert_eq!(a.unwrap()[0].eval, Eval::Cp(35));
+        assert_eq!(b.unwrap()[0].eval, Eval::Cp(-35));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_returns_best_line_so_far() {
+        let client = pooled_client(1, 1, Some(200));
+        let lines = client.evaluate(&fake_fen("slow"), None).await.unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].uci.to_uci(), "c2c4");
+        assert_eq!(lines[0].depth, 5);
+    }
+
+    #[tokio::test]
+    async fn test_unresponsive_engine_is_replaced() {
+        let client = pooled_client(1, 1, Some(100));
+        assert!(client.evaluate(&fake_fen("deaf"), None).await.is_err());
+        let lines = client
+            .evaluate(&FenKey::starting_position(), None)
+            .await
+            .unwrap();
+        assert_eq!(lines[0].uci.to_uci(), "e2e4");
+    }
+
+    #[tokio::test]
+    async fn test_crashed_engine_is_restarted() {
+        let client = client(1);
+        assert!(client.evaluate(&fake_fen("crash"), None).await.is_err());
+        let lines = client
+            .evaluate(&FenKey::starting_position(), None)
+            .await
+            .unwrap();
+        assert_eq!(lines[0].uci.to_uci(), "e2e4");
+    }
+
+    #[test]
+    fn test_engine_path_is_required() {
+        let cfg = QualityConfig::builder().build().unwrap();
+        assert!(LocalEngineClient::new(cfg).is_err());
+    }
+}
@@ -0,0 +1,9 @@
+pub mod engine_pool;
+pub mod local_engine_client;
+pub mod uci_engine;
+pub mod uci_info;
+
+pub use engine_pool::{EnginePool, EngineSettings};
+pub use local_engine_client::LocalEngineClient;
+pub use uci_engine::{SearchLimits, UciEngine};
+pub use uci_info::{EngineScore, UciInfo, parse_info_line};
@@ -0,0 +1,271 @@
+//! A single UCI engine subprocess (e.g. Stockfish) driven over stdin/stdout.
+
+use anyhow::{Context, Result, anyhow};
+use std::{collections::BTreeMap, fmt::Display, process::Stdio, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines},
+    process::{Child, ChildStdin, ChildStdout, Command},
+    time::timeout,
+};
+use tracing::debug;
+
+use crate::{
+    domain::{Eval, EvalLine, FenKey, PieceColor},
+    provider::local_engine::uci_info::{EngineScore, UciInfo, parse_info_line},
+};
+
+/// Search depth used when neither a depth nor a movetime is configured.
+pub const DEFAULT_ENGINE_DEPTH: u32 = 20;
+
+/// How long an engine gets to answer `uci`/`isready` after starting.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long an engine gets to print `bestmove` after being sent `stop`.
+const STOP_GRACE: Duration = Duration::from_secs(2);
+
+/// When to stop a search: at a fixed depth, after a fixed time, or whichever comes first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub movetime_ms: Option<u64>,
+}
+
+impl SearchLimits {
+    /// The `go` command for these limits.
+    /// # Examples
+    /// ```
+    /// use repgrow::provider::local_engine::SearchLimits;
+    /// assert_eq!(SearchLimits { depth: Some(18), movetime_ms: None }.go_command(), "go depth 18");
+    /// assert_eq!(SearchLimits { depth: None, movetime_ms: Some(500) }.go_command(), "go movetime 500");
+    /// assert_eq!(SearchLimits { depth: Some(18), movetime_ms: Some(500) }.go_command(), "go depth 18 movetime 500");
+    /// assert_eq!(SearchLimits::default().go_command(), "go depth 20");
+    /// ```
+    pub fn go_command(&self) -> String {
+        match (self.depth, self.movetime_ms) {
+            (Some(d), Some(t)) => format!("go depth {d} movetime {t}"),
+            (Some(d), None) => format!("go depth {d}"),
+            (None, Some(t)) => format!("go movetime {t}"),
+            (None, None) => format!("go depth {DEFAULT_ENGINE_DEPTH}"),
+        }
+    }
+}
+
+/// A running UCI engine that has completed the `uci`/`isready` handshake.
+pub struct UciEngine {
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    multipv: usize,
+}
+
+impl UciEngine {
+    /// Start the engine binary at `path` and apply the Hash/Threads options if given.
+    pub async fn spawn(path: &str, hash_mb: Option<usize>, threads: Option<usize>) -> Result<Self> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("failed to start UCI engine '{path}'"))?;
+        let stdin = child.stdin.take().context("UCI engine stdin unavailable")?;
+        let stdout = child
+            .stdout
+            .take()
+            .context("UCI engine stdout unavailable")?;
+        let mut engine = Self {
+            _child: child,
+            stdin,
+            stdout: BufReader::new(stdout).lines(),
+            multipv: 1,
+        };
+        timeout(HANDSHAKE_TIMEOUT, engine.handshake(hash_mb, threads))
+            .await
+            .map_err(|_| anyhow!("UCI engine '{path}' did not complete the handshake"))??;
+        debug!("UCI engine '{}' ready", path);
+        Ok(engine)
+    }
+
+    async fn handshake(&mut self, hash_mb: Option<usize>, threads: Option<usize>) -> Result<()> {
+        self.send("uci").await?;
+        self.wait_for("uciok").await?;
+        if let Some(hash) = hash_mb {
+            self.set_option("Hash", hash).await?;
+        }
+        if let Some(threads) = threads {
+            self.set_option("Threads", threads).await?;
+        }
+        self.sync().await
+    }
+
+    /// Send one command line to the engine.
+    pub async fn send(&mut self, cmd: &str) -> Result<()> {
+        self.stdin.write_all(cmd.as_bytes()).await?;
+        self.stdin.write_all(b"\n").await?;
+        self.stdin.flush().await?;
+        Ok(())
+    }
+
+    async fn read_line(&mut self) -> Result<String> {
+        self.stdout
+            .next_line()
+            .await?
+            .ok_or_else(|| anyhow!("UCI engine closed its output"))
+    }
+
+    async fn wait_for(&mut self, token: &str) -> Result<()> {
+        while self.read_line().await?.trim() != token {}
+        Ok(())
+    }
+
+    pub async fn set_option(&mut self, name: &str, value: impl Display) -> Result<()> {
+        self.send(&format!("setoption name {name} value {value}"))
+            .await
+    }
+
+    /// Round-trip `isready`/`readyok` so all previous commands have been processed.
+    pub async fn sync(&mut self) -> Result<()> {
+        self.send("isready").await?;
+        self.wait_for("readyok").await
+    }
+
+    /// Tell the engine the next position is unrelated to the previous one,
+    /// which clears its hash table.
+    pub async fn new_game(&mut self) -> Result<()> {
+        self.send("ucinewgame").await?;
+        self.sync().await
+    }
+
+    /// Search `fen` with `multipv` lines and return the final exact score of each line,
+    /// converted to White's perspective and ordered by multipv index.
+    ///
+    /// With a `time_budget`, a search still running when the budget expires is sent
+    /// `stop` and whatever lines were reported so far are returned. An engine that
+    /// ignores `stop` is reported as an error and should be discarded.
+    pub async fn analyse(
+        &mut self,
+        fen: &FenKey,
+        multipv: usize,
+        limits: &SearchLimits,
+        time_budget: Option<Duration>,
+    ) -> Result<Vec<EvalLine>> {
+        let multipv = multipv.max(1);
+        if multipv != self.multipv {
+            self.set_option("MultiPV", multipv).await?;
+            self.multipv = multipv;
+        }
+        self.send(&position_command(fen)).await?;
+        self.send(&limits.go_command()).await?;
+
+        let mut lines: BTreeMap<usize, UciInfo> = BTreeMap::new();
+        match time_budget {
+            None => self.collect_until_bestmove(&mut lines, multipv).await?,
+            Some(budget) => {
+                if let Ok(res) =
+                    timeout(budget, self.collect_until_bestmove(&mut lines, multipv)).await
+                {
+                    res?;
+                } else {
+                    debug!("UCI search exceeded {:?}, sending stop", budget);
+                    self.send("stop").await?;
+                    timeout(STOP_GRACE, self.collect_until_bestmove(&mut lines, multipv))
+                        .await
+                        .map_err(|_| anyhow!("UCI engine did not answer stop"))??;
+                }
+            }
+        }
+        Ok(lines
+            .into_values()
+            .map(|info| to_eval_line(info, fen.side_to_move()))
+            .collect())
+    }
+
+    /// Read search output until `bestmove`. Later info lines supersede earlier ones
+    /// for the same multipv slot. Safe to cancel and resume between lines.
+    async fn collect_until_bestmove(
+        &mut self,
+        lines: &mut BTreeMap<usize, UciInfo>,
+        multipv: usize,
+    ) -> Result<()> {
+        loop {
+            let line = self.read_line().await?;
+            if line.starts_with("bestmove") {
+                return Ok(());
+            }
+            if let Some(info) = parse_info_line(&line)
+                && !info.bound
+                && info.multipv <= multipv
+            {
+                lines.insert(info.multipv, info);
+            }
+        }
+    }
+}
+
+/// The `position` command for a FenKey.
+fn position_command(fen: &FenKey) -> String {
+    if fen.is_starting_position() {
+        "position startpos".to_string()
+    } else {
+        format!("position fen {}", fen.fen_string())
+    }
+}
+
+/// UCI scores are relative to the side to move; EvalLine scores are White-positive.
+fn to_eval_line(info: UciInfo, side_to_move: PieceColor) -> EvalLine {
+    let sign = if side_to_move.is_white() { 1 } else { -1 };
+    let eval = match info.score {
+        EngineScore::Cp(cp) => Eval::Cp(sign * cp),
+        EngineScore::Mate(m) => Eval::Mate(sign * m),
+    };
+    EvalLine {
+        uci: info.pv[0].clone(),
+        eval,
+        depth: u8::try_from(info.depth).unwrap_or(u8::MAX),
+        pv: info.pv,
+        source: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::chess::UciMove,
+        test_support::{BLACK_TO_MOVE, black_to_move},
+    };
+
+    #[test]
+    fn test_position_command() {
+        let start = FenKey::parse("startpos").unwrap();
+        assert_eq!(position_command(&start), "position startpos");
+        assert_eq!(
+            position_command(&FenKey::starting_position()),
+            "position startpos"
+        );
+        let e4 = black_to_move();
+        assert_eq!(
+            position_command(&e4),
+            format!("position fen {BLACK_TO_MOVE}")
+        );
+    }
+
+    #[test]
+    fn test_to_eval_line_flips_score_for_black() {
+        let info = UciInfo {
+            depth: 300,
+            multipv: 1,
+            score: EngineScore::Cp(40),
+            bound: false,
+            pv: vec![UciMove::from_uci("e7e5").unwrap()],
+        };
+        let line = to_eval_line(info.clone(), PieceColor::Black);
+        assert_eq!(line.eval, Eval::Cp(-40));
+        assert_eq!(line.depth, u8::MAX);
+        let mate = UciInfo {
+            score: EngineScore::Mate(2),
+            ..info
+        };
+        assert_eq!(to_eval_line(mate, PieceColor::Black).eval, Eval::Mate(-2));
+    }
+}
@@ -0,0 +1,113 @@
+//! Parser for UCI `info` lines, e.g.
+//! `info depth 20 seldepth 28 multipv 2 score cp -15 nodes 123 pv e7e5 g1f3`.
+
+use crate::domain::chess::UciMove;
+
+/// Engine score from the side-to-move's perspective, as reported by the engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EngineScore {
+    Cp(i32),
+    Mate(i32),
+}
+
+/// The parts of an `info` line needed to build an `EvalLine`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UciInfo {
+    pub depth: u32,
+    pub multipv: usize,
+    pub score: EngineScore,
+    /// True for `lowerbound`/`upperbound` scores, which are not exact.
+    pub bound: bool,
+    pub pv: Vec<UciMove>,
+}
+
+/// Parse an `info` line carrying a score and a PV. Returns None for any other
+/// line (`info string ...`, `info currmove ...`, `bestmove ...`, etc.).
+pub fn parse_info_line(line: &str) -> Option<UciInfo> {
+    let mut tokens = line.split_whitespace();
+    if tokens.next()? != "info" {
+        return None;
+    }
+    let mut depth = None;
+    let mut multipv = 1;
+    let mut score = None;
+    let mut bound = false;
+    let mut pv = Vec::new();
+    while let Some(tok) = tokens.next() {
+        match tok {
+            "depth" => depth = tokens.next()?.parse().ok(),
+            "multipv" => multipv = tokens.next()?.parse().ok()?,
+            "score" => {
+                let kind = tokens.next()?;
+                let value: i32 = tokens.next()?.parse().ok()?;
+                score = match kind {
+                    "cp" => Some(EngineScore::Cp(value)),
+                    "mate" => Some(EngineScore::Mate(value)),
+                    _ => return None,
+                };
+            }
+            "lowerbound" | "upperbound" => bound = true,
+            "pv" => {
+                for m in tokens.by_ref() {
+                    pv.push(UciMove::from_uci(m).ok()?);
+                }
+            }
+            // Free text runs to the end of the line.
+            "string" => break,
+            _ => {}
+        }
+    }
+    if pv.is_empty() {
+        return None;
+    }
+    Some(UciInfo {
+        depth: depth?,
+        multipv,
+        score: score?,
+        bound,
+        pv,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cp_line() {
+        let info = parse_info_line(
+            "info depth 20 seldepth 28 multipv 2 score cp -15 nodes 123 nps 1000 hashfull 3 tbhits 0 time 12 pv e7e5 g1f3",
+        )
+        .unwrap();
+        assert_eq!(info.depth, 20);
+        assert_eq!(info.multipv, 2);
+        assert_eq!(info.score, EngineScore::Cp(-15));
+        assert!(!info.bound);
+        assert_eq!(info.pv.len(), 2);
+        assert_eq!(info.pv[0].to_uci(), "e7e5");
+    }
+
+    #[test]
+    fn test_parse_mate_line_with_wdl() {
+        let info =
+            parse_info_line("info depth 30 score mate -3 wdl 0 0 1000 pv e1g1 a7a8q").unwrap();
+        assert_eq!(info.multipv, 1);
+        assert_eq!(info.score, EngineScore::Mate(-3));
+        assert_eq!(info.pv[1].to_uci(), "a7a8q");
+    }
+
+    #[test]
+    fn test_parse_bound_line() {
+        let info =
+            parse_info_line("info depth 5 multipv 1 score cp 40 lowerbound pv d2d4").unwrap();
+        assert!(info.bound);
+    }
+
+    #[test]
+    fn test_non_pv_lines_are_ignored() {
+        assert!(parse_info_line("info string NNUE evaluation enabled").is_none());
+        assert!(parse_info_line("info depth 10 currmove e2e4 currmovenumber 1").is_none());
+        assert!(parse_info_line("bestmove e2e4 ponder e7e5").is_none());
+        assert!(parse_info_line("info depth 3 score cp 10").is_none());
+    }
+}
@@ -1,9 +1,14 @@
 use tracing::debug;
 // debug!("build_quality called with engine/source: {:?}", cfg.engine);
 pub mod cloud_eval;
+pub mod composite;
+pub mod eval_db;
 pub mod explorer;
+pub mod local_engine;
 pub mod move_popularity;
 pub mod move_quality;
+pub mod pgn_database;
+pub mod polyglot;
 pub mod popularity;
 pub mod popularity_caps;
 pub mod quality;
@@ -11,9 +16,14 @@ pub mod quality_caps;
 pub mod types;
 
 pub use cloud_eval::LichessEvalClient;
+pub use composite::{FallbackQuality, MergedPopularity};
+pub use eval_db::EvalDbClient;
 pub use explorer::Explorer;
+pub use local_engine::LocalEngineClient;
 pub use move_popularity::MovePopularity;
 pub use move_quality::MoveQuality;
+pub use pgn_database::{LocalExplorer, PgnPopularity};
+pub use polyglot::PolyglotPopularity;
 pub use popularity_caps::PopularityCaps;
 pub use quality_caps::QualityCaps;
 pub use types::CandidateMoves;
@@ -22,39 +32,102 @@ use crate::{
     config::{PopularityConfig, QualityConfig},
     domain::{CandidateMove, FenKey, PopularityRow, Signals},
     infra::Infra,
-    provider::{cloud_eval::build_lichess_eval_client, types::EvalLines},
+    provider::{
+        cloud_eval::build_lichess_eval_client,
+        composite::{MergeBy, MergedSource},
+        types::EvalLines,
+    },
 };
 use std::sync::Arc;
 
 /// Factory: late-bind providers from config.
-pub fn build_quality(cfg: &QualityConfig, _infra: &Infra) -> anyhow::Result<Arc<dyn MoveQuality>> {
+pub fn build_quality(cfg: &QualityConfig, infra: &Infra) -> anyhow::Result<Arc<dyn MoveQuality>> {
     let client = build_lichess_eval_client(&cfg.base_url, cfg.multi_pv, cfg.clone());
     debug!("build_quality called with source: {:?}", cfg.source);
     match cfg.source.as_str() {
         "cloud" => Ok(Arc::new(client)),
+        "uci" => Ok(Arc::new(LocalEngineClient::new(cfg.clone())?)),
+        "evaldb" => Ok(Arc::new(EvalDbClient::from_config(cfg.clone())?)),
+        "chain" => build_quality_chain(cfg, infra),
         other => anyhow::bail!("unknown quality provider '{other}'"),
     }
 }
+
+/// Build a `FallbackQuality` over `cfg.chain`; each member shares the rest of `cfg`.
+fn build_quality_chain(cfg: &QualityConfig, infra: &Infra) -> anyhow::Result<Arc<dyn MoveQuality>> {
+    if cfg.chain.is_empty() {
+        anyhow::bail!("quality.chain must list at least one source for source = \"chain\"");
+    }
+    let sources = cfg
+        .chain
+        .iter()
+        .map(|name| {
+            if name == "chain" {
+                anyhow::bail!("quality.chain cannot contain \"chain\"");
+            }
+            let member = QualityConfig {
+                source: name.clone(),
+                ..cfg.clone()
+            };
+            Ok((name.clone(), build_quality(&member, infra)?))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Arc::new(FallbackQuality::new(sources, cfg.min_depth)))
+}
+
 pub fn build_popularity(
     cfg: &PopularityConfig,
     infra: &Infra,
 ) -> anyhow::Result<Arc<dyn MovePopularity>> {
     match cfg.source.as_str() {
         "explorer" => Ok(Arc::new(Explorer::new(cfg.clone(), infra.clone()))),
+        "pgn" => Ok(Arc::new(PgnPopularity::from_config(cfg)?)),
+        "index" => Ok(Arc::new(LocalExplorer::from_config(cfg)?)),
+        "polyglot" => Ok(Arc::new(PolyglotPopularity::from_config(cfg)?)),
+        "merge" => build_merged_popularity(cfg, infra),
         other => anyhow::bail!("unknown popularity provider '{other}'"),
     }
 }
 
+/// Build a `MergedPopularity` over `cfg.merge`; each entry overrides parts of `cfg`.
+fn build_merged_popularity(
+    cfg: &PopularityConfig,
+    infra: &Infra,
+) -> anyhow::Result<Arc<dyn MovePopularity>> {
+    if cfg.merge.is_empty() {
+        anyhow::bail!("popularity.merge must list at least one source for source = \"merge\"");
+    }
+    let sources = cfg
+        .merge
+        .iter()
+        .map(|member| {
+            if member.source == "merge" {
+                anyhow::bail!("popularity.merge cannot contain \"merge\"");
+            }
+            if member.weight() < 0.0 {
+                anyhow::bail!("popularity.merge weights must not be negative");
+            }
+            Ok(MergedSource {
+                name: member.name().to_string(),
+                weight: member.weight(),
+                provider: build_popularity(&member.apply(cfg), infra)?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let merge_by = MergeBy::parse(cfg.merge_by.as_deref())?;
+    Ok(Arc::new(MergedPopularity::new(sources, merge_by)))
+}
+
 /// Normalize specialized outputs into unified CandidateMove.
 pub fn normalize_quality(fen: &FenKey, lines: EvalLines) -> CandidateMoves {
     lines
         .into_iter()
         .map(|l| {
             let sig = Signals {
-                play_rate: None,
-                games: None,
-                eval_cp: Some(l.eval_cp),
+                eval: Some(l.eval),
                 depth: Some(l.depth),
+                source: l.source,
+                ..Default::default()
             };
             // next_fen is filled by orchestrator using shakmaty (legal move application)
             CandidateMove {
@@ -72,6 +145,10 @@ pub fn normalize_popularity(fen: &FenKey, rows: Vec<PopularityRow>) -> Candidate
             let sig = Signals {
                 play_rate: Some(r.play_rate),
                 games: Some(r.games),
+                wdl: r.wdl,
+                expected_score: r.wdl.and_then(|w| w.expected_score(fen.side_to_move())),
+                avg_rating: r.avg_rating,
+                games_by_source: r.games_by_source,
                 ..Default::default()
             };
             CandidateMove {
@@ -82,3 +159,105 @@ pub fn normalize_popularity(fen: &FenKey, rows: Vec<PopularityRow>) -> Candidate
         })
         .collect()
 }
+
+/// Join engine lines and explorer rows by move, so each candidate carries eval,
+/// play rate and game results together. Engine moves come first in engine order,
+/// followed by moves only the explorer knows about (without an eval).
+pub fn normalize_hybrid(
+    fen: &FenKey,
+    lines: EvalLines,
+    rows: Vec<PopularityRow>,
+) -> CandidateMoves {
+    let mut cands = normalize_quality(fen, lines);
+    for popular in normalize_popularity(fen, rows) {
+        let Some(c) = cands.iter_mut().find(|c| c.uci == popular.uci) else {
+            cands.push(popular);
+            continue;
+        };
+        let p = popular.signals;
+        c.signals.play_rate = p.play_rate;
+        c.signals.games = p.games;
+        c.signals.wdl = p.wdl;
+        c.signals.expected_score = p.expected_score;
+        c.signals.avg_rating = p.avg_rating;
+        c.signals.games_by_source = p.games_by_source;
+    }
+    cands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::{PlayRate, Wdl, chess::UciMove},
+        test_support::black_to_move,
+    };
+
+    #[test]
+    fn test_normalize_popularity_scores_from_mover_perspective() {
+        let after_e4 = black_to_move();
+        let rows = vec![
+            PopularityRow {
+                uci: UciMove::from_uci("c7c5").unwrap(),
+                play_rate: PlayRate::new(0.6),
+                games: 10,
+                wdl: Some(Wdl::new(3, 2, 5)),
+                avg_rating: Some(1800),
+                games_by_source: Vec::new(),
+            },
+            PopularityRow {
+                uci: UciMove::from_uci("a7a6").unwrap(),
+                play_rate: PlayRate::new(0.4),
+                games: 4,
+                wdl: None,
+                avg_rating: None,
+                games_by_source: Vec::new(),
+            },
+        ];
+        let cands = normalize_popularity(&after_e4, rows);
+        let c5 = &cands[0].signals;
+        assert_eq!(c5.wdl, Some(Wdl::new(3, 2, 5)));
+        assert_eq!(c5.expected_score, Some(0.6));
+        assert_eq!(c5.avg_rating, Some(1800));
+        assert_eq!(cands[1].signals.expected_score, None);
+    }
+
+    #[test]
+    fn test_normalize_hybrid_joins_by_move() {
+        use crate::domain::{Eval, EvalLine};
+
+        let fen = FenKey::starting_position();
+        let line = |uci: &str, cp: i32| EvalLine {
+            uci: UciMove::from_uci(uci).unwrap(),
+            eval: Eval::Cp(cp),
+            depth: 20,
+            pv: Vec::new(),
+            source: Some("cloud".to_string()),
+        };
+        let row = |uci: &str, rate: f32, games: u32| PopularityRow {
+            uci: UciMove::from_uci(uci).unwrap(),
+            play_rate: PlayRate::new(rate),
+            games,
+            wdl: Some(Wdl::new(games as u64, 0, 0)),
+            avg_rating: None,
+            games_by_source: Vec::new(),
+        };
+        let cands = normalize_hybrid(
+            &fen,
+            vec![line("e2e4", 30), line("g1f3", 20)],
+            vec![row("d2d4", 0.4, 40), row("e2e4", 0.5, 50)],
+        );
+
+        let ucis: Vec<String> = cands.iter().map(|c| c.uci.to_uci()).collect();
+        assert_eq!(ucis, vec!["e2e4", "g1f3", "d2d4"]);
+        let e4 = &cands[0].signals;
+        assert_eq!(e4.eval, Some(Eval::Cp(30)));
+        assert_eq!(e4.source.as_deref(), Some("cloud"));
+        assert_eq!(e4.play_rate, Some(PlayRate::new(0.5)));
+        assert_eq!(e4.games, Some(50));
+        assert_eq!(e4.expected_score, Some(1.0));
+        assert_eq!(cands[1].signals.play_rate, None);
+        assert_eq!(cands[2].signals.eval, None);
+        assert_eq!(cands[2].signals.games, Some(40));
+    }
+}
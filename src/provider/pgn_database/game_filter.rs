@@ -0,0 +1,200 @@
+//! Applies the `PopularityConfig` rating, speed, date and variant filters to PGN games
+//! using their header tags, mirroring what the Lichess explorer filters on.
+//! A game whose headers do not say enough to check a filter is rejected.
+
+use crate::{config::PopularityConfig, pgn::PgnGame};
+
+#[derive(Debug, Clone)]
+pub struct GameFilter {
+    /// Accepted speed categories; `None` accepts every speed.
+    speeds: Option<Vec<String>>,
+    min_rating: u32,
+    max_rating: u32,
+    since_year: u32,
+    variant: String,
+}
+
+impl GameFilter {
+    pub fn new(cfg: &PopularityConfig) -> Self {
+        let speeds = if cfg.speed.is_empty() || cfg.speed == "all" {
+            None
+        } else {
+            Some(
+                cfg.speed
+                    .split(',')
+                    .map(|s| s.trim().to_ascii_lowercase())
+                    .collect(),
+            )
+        };
+        Self {
+            speeds,
+            min_rating: cfg.min_rating,
+            max_rating: cfg.max_rating,
+            since_year: cfg.since_year,
+            variant: normalize_variant(&cfg.variant),
+        }
+    }
+
+    pub fn accepts(&self, game: &PgnGame) -> bool {
+        self.accepts_variant(game)
+            && self.accepts_rating(game)
+            && self.accepts_speed(game)
+            && self.accepts_date(game)
+    }
+
+    pub fn accepts_variant(&self, game: &PgnGame) -> bool {
+        normalize_variant(game.header("Variant").unwrap_or("standard")) == self.variant
+    }
+
+    /// The mean rating of the players must fall inside `[min_rating, max_rating]`.
+    pub fn accepts_rating(&self, game: &PgnGame) -> bool {
+        mean_rating(game).is_some_and(|mean| (self.min_rating..=self.max_rating).contains(&mean))
+    }
+
+    pub fn accepts_speed(&self, game: &PgnGame) -> bool {
+        let Some(speeds) = &self.speeds else {
+            return true;
+        };
+        game.header("TimeControl")
+            .and_then(speed_of)
+            .is_some_and(|speed| speeds.iter().any(|s| s.eq_ignore_ascii_case(speed)))
+    }
+
+    pub fn accepts_date(&self, game: &PgnGame) -> bool {
+        game.header("UTCDate")
+            .or_else(|| game.header("Date"))
+            .and_then(|date| date.get(..4)?.parse::<u32>().ok())
+            .is_some_and(|year| year >= self.since_year)
+    }
+}
+
+/// Mean of the players' `WhiteElo` / `BlackElo`, or the one that is known.
+pub fn mean_rating(game: &PgnGame) -> Option<u32> {
+    let ratings: Vec<u32> = ["WhiteElo", "BlackElo"]
+        .iter()
+        .filter_map(|tag| game.header(tag)?.parse().ok())
+        .collect();
+    if ratings.is_empty() {
+        return None;
+    }
+    Some(ratings.iter().sum::<u32>() / ratings.len() as u32)
+}
+
+/// Lichess speed categories, fastest first.
+pub const SPEEDS: [&str; 6] = [
+    "ultraBullet",
+    "bullet",
+    "blitz",
+    "rapid",
+    "classical",
+    "correspondence",
+];
+
+/// Lichess speed category for a PGN `TimeControl` value such as `"180+2"`.
+/// Uses the Lichess estimate of base time plus 40 increments.
+pub fn speed_of(time_control: &str) -> Option<&'static str> {
+    if time_control == "-" || time_control.contains('/') {
+        return Some("correspondence");
+    }
+    let (base, inc) = time_control.split_once('+').unwrap_or((time_control, "0"));
+    let estimate = base.parse::<u32>().ok()? + 40 * inc.parse::<u32>().ok()?;
+    Some(match estimate {
+        0..30 => "ultraBullet",
+        30..180 => "bullet",
+        180..480 => "blitz",
+        480..1500 => "rapid",
+        _ => "classical",
+    })
+}
+
+/// Lower-case and drop spaces; a custom start position still counts as standard chess.
+fn normalize_variant(variant: &str) -> String {
+    let v: String = variant
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if v == "fromposition" {
+        "standard".to_string()
+    } else {
+        v
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(headers: &[(&str, &str)]) -> PgnGame {
+        PgnGame {
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            moves: vec![],
+        }
+    }
+
+    fn filter(speed: &str) -> GameFilter {
+        GameFilter::new(
+            &PopularityConfig::builder()
+                .speed(speed.to_string())
+                .min_rating(1200)
+                .max_rating(1800)
+                .since_year(2020)
+                .build()
+                .unwrap(),
+        )
+    }
+
+    const CLUB_BLITZ: &[(&str, &str)] = &[
+        ("WhiteElo", "1500"),
+        ("BlackElo", "1700"),
+        ("TimeControl", "180+2"),
+        ("Date", "2022.05.01"),
+    ];
+
+    #[test]
+    fn test_speed_of() {
+        assert_eq!(speed_of("15+0"), Some("ultraBullet"));
+        assert_eq!(speed_of("60+0"), Some("bullet"));
+        assert_eq!(speed_of("180+2"), Some("blitz"));
+        assert_eq!(speed_of("600+5"), Some("rapid"));
+        assert_eq!(speed_of("1800"), Some("classical"));
+        assert_eq!(speed_of("-"), Some("correspondence"));
+        assert_eq!(speed_of("abc"), None);
+    }
+
+    #[test]
+    fn test_accepts_matching_game() {
+        assert!(filter("all").accepts(&game(CLUB_BLITZ)));
+        assert!(filter("blitz,rapid").accepts(&game(CLUB_BLITZ)));
+        assert!(!filter("classical").accepts(&game(CLUB_BLITZ)));
+    }
+
+    #[test]
+    fn test_rating_and_date_filters() {
+        let strong = game(&[
+            ("WhiteElo", "2400"),
+            ("BlackElo", "2300"),
+            ("Date", "2022.01.01"),
+        ]);
+        assert!(!filter("all").accepts(&strong));
+        let old = game(&[("WhiteElo", "1500"), ("Date", "2015.01.01")]);
+        assert!(!filter("all").accepts(&old));
+        let undated = game(&[("WhiteElo", "1500"), ("Date", "????.??.??")]);
+        assert!(!filter("all").accepts(&undated));
+        let unrated = game(&[("Date", "2022.01.01")]);
+        assert!(!filter("all").accepts(&unrated));
+    }
+
+    #[test]
+    fn test_variant_filter() {
+        let mut headers = CLUB_BLITZ.to_vec();
+        headers.push(("Variant", "From Position"));
+        assert!(filter("all").accepts(&game(&headers)));
+        headers.pop();
+        headers.push(("Variant", "Chess960"));
+        assert!(!filter("all").accepts(&game(&headers)));
+    }
+}
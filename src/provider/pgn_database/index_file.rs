@@ -0,0 +1,717 @@
+//! Compact on-disk opening index, built once from PGN dumps by `repgrow index` and
+//! memory-mapped by `LocalExplorer`.
+//!
+//! For every position (keyed by its 64-bit Zobrist hash) and every move played from it,
+//! the index stores White wins, draws, Black wins and the sum of mean player ratings,
+//! bucketed by explorer rating band and speed, so rating and speed filters can be
+//! applied at lookup time. Variant and `since_year` are applied while indexing.
+//!
+//! The index is a `KeyedFile` with magic `RGOI` and the since_year as its `meta`,
+//! holding one blob per position. Blob layout, all integers little endian: move
+//! count `u16`, then per move: packed move `u16`, bucket count `u8`, then per bucket:
+//! bucket `u8`, white `u32`, draws `u32`, black `u32`, rating sum `u64`.
+
+use anyhow::{Context, Result, anyhow};
+use shakmaty::{Move, Role, Square, uci::Uci};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap, HashMap},
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::PopularityConfig,
+    domain::{PositionKey, Wdl, chess::UciMove},
+    infra::keyed_file::{KeyedFile, KeyedFileWriter},
+    pgn::{PgnGame, PgnReader},
+    provider::{
+        explorer::{EXPLORER_RATING_BANDS, rating_bands},
+        pgn_database::{
+            GameFilter,
+            game_filter::{SPEEDS, mean_rating, speed_of},
+            opening_index::for_each_mainline_move,
+        },
+    },
+};
+
+const MAGIC: [u8; 4] = *b"RGOI";
+
+/// Result counts and rating sum for a move, in one bucket or summed over several.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResultCounts {
+    pub white: u64,
+    pub draws: u64,
+    pub black: u64,
+    /// Sum over games of the players' mean rating.
+    pub rating_sum: u64,
+}
+
+impl ResultCounts {
+    pub fn games(&self) -> u64 {
+        self.white + self.draws + self.black
+    }
+
+    /// Average rating of the games, if there are any.
+    pub fn average_rating(&self) -> Option<u32> {
+        let games = self.games();
+        (games > 0).then(|| (self.rating_sum / games) as u32)
+    }
+
+    pub fn wdl(&self) -> Wdl {
+        Wdl::new(self.white, self.draws, self.black)
+    }
+
+    pub fn add(&mut self, other: &ResultCounts) {
+        self.white += other.white;
+        self.draws += other.draws;
+        self.black += other.black;
+        self.rating_sum += other.rating_sum;
+    }
+
+    fn record(&mut self, result: &Wdl, rating: u32) {
+        self.white += result.white;
+        self.draws += result.draws;
+        self.black += result.black;
+        self.rating_sum += u64::from(rating);
+    }
+}
+
+/// Bucket id for a game: rating band index times the number of speeds plus speed index.
+pub fn bucket_of(rating: u32, speed: &str) -> Option<u8> {
+    let band = EXPLORER_RATING_BANDS.iter().rposition(|&lo| lo <= rating)?;
+    let speed = SPEEDS.iter().position(|s| *s == speed)?;
+    u8::try_from(band * SPEEDS.len() + speed).ok()
+}
+
+/// The buckets selected by the rating and speed filters of a `PopularityConfig`.
+#[derive(Debug, Clone)]
+pub struct BucketFilter {
+    selected: Vec<bool>,
+}
+
+impl BucketFilter {
+    pub fn new(cfg: &PopularityConfig) -> Self {
+        let bands = rating_bands(cfg.min_rating, cfg.max_rating);
+        let speeds: Vec<&str> = if cfg.speed.is_empty() || cfg.speed == "all" {
+            SPEEDS.to_vec()
+        } else {
+            cfg.speed.split(',').map(str::trim).collect()
+        };
+        let mut selected = vec![false; EXPLORER_RATING_BANDS.len() * SPEEDS.len()];
+        for band in bands {
+            for speed in &speeds {
+                if let Some(b) = SPEEDS
+                    .iter()
+                    .find(|s| s.eq_ignore_ascii_case(speed))
+                    .and_then(|s| bucket_of(band, s))
+                {
+                    selected[usize::from(b)] = true;
+                }
+            }
+        }
+        Self { selected }
+    }
+
+    pub fn contains(&self, bucket: u8) -> bool {
+        self.selected
+            .get(usize::from(bucket))
+            .copied()
+            .unwrap_or(false)
+    }
+}
+
+/// Pack a move as `from | to << 6 | promotion role << 12`.
+fn pack_move(mv: &Move) -> u16 {
+    match Uci::from_standard(mv) {
+        Uci::Normal {
+            from,
+            to,
+            promotion,
+        } => u16::from(from) | (u16::from(to) << 6) | (promotion.map_or(0, |r| r as u16) << 12),
+        // Drops and null moves do not occur in standard chess.
+        _ => 0,
+    }
+}
+
+fn unpack_move(packed: u16) -> Result<UciMove> {
+    let from = Square::try_from(u32::from(packed & 0x3f))?;
+    let to = Square::try_from(u32::from((packed >> 6) & 0x3f))?;
+    let promotion = match packed >> 12 {
+        0 => None,
+        r => Some(Role::try_from(r as u8)?),
+    };
+    let uci = Uci::Normal {
+        from,
+        to,
+        promotion,
+    }
+    .to_string();
+    UciMove::from_uci(&uci).map_err(|_| anyhow!("opening index holds bad move '{uci}'"))
+}
+
+type MoveBuckets = HashMap<u16, BTreeMap<u8, ResultCounts>>;
+
+/// Positions `IndexBuilder` keeps in memory before spilling them to disk.
+pub const DEFAULT_BUFFER_POSITIONS: usize = 1_000_000;
+
+/// Indexes PGN games and writes the index file.
+///
+/// Positions are accumulated in memory up to a limit, then written to a run file
+/// (`<out>.run<N>`) sorted by key. `write` merges the runs into the index, so memory
+/// stays bounded however large the dumps are.
+pub struct IndexBuilder {
+    filter: GameFilter,
+    since_year: u32,
+    max_plies: usize,
+    out: PathBuf,
+    buffer_positions: usize,
+    positions: HashMap<u64, MoveBuckets>,
+    runs: Vec<PathBuf>,
+    games: u64,
+}
+
+impl IndexBuilder {
+    /// Index the first `max_plies` plies of games matching the variant and
+    /// `since_year` of `cfg` into `out`. Rating and speed are kept as buckets.
+    pub fn new(cfg: &PopularityConfig, max_plies: usize, out: &Path) -> Self {
+        Self {
+            filter: GameFilter::new(cfg),
+            since_year: cfg.since_year,
+            max_plies,
+            out: out.to_path_buf(),
+            buffer_positions: DEFAULT_BUFFER_POSITIONS,
+            positions: HashMap::new(),
+            runs: Vec::new(),
+            games: 0,
+        }
+    }
+
+    /// Keep at most `positions` positions in memory before spilling them to disk.
+    pub fn with_buffer_positions(mut self, positions: usize) -> Self {
+        self.buffer_positions = positions.max(1);
+        self
+    }
+
+    /// Number of games indexed so far.
+    pub fn games(&self) -> u64 {
+        self.games
+    }
+
+    /// Index one game. Returns false if it was skipped because of its variant or
+    /// date, or because its result, rating or time control is unknown.
+    pub fn add_game(&mut self, game: &PgnGame) -> Result<bool> {
+        if !self.filter.accepts_variant(game) || !self.filter.accepts_date(game) {
+            return Ok(false);
+        }
+        let Some(result) = game.header("Result").and_then(Wdl::from_pgn_result) else {
+            return Ok(false);
+        };
+        let Some(rating) = mean_rating(game) else {
+            return Ok(false);
+        };
+        let Some(bucket) = game
+            .header("TimeControl")
+            .and_then(speed_of)
+            .and_then(|speed| bucket_of(rating, speed))
+        else {
+            return Ok(false);
+        };
+        let positions = &mut self.positions;
+        for_each_mainline_move(game, self.max_plies, |pos, mv| {
+            positions
+                .entry(PositionKey::of(pos).0)
+                .or_default()
+                .entry(pack_move(mv))
+                .or_default()
+                .entry(bucket)
+                .or_default()
+                .record(&result, rating);
+        });
+        self.games += 1;
+        if self.positions.len() >= self.buffer_positions {
+            self.spill()?;
+        }
+        Ok(true)
+    }
+
+    /// Index every game of a PGN file. Returns `(games read, games indexed)`.
+    pub fn add_pgn_file(&mut self, path: &str) -> Result<(u64, u64)> {
+        let file = File::open(path).with_context(|| format!("opening PGN file {path}"))?;
+        let (mut read, mut indexed) = (0, 0);
+        for game in PgnReader::new(BufReader::new(file)) {
+            let game = game.with_context(|| format!("reading PGN file {path}"))?;
+            read += 1;
+            if self.add_game(&game)? {
+                indexed += 1;
+            }
+        }
+        Ok((read, indexed))
+    }
+
+    /// Write the positions in memory to a new run file, sorted by key.
+    fn spill(&mut self) -> Result<()> {
+        if self.positions.is_empty() {
+            return Ok(());
+        }
+        let path = sibling(&self.out, &format!("run{}", self.runs.len()));
+        let file =
+            File::create(&path).with_context(|| format!("creating run file {}", path.display()))?;
+        let mut out = BufWriter::new(file);
+        let mut positions: Vec<(u64, MoveBuckets)> = self.positions.drain().collect();
+        positions.sort_unstable_by_key(|(key, _)| *key);
+        let mut blob = Vec::new();
+        for (key, moves) in positions {
+            blob.clear();
+            encode_position(&moves, &mut blob);
+            out.write_all(&key.to_le_bytes())?;
+            out.write_all(&(blob.len() as u32).to_le_bytes())?;
+            out.write_all(&blob)?;
+        }
+        out.flush()?;
+        self.runs.push(path);
+        Ok(())
+    }
+
+    /// Write the index, dropping positions reached by fewer than `min_games` games.
+    /// Returns the number of positions written.
+    ///
+    /// The runs are merged one position at a time, in key order, so the index file
+    /// is written as it goes.
+    pub fn write(mut self, min_games: u64) -> Result<usize> {
+        self.spill()?;
+        let mut out = KeyedFileWriter::create(&self.out, MAGIC)?.with_meta(self.since_year);
+        let mut merge = RunMerge::open(&self.runs)?;
+        let mut blob = Vec::new();
+        while let Some((key, moves)) = merge.next()? {
+            if position_games(&moves) < min_games.max(1) {
+                continue;
+            }
+            blob.clear();
+            encode_position(&moves, &mut blob);
+            out.append(key, &blob)?;
+        }
+        let positions = out.finish()?;
+        for path in &self.runs {
+            fs::remove_file(path)?;
+        }
+        Ok(positions)
+    }
+}
+
+/// `<path>.<suffix>`, for the builder's temporary files.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Reads the `(key, position)` records of a run file in order.
+struct RunReader {
+    reader: BufReader<File>,
+}
+
+impl RunReader {
+    fn next(&mut self) -> Result<Option<(u64, MoveBuckets)>> {
+        let mut key = [0; 8];
+        match self.reader.read_exact(&mut key) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        let mut len = [0; 4];
+        self.reader.read_exact(&mut len)?;
+        let mut blob = vec![0; u32::from_le_bytes(len) as usize];
+        self.reader.read_exact(&mut blob)?;
+        let mut moves = MoveBuckets::new();
+        read_position(&mut ByteReader::new(&blob, 0), |mv, bucket, counts| {
+            moves.entry(mv).or_default().insert(bucket, counts);
+        })?;
+        Ok(Some((u64::from_le_bytes(key), moves)))
+    }
+}
+
+/// K-way merge of sorted run files, summing the positions that appear in several.
+struct RunMerge {
+    runs: Vec<RunReader>,
+    /// The next record of each run, keyed for the smallest key first.
+    heads: BinaryHeap<Reverse<(u64, usize)>>,
+    pending: Vec<Option<MoveBuckets>>,
+}
+
+impl RunMerge {
+    fn open(paths: &[PathBuf]) -> Result<Self> {
+        let mut merge = Self {
+            runs: Vec::with_capacity(paths.len()),
+            heads: BinaryHeap::with_capacity(paths.len()),
+            pending: Vec::with_capacity(paths.len()),
+        };
+        for path in paths {
+            let file =
+                File::open(path).with_context(|| format!("opening run file {}", path.display()))?;
+            merge.runs.push(RunReader {
+                reader: BufReader::new(file),
+            });
+            merge.pending.push(None);
+            merge.advance(merge.runs.len() - 1)?;
+        }
+        Ok(merge)
+    }
+
+    fn advance(&mut self, run: usize) -> Result<()> {
+        if let Some((key, moves)) = self.runs[run].next()? {
+            self.heads.push(Reverse((key, run)));
+            self.pending[run] = Some(moves);
+        }
+        Ok(())
+    }
+
+    /// The next position in key order, with the counts of every run summed.
+    fn next(&mut self) -> Result<Option<(u64, MoveBuckets)>> {
+        let Some(Reverse((key, run))) = self.heads.pop() else {
+            return Ok(None);
+        };
+        let mut moves = self.pending[run].take().expect("head has a record");
+        self.advance(run)?;
+        while let Some(&Reverse((next, run))) = self.heads.peek() {
+            if next != key {
+                break;
+            }
+            self.heads.pop();
+            let more = self.pending[run].take().expect("head has a record");
+            for (mv, buckets) in more {
+                let into = moves.entry(mv).or_default();
+                for (bucket, counts) in buckets {
+                    into.entry(bucket).or_default().add(&counts);
+                }
+            }
+            self.advance(run)?;
+        }
+        Ok(Some((key, moves)))
+    }
+}
+
+fn position_games(moves: &MoveBuckets) -> u64 {
+    moves
+        .values()
+        .flat_map(BTreeMap::values)
+        .map(ResultCounts::games)
+        .sum()
+}
+
+fn encode_position(moves: &MoveBuckets, data: &mut Vec<u8>) {
+    let mut packed: Vec<&u16> = moves.keys().collect();
+    packed.sort_unstable();
+    let count = u16::try_from(packed.len()).unwrap_or(u16::MAX);
+    data.extend_from_slice(&count.to_le_bytes());
+    for mv in packed.into_iter().take(usize::from(count)) {
+        let buckets = &moves[mv];
+        data.extend_from_slice(&mv.to_le_bytes());
+        data.push(buckets.len() as u8);
+        for (bucket, counts) in buckets {
+            data.push(*bucket);
+            for n in [counts.white, counts.draws, counts.black] {
+                let n = u32::try_from(n).unwrap_or(u32::MAX);
+                data.extend_from_slice(&n.to_le_bytes());
+            }
+            data.extend_from_slice(&counts.rating_sum.to_le_bytes());
+        }
+    }
+}
+
+/// Read one position of the data section, calling `visit` with the counts of each
+/// move and bucket in the order they are stored (by packed move, then bucket).
+fn read_position(r: &mut ByteReader, mut visit: impl FnMut(u16, u8, ResultCounts)) -> Result<()> {
+    for _ in 0..r.u16()? {
+        let packed = r.u16()?;
+        for _ in 0..r.u8()? {
+            let bucket = r.u8()?;
+            let counts = ResultCounts {
+                white: u64::from(r.u32()?),
+                draws: u64::from(r.u32()?),
+                black: u64::from(r.u32()?),
+                rating_sum: r.u64()?,
+            };
+            visit(packed, bucket, counts);
+        }
+    }
+    Ok(())
+}
+
+/// Bounds-checked little-endian reads over a position's blob.
+struct ByteReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(buf: &'a [u8], pos: usize) -> Self {
+        Self { buf, pos }
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let bytes = self
+            .pos
+            .checked_add(N)
+            .and_then(|end| self.buf.get(self.pos..end))
+            .ok_or_else(|| anyhow!("opening index is truncated"))?;
+        self.pos += N;
+        Ok(bytes.try_into()?)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+}
+
+/// A memory-mapped opening index.
+#[derive(Debug)]
+pub struct OpeningIndexFile {
+    file: KeyedFile,
+}
+
+impl OpeningIndexFile {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = KeyedFile::open(path, MAGIC)
+            .with_context(|| format!("opening index file {}", path.display()))?;
+        Ok(Self { file })
+    }
+
+    /// Number of positions in the index.
+    pub fn positions(&self) -> usize {
+        self.file.len()
+    }
+
+    /// Earliest game year included when the index was built.
+    pub fn since_year(&self) -> u32 {
+        self.file.meta()
+    }
+
+    /// Moves played from the position with `key`, summed over the buckets `filter`
+    /// selects. Moves with no games in those buckets are left out.
+    pub fn moves(
+        &self,
+        key: PositionKey,
+        filter: &BucketFilter,
+    ) -> Result<Vec<(UciMove, ResultCounts)>> {
+        let Some(blob) = self.file.get(key.0)? else {
+            return Ok(Vec::new());
+        };
+        let mut moves: Vec<(u16, ResultCounts)> = Vec::new();
+        read_position(&mut ByteReader::new(blob, 0), |packed, bucket, counts| {
+            if moves.last().is_none_or(|(mv, _)| *mv != packed) {
+                moves.push((packed, ResultCounts::default()));
+            }
+            if filter.contains(bucket) {
+                moves.last_mut().expect("just pushed").1.add(&counts);
+            }
+        })?;
+        moves
+            .into_iter()
+            .filter(|(_, total)| total.games() > 0)
+            .map(|(packed, total)| Ok((unpack_move(packed)?, total)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::{CastlingMode, Chess, Position, fen::Fen};
+
+    fn game(headers: &[(&str, &str)], moves: &str) -> PgnGame {
+        PgnGame {
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            moves: moves.split_whitespace().map(str::to_string).collect(),
+        }
+    }
+
+    fn cfg(speed: &str, min_rating: u32, max_rating: u32) -> PopularityConfig {
+        PopularityConfig::builder()
+            .speed(speed.to_string())
+            .min_rating(min_rating)
+            .max_rating(max_rating)
+            .since_year(2020)
+            .build()
+            .unwrap()
+    }
+
+    fn headers<'a>(elo: &'a str, tc: &'a str, result: &'a str) -> Vec<(&'a str, &'a str)> {
+        vec![
+            ("WhiteElo", elo),
+            ("BlackElo", elo),
+            ("TimeControl", tc),
+            ("Result", result),
+            ("Date", "2023.01.01"),
+        ]
+    }
+
+    #[test]
+    fn test_pack_move_round_trip() {
+        let pos = Chess::default();
+        for mv in pos.legal_moves() {
+            let uci = Uci::from_standard(&mv).to_string();
+            assert_eq!(unpack_move(pack_move(&mv)).unwrap().to_uci(), uci);
+        }
+        let pos: Chess = "7k/P7/8/8/8/8/8/K7 w - - 0 1"
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap();
+        let promo = "a7a8q".parse::<Uci>().unwrap().to_move(&pos).unwrap();
+        assert_eq!(unpack_move(pack_move(&promo)).unwrap().to_uci(), "a7a8q");
+    }
+
+    #[test]
+    fn test_bucket_of() {
+        assert_eq!(bucket_of(1500, "ultraBullet"), Some(3 * 6));
+        assert_eq!(bucket_of(2700, "correspondence"), Some(8 * 6 + 5));
+        assert_eq!(bucket_of(1500, "hyperBullet"), None);
+    }
+
+    #[test]
+    fn test_write_open_and_filter() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("openings.idx");
+        let mut builder = IndexBuilder::new(&cfg("all", 0, 3000), 10, &path);
+        let add = |builder: &mut IndexBuilder, headers: &[(&str, &str)], moves| {
+            builder.add_game(&game(headers, moves)).unwrap()
+        };
+        assert!(add(&mut builder, &headers("1500", "180+0", "1-0"), "e4 e5"));
+        assert!(add(&mut builder, &headers("1500", "180+0", "0-1"), "e4 c5"));
+        assert!(add(
+            &mut builder,
+            &headers("2100", "600+0", "1/2-1/2"),
+            "d4 d5"
+        ));
+        // Unfinished and undated games are skipped.
+        assert!(!add(&mut builder, &headers("1500", "180+0", "*"), "e4"));
+        assert!(!add(&mut builder, &[("Result", "1-0")], "e4"));
+        assert_eq!(builder.games(), 3);
+        // The start position and the positions after 1. e4 and 1. d4.
+        assert_eq!(builder.write(1).unwrap(), 3);
+
+        let index = OpeningIndexFile::open(&path).unwrap();
+        assert_eq!(index.since_year(), 2020);
+        let start = PositionKey::of(&Chess::default());
+
+        let all = index
+            .moves(start, &BucketFilter::new(&cfg("all", 0, 3000)))
+            .unwrap();
+        let e4 = all.iter().find(|(m, _)| m.to_uci() == "e2e4").unwrap().1;
+        assert_eq!((e4.white, e4.draws, e4.black), (1, 0, 1));
+        assert_eq!(e4.average_rating(), Some(1500));
+        assert_eq!(all.len(), 2);
+
+        let blitz = index
+            .moves(start, &BucketFilter::new(&cfg("blitz", 0, 3000)))
+            .unwrap();
+        assert_eq!(blitz.len(), 1);
+        let strong = index
+            .moves(start, &BucketFilter::new(&cfg("all", 2000, 3000)))
+            .unwrap();
+        assert_eq!(strong.len(), 1);
+        assert_eq!(strong[0].0.to_uci(), "d2d4");
+
+        assert!(
+            index
+                .moves(PositionKey(42), &BucketFilter::new(&cfg("all", 0, 3000)))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_min_games_prunes_rare_positions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("openings.idx");
+        let mut builder = IndexBuilder::new(&cfg("all", 0, 3000), 10, &path);
+        for moves in ["e4 e5", "e4 c5"] {
+            builder
+                .add_game(&game(&headers("1500", "180+0", "1-0"), moves))
+                .unwrap();
+        }
+        // Only the start position and the position after 1. e4 were reached twice.
+        assert_eq!(builder.write(2).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_spilled_runs_merge_into_the_same_index() {
+        let games = [
+            ("1500", "180+0", "1-0", "e4 e5 Nf3"),
+            ("1500", "180+0", "0-1", "e4 c5 Nf3"),
+            ("2100", "600+0", "1/2-1/2", "d4 d5 c4"),
+            ("1500", "180+0", "1-0", "e4 e5 Nc3"),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let build = |name: &str, buffer_positions| {
+            let path = dir.path().join(name);
+            let mut builder = IndexBuilder::new(&cfg("all", 0, 3000), 10, &path)
+                .with_buffer_positions(buffer_positions);
+            for (elo, tc, result, moves) in games {
+                builder
+                    .add_game(&game(&headers(elo, tc, result), moves))
+                    .unwrap();
+            }
+            // Positions reached only once are dropped after the runs are summed.
+            let written = builder.write(2).unwrap();
+            (written, OpeningIndexFile::open(&path).unwrap())
+        };
+        let (in_memory, whole) = build("whole.idx", 1000);
+        // One position per run, so every position shared by games gets merged.
+        let (spilled, merged) = build("merged.idx", 1);
+        assert_eq!(in_memory, 3);
+        assert_eq!(spilled, in_memory);
+
+        let filter = BucketFilter::new(&cfg("all", 0, 3000));
+        let mut pos = Chess::default();
+        for uci in ["", "e2e4", "e7e5"] {
+            if !uci.is_empty() {
+                let mv = uci.parse::<Uci>().unwrap().to_move(&pos).unwrap();
+                pos.play_unchecked(&mv);
+            }
+            let key = PositionKey::of(&pos);
+            assert_eq!(
+                merged.moves(key, &filter).unwrap(),
+                whole.moves(key, &filter).unwrap()
+            );
+        }
+        let start = merged
+            .moves(PositionKey::of(&Chess::default()), &filter)
+            .unwrap();
+        let e4 = start.iter().find(|(m, _)| m.to_uci() == "e2e4").unwrap().1;
+        assert_eq!((e4.white, e4.draws, e4.black), (2, 0, 1));
+        // Only the index itself is left behind.
+        let mut files: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["merged.idx", "whole.idx"]);
+    }
+
+    #[test]
+    fn test_open_rejects_other_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("not_an_index");
+        std::fs::write(&path, b"[Event \"?\"]").unwrap();
+        assert!(OpeningIndexFile::open(&path).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+//! Self-hosted explorer: popularity from an opening index built by `repgrow index`.
+//! Rating and speed filters come from `PopularityConfig` and are applied per lookup.
+
+use anyhow::Context;
+use async_trait::async_trait;
+use std::path::Path;
+use tracing::warn;
+
+use crate::{
+    config::PopularityConfig,
+    domain::{FenKey, PlayRate, PopularityRow},
+    provider::{
+        MovePopularity, PopularityCaps,
+        pgn_database::{BucketFilter, OpeningIndexFile},
+    },
+};
+
+#[derive(Debug)]
+pub struct LocalExplorer {
+    index: OpeningIndexFile,
+    filter: BucketFilter,
+}
+
+impl LocalExplorer {
+    /// Open `cfg.index_path`; fails if it is unset or not an opening index.
+    pub fn from_config(cfg: &PopularityConfig) -> anyhow::Result<Self> {
+        let path = cfg
+            .index_path
+            .as_deref()
+            .context("popularity.index_path must be set for source = \"index\"")?;
+        let index = OpeningIndexFile::open(Path::new(path))?;
+        if index.since_year() > cfg.since_year {
+            warn!(
+                "opening index {path} only has games from {} on, but since_year is {}",
+                index.since_year(),
+                cfg.since_year
+            );
+        }
+        Ok(Self {
+            index,
+            filter: BucketFilter::new(cfg),
+        })
+    }
+}
+
+#[async_trait]
+impl MovePopularity for LocalExplorer {
+    async fn sample(&self, fen: &FenKey) -> anyhow::Result<Vec<PopularityRow>> {
+        let moves = self.index.moves(fen.key(), &self.filter)?;
+        let total: u64 = moves.iter().map(|(_, c)| c.games()).sum();
+        let mut rows: Vec<PopularityRow> = moves
+            .into_iter()
+            .map(|(uci, counts)| PopularityRow {
+                uci,
+                play_rate: PlayRate::new(counts.games() as f32 / total as f32),
+                games: u32::try_from(counts.games()).unwrap_or(u32::MAX),
+                wdl: Some(counts.wdl()),
+                avg_rating: counts.average_rating(),
+                games_by_source: Vec::new(),
+            })
+            .collect();
+        rows.sort_by_key(|r| std::cmp::Reverse(r.games));
+        Ok(rows)
+    }
+    fn caps(&self) -> PopularityCaps {
+        PopularityCaps {
+            supports_filters: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::pgn_database::IndexBuilder;
+
+    fn fixture_path() -> String {
+        format!(
+            "{}/tests/fixtures/club_games.pgn",
+            env!("CARGO_MANIFEST_DIR")
+        )
+    }
+
+    fn build_index(dir: &Path) -> String {
+        let cfg = PopularityConfig::builder()
+            .since_year(2022)
+            .build()
+            .unwrap();
+        let path = dir.join("club.idx");
+        let mut builder = IndexBuilder::new(&cfg, 20, &path);
+        let (read, indexed) = builder.add_pgn_file(&fixture_path()).unwrap();
+        assert_eq!((read, indexed), (5, 5));
+        builder.write(1).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn explorer(index_path: String, speed: &str, max_rating: u32) -> LocalExplorer {
+        let cfg = PopularityConfig::builder()
+            .source("index".to_string())
+            .index_path(Some(index_path))
+            .speed(speed.to_string())
+            .min_rating(1400)
+            .max_rating(max_rating)
+            .since_year(2022)
+            .build()
+            .unwrap();
+        LocalExplorer::from_config(&cfg).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sample_matches_pgn_provider() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = build_index(dir.path());
+        // The simul game (mean rating 1990) falls in the 1800 band and is excluded
+        // when the range stops below it.
+        let rows = explorer(path, "all", 1700)
+            .sample(&FenKey::starting_position())
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].uci.to_uci(), "e2e4");
+        assert_eq!(rows[0].games, 3);
+        assert!((rows[0].play_rate.value() - 0.75).abs() < 1e-6);
+        assert_eq!(rows[0].wdl, Some(crate::domain::Wdl::new(2, 0, 1)));
+        assert_eq!(rows[0].avg_rating, Some(1518));
+    }
+
+    #[tokio::test]
+    async fn test_rating_and_speed_filters_apply_at_lookup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = build_index(dir.path());
+        let start = FenKey::starting_position();
+        let rows = explorer(path.clone(), "all", 2000)
+            .sample(&start)
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+        let rows = explorer(path, "blitz", 2000).sample(&start).await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].games, 1);
+    }
+
+    #[test]
+    fn test_index_path_is_required() {
+        let cfg = PopularityConfig::builder().build().unwrap();
+        assert!(LocalExplorer::from_config(&cfg).is_err());
+    }
+}
@@ -0,0 +1,13 @@
+pub mod game_filter;
+pub mod index_file;
+pub mod local_explorer;
+pub mod opening_index;
+pub mod pgn_popularity;
+
+pub use game_filter::GameFilter;
+pub use index_file::{
+    BucketFilter, DEFAULT_BUFFER_POSITIONS, IndexBuilder, OpeningIndexFile, ResultCounts,
+};
+pub use local_explorer::LocalExplorer;
+pub use opening_index::{MoveStats, OpeningIndex, PositionStats};
+pub use pgn_popularity::PgnPopularity;
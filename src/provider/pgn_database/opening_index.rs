@@ -0,0 +1,237 @@
+//! In-memory index of move counts per position, built from PGN games.
+//! Positions are keyed by their `PositionKey`, which ignores the move counters, so
+//! the same position reached by different move orders shares one entry. Besides counts, each
+//! move keeps the results and player ratings of its games when the headers give them.
+
+use anyhow::{Result, anyhow};
+use shakmaty::{CastlingMode, Chess, Move, Position, fen::Fen, san::SanPlus, uci::Uci};
+use std::collections::HashMap;
+use tracing::debug;
+
+use crate::{
+    domain::{FenKey, PlayRate, PopularityRow, PositionKey, Wdl, chess::UciMove},
+    pgn::PgnGame,
+    provider::pgn_database::game_filter::mean_rating,
+};
+
+/// Games that reached a position and the moves played from it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PositionStats {
+    pub games: u64,
+    /// Stats per move, keyed by UCI.
+    pub moves: HashMap<String, MoveStats>,
+}
+
+/// Games in which one move was played from a position.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MoveStats {
+    pub games: u64,
+    /// Results of the finished games among them.
+    pub results: Wdl,
+    /// Sum over rated games of the players' mean rating.
+    pub rating_sum: u64,
+    pub rated_games: u64,
+}
+
+impl MoveStats {
+    /// Average rating of the rated games, if there are any.
+    pub fn average_rating(&self) -> Option<u32> {
+        (self.rated_games > 0).then(|| (self.rating_sum / self.rated_games) as u32)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OpeningIndex {
+    positions: HashMap<PositionKey, PositionStats>,
+    /// Positions deeper than this many plies are not indexed; `None` indexes whole games.
+    max_plies: Option<usize>,
+}
+
+impl OpeningIndex {
+    pub fn new(max_plies: Option<usize>) -> Self {
+        Self {
+            positions: HashMap::new(),
+            max_plies,
+        }
+    }
+
+    /// Number of distinct positions in the index.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Add every mainline move of `game`.
+    pub fn add_game(&mut self, game: &PgnGame) {
+        let limit = self.max_plies.unwrap_or(usize::MAX);
+        let result = game.header("Result").and_then(Wdl::from_pgn_result);
+        let rating = mean_rating(game);
+        for_each_mainline_move(game, limit, |pos, mv| {
+            let stats = self.positions.entry(PositionKey::of(pos)).or_default();
+            stats.games += 1;
+            let mv = stats
+                .moves
+                .entry(Uci::from_standard(mv).to_string())
+                .or_default();
+            mv.games += 1;
+            if let Some(result) = &result {
+                mv.results.add(result);
+            }
+            if let Some(rating) = rating {
+                mv.rating_sum += u64::from(rating);
+                mv.rated_games += 1;
+            }
+        });
+    }
+
+    /// Stats for a position, if any indexed game reached it.
+    pub fn stats(&self, fen: &FenKey) -> Option<&PositionStats> {
+        self.positions.get(&fen.key())
+    }
+
+    /// Popularity rows for a position, most played first. Unknown positions have none.
+    pub fn rows(&self, fen: &FenKey) -> Result<Vec<PopularityRow>> {
+        let Some(stats) = self.stats(fen) else {
+            return Ok(Vec::new());
+        };
+        let mut rows = stats
+            .moves
+            .iter()
+            .map(|(uci, mv)| {
+                let uci = UciMove::from_uci(uci)
+                    .map_err(|_| anyhow!("opening index holds bad UCI '{uci}'"))?;
+                Ok(PopularityRow {
+                    uci,
+                    play_rate: PlayRate::new(mv.games as f32 / stats.games as f32),
+                    games: u32::try_from(mv.games).unwrap_or(u32::MAX),
+                    wdl: (mv.results.games() > 0).then_some(mv.results),
+                    avg_rating: mv.average_rating(),
+                    games_by_source: Vec::new(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        rows.sort_by(|a, b| {
+            b.games
+                .cmp(&a.games)
+                .then_with(|| a.uci.to_uci().cmp(&b.uci.to_uci()))
+        });
+        Ok(rows)
+    }
+}
+
+/// Replay the first `max_plies` mainline moves of `game`, calling `f` with each
+/// position and the move played from it. Replay stops at the first illegal or
+/// unparsable move; games with an unreadable `FEN` header are skipped.
+pub fn for_each_mainline_move(game: &PgnGame, max_plies: usize, mut f: impl FnMut(&Chess, &Move)) {
+    let Some(mut pos) = start_position(game) else {
+        debug!("skipping PGN game with bad FEN header");
+        return;
+    };
+    for san in game.moves.iter().take(max_plies) {
+        let Some(mv) = SanPlus::from_ascii(san.as_bytes())
+            .ok()
+            .and_then(|s| s.san.to_move(&pos).ok())
+        else {
+            debug!("stopping PGN game replay at illegal move '{san}'");
+            break;
+        };
+        f(&pos, &mv);
+        pos.play_unchecked(&mv);
+    }
+}
+
+fn start_position(game: &PgnGame) -> Option<Chess> {
+    match game.header("FEN") {
+        Some(fen) => fen
+            .parse::<Fen>()
+            .ok()?
+            .into_position(CastlingMode::Standard)
+            .ok(),
+        None => Some(Chess::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(moves: &str) -> PgnGame {
+        PgnGame {
+            headers: HashMap::new(),
+            moves: moves.split_whitespace().map(str::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn test_counts_and_play_rates() {
+        let mut index = OpeningIndex::new(None);
+        index.add_game(&game("e4 e5 Nf3"));
+        index.add_game(&game("e4 c5"));
+        index.add_game(&game("d4 d5"));
+        index.add_game(&game("e4 e5 Bc4"));
+
+        let rows = index.rows(&FenKey::starting_position()).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].uci.to_uci(), "e2e4");
+        assert_eq!(rows[0].games, 3);
+        assert_eq!(rows[0].play_rate, PlayRate::new(0.75));
+        assert_eq!(rows[1].uci.to_uci(), "d2d4");
+
+        let after_e4_e5 =
+            FenKey::parse("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2").unwrap();
+        let rows = index.rows(&after_e4_e5).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].play_rate, PlayRate::new(0.5));
+    }
+
+    #[test]
+    fn test_results_and_ratings_per_move() {
+        let with_headers = |moves: &str, result: &str, elo: Option<&str>| {
+            let mut g = game(moves);
+            g.headers.insert("Result".to_string(), result.to_string());
+            if let Some(elo) = elo {
+                g.headers.insert("WhiteElo".to_string(), elo.to_string());
+            }
+            g
+        };
+        let mut index = OpeningIndex::new(None);
+        index.add_game(&with_headers("e4 e5", "1-0", Some("1500")));
+        index.add_game(&with_headers("e4 c5", "0-1", Some("1700")));
+        index.add_game(&with_headers("e4 c5", "1/2-1/2", None));
+        index.add_game(&with_headers("d4", "*", None));
+
+        let rows = index.rows(&FenKey::starting_position()).unwrap();
+        assert_eq!(rows[0].uci.to_uci(), "e2e4");
+        assert_eq!(rows[0].wdl, Some(Wdl::new(1, 1, 1)));
+        assert_eq!(rows[0].avg_rating, Some(1600));
+        assert_eq!(rows[1].uci.to_uci(), "d2d4");
+        assert_eq!(rows[1].wdl, None);
+        assert_eq!(rows[1].avg_rating, None);
+    }
+
+    #[test]
+    fn test_transpositions_share_an_entry() {
+        let mut index = OpeningIndex::new(None);
+        index.add_game(&game("Nf3 d5 d4 Nf6"));
+        index.add_game(&game("d4 d5 Nf3 e6"));
+        let after = FenKey::parse("rnbqkbnr/ppp1pppp/8/3p4/3P4/5N2/PPP1PPPP/RNBQKB1R b KQkq - 1 2")
+            .unwrap();
+        assert_eq!(index.stats(&after).unwrap().games, 2);
+    }
+
+    #[test]
+    fn test_max_plies_and_illegal_moves() {
+        let mut index = OpeningIndex::new(Some(1));
+        index.add_game(&game("e4 e5 Nf3"));
+        assert_eq!(index.len(), 1);
+
+        let mut index = OpeningIndex::new(None);
+        index.add_game(&game("e4 Ke3 Nf3"));
+        assert_eq!(index.len(), 1);
+        let startpos = FenKey::starting_position();
+        assert_eq!(index.rows(&startpos).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,130 @@
+//! Offline popularity provider backed by local PGN files.
+//! Games are filtered by their headers and indexed once, when the provider is built.
+
+use anyhow::Context;
+use async_trait::async_trait;
+use std::{fs::File, io::BufReader};
+use tracing::info;
+
+use crate::{
+    config::PopularityConfig,
+    domain::{FenKey, PopularityRow},
+    pgn::PgnReader,
+    provider::{
+        MovePopularity, PopularityCaps,
+        pgn_database::{GameFilter, OpeningIndex},
+    },
+};
+
+#[derive(Debug, Clone)]
+pub struct PgnPopularity {
+    index: OpeningIndex,
+}
+
+impl PgnPopularity {
+    /// Build from an existing index.
+    pub fn new(index: OpeningIndex) -> Self {
+        Self { index }
+    }
+
+    /// Read and index every game in `cfg.pgn_paths` that passes the config filters.
+    pub fn from_config(cfg: &PopularityConfig) -> anyhow::Result<Self> {
+        if cfg.pgn_paths.is_empty() {
+            anyhow::bail!("popularity.pgn_paths must list at least one file for source = \"pgn\"");
+        }
+        let filter = GameFilter::new(cfg);
+        let mut index = OpeningIndex::new(cfg.pgn_max_plies);
+        for path in &cfg.pgn_paths {
+            let file = File::open(path).with_context(|| format!("opening PGN file {path}"))?;
+            let (mut read, mut kept) = (0usize, 0usize);
+            for game in PgnReader::new(BufReader::new(file)) {
+                let game = game.with_context(|| format!("reading PGN file {path}"))?;
+                read += 1;
+                if filter.accepts(&game) {
+                    index.add_game(&game);
+                    kept += 1;
+                }
+            }
+            info!("indexed {kept} of {read} games from {path}");
+        }
+        Ok(Self::new(index))
+    }
+}
+
+#[async_trait]
+impl MovePopularity for PgnPopularity {
+    async fn sample(&self, fen: &FenKey) -> anyhow::Result<Vec<PopularityRow>> {
+        self.index.rows(fen)
+    }
+    fn caps(&self) -> PopularityCaps {
+        PopularityCaps {
+            supports_filters: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_path() -> String {
+        format!(
+            "{}/tests/fixtures/club_games.pgn",
+            env!("CARGO_MANIFEST_DIR")
+        )
+    }
+
+    fn provider(speed: &str) -> PgnPopularity {
+        let cfg = PopularityConfig::builder()
+            .source("pgn".to_string())
+            .pgn_paths(vec![fixture_path()])
+            .speed(speed.to_string())
+            .min_rating(1400)
+            .max_rating(1700)
+            .since_year(2022)
+            .build()
+            .unwrap();
+        PgnPopularity::from_config(&cfg).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sample_from_fixture() {
+        // The simul game is outside the rating range, leaving four games.
+        let rows = provider("all")
+            .sample(&FenKey::starting_position())
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].uci.to_uci(), "e2e4");
+        assert_eq!(rows[0].games, 3);
+        assert!((rows[0].play_rate.value() - 0.75).abs() < 1e-6);
+        assert_eq!(rows[1].uci.to_uci(), "d2d4");
+    }
+
+    #[tokio::test]
+    async fn test_speed_filter_uses_time_control() {
+        let rows = provider("blitz")
+            .sample(&FenKey::starting_position())
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].games, 1);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_position_has_no_rows() {
+        let fen = FenKey::parse("8/8/8/4k3/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(provider("all").sample(&fen).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_missing_files_are_errors() {
+        let cfg = PopularityConfig::builder().build().unwrap();
+        assert!(PgnPopularity::from_config(&cfg).is_err());
+        let cfg = PopularityConfig::builder()
+            .pgn_paths(vec!["/nonexistent/games.pgn".to_string()])
+            .build()
+            .unwrap();
+        assert!(PgnPopularity::from_config(&cfg).is_err());
+    }
+}
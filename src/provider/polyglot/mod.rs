@@ -0,0 +1,5 @@
+pub mod polyglot_book;
+pub mod polyglot_popularity;
+
+pub use polyglot_book::{PolyglotBook, PolyglotEntry, decode_move, encode_move, polyglot_key};
+pub use polyglot_popularity::PolyglotPopularity;
@@ -0,0 +1,279 @@
+//! Polyglot opening book (`.bin`) reader.
+//!
+//! A book is a list of 16-byte big-endian entries sorted by position key:
+//! `key u64, move u16, weight u16, learn u32`. Keys are the Polyglot Zobrist
+//! hash, which is what shakmaty's `Zobrist64` computes when the en passant
+//! square is only hashed if a pawn could capture there. Moves pack
+//! `to file | to rank << 3 | from file << 6 | from rank << 9 | promotion << 12`,
+//! and castling is written as the king capturing its own rook.
+
+use anyhow::{Context, Result, bail};
+use shakmaty::{
+    Chess, EnPassantMode, File, Move, Position, Rank, Role, Square,
+    zobrist::{Zobrist64, ZobristHash},
+};
+use std::path::Path;
+
+use crate::domain::{FenKey, chess::UciMove};
+
+pub const ENTRY_LEN: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PolyglotEntry {
+    pub key: u64,
+    pub mv: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl PolyglotEntry {
+    pub fn from_bytes(b: &[u8; ENTRY_LEN]) -> Self {
+        Self {
+            key: u64::from_be_bytes(b[0..8].try_into().expect("8 bytes")),
+            mv: u16::from_be_bytes(b[8..10].try_into().expect("2 bytes")),
+            weight: u16::from_be_bytes(b[10..12].try_into().expect("2 bytes")),
+            learn: u32::from_be_bytes(b[12..16].try_into().expect("4 bytes")),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; ENTRY_LEN] {
+        let mut b = [0; ENTRY_LEN];
+        b[0..8].copy_from_slice(&self.key.to_be_bytes());
+        b[8..10].copy_from_slice(&self.mv.to_be_bytes());
+        b[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        b[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        b
+    }
+}
+
+/// A whole book held in memory, sorted by key.
+#[derive(Debug, Clone, Default)]
+pub struct PolyglotBook {
+    entries: Vec<PolyglotEntry>,
+}
+
+impl PolyglotBook {
+    pub fn open(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("reading Polyglot book {}", path.display()))?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if !bytes.len().is_multiple_of(ENTRY_LEN) {
+            bail!("Polyglot book size is not a multiple of {ENTRY_LEN} bytes");
+        }
+        let mut entries: Vec<PolyglotEntry> = bytes
+            .chunks_exact(ENTRY_LEN)
+            .map(|c| PolyglotEntry::from_bytes(c.try_into().expect("exact chunk")))
+            .collect();
+        // Books should already be sorted; sorting keeps lookups correct if one is not.
+        entries.sort_by_key(|e| e.key);
+        Ok(Self { entries })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// All entries for a position key.
+    pub fn entries_for(&self, key: u64) -> &[PolyglotEntry] {
+        let start = self.entries.partition_point(|e| e.key < key);
+        let end = start + self.entries[start..].partition_point(|e| e.key == key);
+        &self.entries[start..end]
+    }
+
+    /// Book moves for a position with their weights normalized to sum to 1.
+    /// Moves that are illegal in the position are dropped; zero-weight moves are kept
+    /// with a share of 0.
+    pub fn weighted_moves(&self, fen: &FenKey) -> Result<Vec<(UciMove, u16, f32)>> {
+        let pos = fen.position();
+        let moves: Vec<(UciMove, u16)> = self
+            .entries_for(polyglot_key(&pos))
+            .iter()
+            .filter_map(|e| Some((decode_move(e.mv, &pos)?, e.weight)))
+            .collect();
+        let total: u32 = moves.iter().map(|(_, w)| u32::from(*w)).sum();
+        Ok(moves
+            .into_iter()
+            .map(|(uci, w)| {
+                let share = if total > 0 {
+                    f32::from(w) / total as f32
+                } else {
+                    0.0
+                };
+                (uci, w, share)
+            })
+            .collect())
+    }
+}
+
+/// Polyglot key of a position.
+pub fn polyglot_key(pos: &Chess) -> u64 {
+    pos.zobrist_hash::<Zobrist64>(EnPassantMode::PseudoLegal).0
+}
+
+fn square(file: u16, rank: u16) -> Square {
+    Square::from_coords(File::new(u32::from(file)), Rank::new(u32::from(rank)))
+}
+
+/// Encode a move the Polyglot way; castling becomes the king capturing its rook.
+pub fn encode_move(mv: &Move) -> u16 {
+    let (from, to) = match *mv {
+        Move::Castle { king, rook } => (king, rook),
+        _ => (
+            mv.from().expect("standard chess moves have an origin"),
+            mv.to(),
+        ),
+    };
+    let promotion = match mv.promotion() {
+        Some(Role::Knight) => 1,
+        Some(Role::Bishop) => 2,
+        Some(Role::Rook) => 3,
+        Some(Role::Queen) => 4,
+        _ => 0,
+    };
+    u16::from(to.file())
+        | (u16::from(to.rank()) << 3)
+        | (u16::from(from.file()) << 6)
+        | (u16::from(from.rank()) << 9)
+        | (promotion << 12)
+}
+
+/// Decode a Polyglot move into standard UCI, converting king-takes-rook castling.
+/// Returns `None` if the move is not legal in `pos`.
+pub fn decode_move(mv: u16, pos: &Chess) -> Option<UciMove> {
+    let from = square((mv >> 6) & 7, (mv >> 9) & 7);
+    let mut to = square(mv & 7, (mv >> 3) & 7);
+    let promotion = match (mv >> 12) & 7 {
+        0 => None,
+        1 => Some(Role::Knight),
+        2 => Some(Role::Bishop),
+        3 => Some(Role::Rook),
+        4 => Some(Role::Queen),
+        _ => return None,
+    };
+    if pos.board().role_at(from) == Some(Role::King)
+        && pos.board().color_at(from) == pos.board().color_at(to)
+        && from.rank() == to.rank()
+    {
+        let file = if to.file() > from.file() {
+            File::G
+        } else {
+            File::C
+        };
+        to = Square::from_coords(file, from.rank());
+    }
+    let uci = shakmaty::uci::Uci::Normal {
+        from,
+        to,
+        promotion,
+    };
+    uci.to_move(pos).ok()?;
+    UciMove::from_uci(&uci.to_string()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::{CastlingMode, fen::Fen, san::San};
+
+    fn play(sans: &str) -> Chess {
+        let mut pos = Chess::default();
+        for san in sans.split_whitespace() {
+            let mv = san.parse::<San>().unwrap().to_move(&pos).unwrap();
+            pos.play_unchecked(&mv);
+        }
+        pos
+    }
+
+    #[test]
+    fn test_polyglot_keys_match_reference_values() {
+        // Reference keys from the Polyglot book format specification.
+        assert_eq!(polyglot_key(&play("")), 0x463b96181691fc9c);
+        assert_eq!(polyglot_key(&play("e4")), 0x823c9b50fd114196);
+        assert_eq!(polyglot_key(&play("e4 d5")), 0x0756b94461c50fb0);
+        assert_eq!(polyglot_key(&play("e4 d5 e5")), 0x662fafb965db29d4);
+        assert_eq!(polyglot_key(&play("e4 d5 e5 f5")), 0x22a48b5a8e47ff78);
+        assert_eq!(polyglot_key(&play("e4 d5 e5 f5 Ke2")), 0x652a607ca3f242c1);
+        assert_eq!(polyglot_key(&play("a4 b5 h4 b4 c4")), 0x3c8123ea7b067637);
+    }
+
+    #[test]
+    fn test_entry_round_trip() {
+        let e = PolyglotEntry {
+            key: 0x463b96181691fc9c,
+            mv: 0x031c,
+            weight: 7,
+            learn: 0,
+        };
+        assert_eq!(PolyglotEntry::from_bytes(&e.to_bytes()), e);
+    }
+
+    #[test]
+    fn test_decode_moves_and_castling() {
+        let start = play("");
+        // e2e4: to e4 (file 4, rank 3), from e2 (file 4, rank 1).
+        let e2e4 = 4 | (3 << 3) | (4 << 6) | (1 << 9);
+        assert_eq!(decode_move(e2e4, &start).unwrap().to_uci(), "e2e4");
+        // Illegal in the start position.
+        let e2e5 = 4 | (4 << 3) | (4 << 6) | (1 << 9);
+        assert!(decode_move(e2e5, &start).is_none());
+
+        let castle_ready = play("e4 e5 Nf3 Nc6 Bc4 Bc5");
+        // e1h1 is Polyglot for short castling.
+        let e1h1 = 7 | (4 << 6);
+        assert_eq!(decode_move(e1h1, &castle_ready).unwrap().to_uci(), "e1g1");
+    }
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        for pos in [
+            play(""),
+            play("e4 e5 Nf3 Nc6 Bc4 Bc5"),
+            play("d4 d5 Nc3 Nc6 Bf4 Bf5 Qd2 Qd7"),
+        ] {
+            for mv in pos.legal_moves() {
+                let uci = shakmaty::uci::Uci::from_standard(&mv).to_string();
+                assert_eq!(decode_move(encode_move(&mv), &pos).unwrap().to_uci(), uci);
+            }
+        }
+        let promo: Chess = "7k/P7/8/8/8/8/8/K7 w - - 0 1"
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap();
+        for mv in promo.legal_moves() {
+            let uci = shakmaty::uci::Uci::from_standard(&mv).to_string();
+            assert_eq!(decode_move(encode_move(&mv), &promo).unwrap().to_uci(), uci);
+        }
+    }
+
+    #[test]
+    fn test_weighted_moves_normalize() {
+        let start = play("");
+        let key = polyglot_key(&start);
+        let e2e4 = 4 | (3 << 3) | (4 << 6) | (1 << 9);
+        let d2d4 = 3 | (3 << 3) | (3 << 6) | (1 << 9);
+        let mut bytes = Vec::new();
+        for (mv, weight) in [(e2e4, 30), (d2d4, 10)] {
+            let e = PolyglotEntry {
+                key,
+                mv,
+                weight,
+                learn: 0,
+            };
+            bytes.extend_from_slice(&e.to_bytes());
+        }
+        let book = PolyglotBook::from_bytes(&bytes).unwrap();
+        let moves = book.weighted_moves(&FenKey::starting_position()).unwrap();
+        assert_eq!(moves.len(), 2);
+        assert_eq!(moves[0].0.to_uci(), "e2e4");
+        assert!((moves[0].2 - 0.75).abs() < 1e-6);
+        assert!(PolyglotBook::from_bytes(&bytes[..10]).is_err());
+    }
+}
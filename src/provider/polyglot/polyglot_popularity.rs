@@ -0,0 +1,116 @@
+//! Popularity from a curated Polyglot book: normalized book weights become play rates.
+
+use anyhow::Context;
+use async_trait::async_trait;
+use std::path::Path;
+
+use crate::{
+    config::PopularityConfig,
+    domain::{FenKey, PlayRate, PopularityRow},
+    provider::{MovePopularity, PopularityCaps, polyglot::PolyglotBook},
+};
+
+#[derive(Debug, Clone)]
+pub struct PolyglotPopularity {
+    book: PolyglotBook,
+}
+
+impl PolyglotPopularity {
+    pub fn new(book: PolyglotBook) -> Self {
+        Self { book }
+    }
+
+    /// Load `cfg.book_path`; fails if it is unset or unreadable.
+    pub fn from_config(cfg: &PopularityConfig) -> anyhow::Result<Self> {
+        let path = cfg
+            .book_path
+            .as_deref()
+            .context("popularity.book_path must be set for source = \"polyglot\"")?;
+        Ok(Self::new(PolyglotBook::open(Path::new(path))?))
+    }
+}
+
+#[async_trait]
+impl MovePopularity for PolyglotPopularity {
+    /// Rows in descending play-rate order. A book weight is not a game count, so
+    /// `games` is 0 and merges that pool games leave these rows out of the count.
+    async fn sample(&self, fen: &FenKey) -> anyhow::Result<Vec<PopularityRow>> {
+        let mut rows: Vec<PopularityRow> = self
+            .book
+            .weighted_moves(fen)?
+            .into_iter()
+            .map(|(uci, _weight, share)| PopularityRow {
+                uci,
+                play_rate: PlayRate::new(share),
+                games: 0,
+                wdl: None,
+                avg_rating: None,
+                games_by_source: Vec::new(),
+            })
+            .collect();
+        rows.sort_by(|a, b| b.play_rate.compare(&a.play_rate));
+        Ok(rows)
+    }
+    fn caps(&self) -> PopularityCaps {
+        PopularityCaps {
+            supports_filters: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        provider::polyglot::{PolyglotEntry, polyglot_key},
+        test_support::black_to_move,
+    };
+    use shakmaty::Chess;
+
+    #[tokio::test]
+    async fn test_sample_from_book_file() {
+        let key = polyglot_key(&Chess::default());
+        // d2d4 weight 1, e2e4 weight 3, g1f3 weight 0.
+        let entries = [
+            (3 | (3 << 3) | (3 << 6) | (1 << 9), 1),
+            (4 | (3 << 3) | (4 << 6) | (1 << 9), 3),
+            (5 | (2 << 3) | (6 << 6), 0),
+        ];
+        let mut bytes = Vec::new();
+        for (mv, weight) in entries {
+            let e = PolyglotEntry {
+                key,
+                mv,
+                weight,
+                learn: 0,
+            };
+            bytes.extend_from_slice(&e.to_bytes());
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("book.bin");
+        std::fs::write(&path, bytes).unwrap();
+
+        let cfg = PopularityConfig::builder()
+            .source("polyglot".to_string())
+            .book_path(Some(path.to_string_lossy().into_owned()))
+            .build()
+            .unwrap();
+        let provider = PolyglotPopularity::from_config(&cfg).unwrap();
+        let rows = provider.sample(&FenKey::starting_position()).await.unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].uci.to_uci(), "e2e4");
+        assert_eq!(rows[0].play_rate, PlayRate::new(0.75));
+        assert_eq!(rows[1].uci.to_uci(), "d2d4");
+        assert_eq!(rows[2].play_rate, PlayRate::new(0.0));
+        assert!(rows.iter().all(|r| r.games == 0));
+
+        let after_e4 = black_to_move();
+        assert!(provider.sample(&after_e4).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_book_path_is_required() {
+        let cfg = PopularityConfig::builder().build().unwrap();
+        assert!(PolyglotPopularity::from_config(&cfg).is_err());
+    }
+}
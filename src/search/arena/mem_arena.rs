@@ -1,50 +1,215 @@
-use std::sync::Arc;
+use anyhow::{Result, ensure};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 use tokio::sync::Mutex;
 
-use crate::{domain::RepertoireNode, search::arena::NodeArenaStore};
+use crate::{
+    domain::{FenKey, ParentLink, PositionKey, RepertoireNode, Signals},
+    search::arena::NodeArenaStore,
+};
 
-/// In-memory arena backed by Arc<Mutex<Vec<RepertoireNode>>>
+/// In-memory arena: nodes in a Vec indexed by id, plus the first node seen for
+/// each position and the set of nodes already expanded.
 #[derive(Clone, Default)]
 pub struct MemArena {
-    inner: Arc<Mutex<Vec<RepertoireNode>>>,
+    inner: Arc<Mutex<ArenaState>>,
+}
+
+#[derive(Default)]
+struct ArenaState {
+    nodes: Vec<RepertoireNode>,
+    by_position: HashMap<PositionKey, u64>,
+    expanded: HashSet<u64>,
+}
+
+impl ArenaState {
+    fn push(&mut self, mut node: RepertoireNode) -> u64 {
+        let id = self.nodes.len() as u64;
+        node.id = id;
+        self.by_position.entry(node.fen_key.key()).or_insert(id);
+        self.nodes.push(node);
+        id
+    }
+
+    fn push_child(&mut self, parent: u64, child_id: u64) {
+        if let Some(p) = self.nodes.get_mut(parent as usize) {
+            p.children.push(child_id);
+        }
+    }
 }
 
 impl MemArena {
     pub fn new() -> Self {
-        Self {
-            inner: Arc::new(Mutex::new(Vec::new())),
-        }
+        Self::default()
     }
     /// Returns a clone of all nodes currently in the arena.
     pub async fn all_nodes(&self) -> Vec<RepertoireNode> {
         let g = self.inner.lock().await;
-        g.clone()
+        g.nodes.clone()
+    }
+
+    /// All nodes, plus the ids of those not expanded yet (the frontier), taken
+    /// together so a node is never counted as expanded without its children.
+    pub async fn snapshot(&self) -> (Vec<RepertoireNode>, Vec<u64>) {
+        let g = self.inner.lock().await;
+        let frontier = g
+            .nodes
+            .iter()
+            .map(|n| n.id)
+            .filter(|id| !g.expanded.contains(id))
+            .collect();
+        (g.nodes.clone(), frontier)
+    }
+
+    /// Replaces the contents with a `snapshot`: every node outside `frontier` counts as
+    /// expanded, and the position index is rebuilt from the nodes.
+    pub async fn restore(&self, nodes: Vec<RepertoireNode>, frontier: &[u64]) -> Result<()> {
+        let mut state = ArenaState::default();
+        for (i, node) in nodes.into_iter().enumerate() {
+            ensure!(
+                node.id == i as u64,
+                "node {} stored at index {}",
+                node.id,
+                i
+            );
+            state.push(node);
+        }
+        let frontier: HashSet<u64> = frontier.iter().copied().collect();
+        state.expanded = (0..state.nodes.len() as u64)
+            .filter(|id| !frontier.contains(id))
+            .collect();
+        *self.inner.lock().await = state;
+        Ok(())
     }
 }
 
 #[async_trait::async_trait]
 impl NodeArenaStore for MemArena {
     async fn len(&self) -> usize {
-        self.inner.lock().await.len()
+        self.inner.lock().await.nodes.len()
     }
 
     async fn get(&self, id: u64) -> Option<RepertoireNode> {
-        self.inner.lock().await.get(id as usize).cloned()
+        self.inner.lock().await.nodes.get(id as usize).cloned()
     }
 
     async fn push(&self, node: RepertoireNode) -> u64 {
-        let mut g = self.inner.lock().await;
-        let id = g.len() as u64;
-        let mut n = node;
-        n.id = id;
-        g.push(n);
-        id
+        self.inner.lock().await.push(node)
     }
 
     async fn push_child(&self, parent: u64, child_id: u64) {
+        self.inner.lock().await.push_child(parent, child_id);
+    }
+
+    async fn set_signals(&self, id: u64, signals: Signals) {
         let mut g = self.inner.lock().await;
-        if let Some(p) = g.get_mut(parent as usize) {
-            p.children.push(child_id);
+        if let Some(n) = g.nodes.get_mut(id as usize) {
+            n.signals = signals;
+        }
+    }
+
+    async fn mark_expanded(&self, id: u64) {
+        self.inner.lock().await.expanded.insert(id);
+    }
+
+    async fn find(&self, fen_key: &FenKey) -> Option<u64> {
+        let g = self.inner.lock().await;
+        g.by_position.get(&fen_key.key()).copied()
+    }
+
+    async fn push_or_link(&self, node: RepertoireNode) -> (u64, bool) {
+        let mut g = self.inner.lock().await;
+        let Some(&id) = g.by_position.get(&node.fen_key.key()) else {
+            let parent = node.parent;
+            let id = g.push(node);
+            if let Some(parent) = parent {
+                g.push_child(parent, id);
+            }
+            return (id, true);
+        };
+        if let (Some(parent), Some(uci)) = (node.parent, node.last_move_uci) {
+            // Already linked from this parent: nothing to add.
+            let existing = &mut g.nodes[id as usize];
+            if existing.move_from(parent).is_none() {
+                existing.other_parents.push(ParentLink {
+                    parent,
+                    uci,
+                    play_rate: node.signals.play_rate,
+                });
+                if let (Some(reach), Some(added)) = (existing.signals.reach, node.signals.reach) {
+                    existing.signals.reach = Some((reach + added).min(1.0));
+                }
+                g.push_child(parent, id);
+            }
         }
+        (id, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::chess::UciMove,
+        search::{build::make_node, util::apply_uci},
+    };
+
+    #[tokio::test]
+    async fn test_transposition_links_existing_node() {
+        let arena = MemArena::new();
+        let start = FenKey::starting_position();
+        let root = arena.push(make_node(None, &start, None, 0)).await;
+        let add = async |parent: u64, uci: &str| {
+            let fen = arena.get(parent).await.unwrap().fen_key;
+            let (next, _) = apply_uci(&fen, uci).unwrap();
+            let ply = arena.get(parent).await.unwrap().ply_depth + 1;
+            let mv = Some(UciMove::from_uci(uci).unwrap());
+            arena
+                .push_or_link(make_node(Some(parent), &next, mv, ply))
+                .await
+        };
+        // 1. Nf3 Nf6 2. Nc3 and 1. Nc3 Nf6 2. Nf3 reach the same position.
+        let (nf3, _) = add(root, "g1f3").await;
+        let (nf6, _) = add(nf3, "g8f6").await;
+        let (canonical, created) = add(nf6, "b1c3").await;
+        assert!(created);
+        let (nc3, _) = add(root, "b1c3").await;
+        let (nf6b, _) = add(nc3, "g8f6").await;
+        let (linked, created) = add(nf6b, "g1f3").await;
+        assert!(!created);
+        assert_eq!(linked, canonical);
+
+        assert_eq!(arena.len().await, 6);
+        let node = arena.get(canonical).await.unwrap();
+        assert_eq!(node.parent, Some(nf6));
+        assert_eq!(node.move_from(nf6b).unwrap().to_uci(), "g1f3");
+        assert_eq!(arena.get(nf6b).await.unwrap().children, vec![canonical]);
+        assert_eq!(arena.find(&node.fen_key).await, Some(canonical));
+    }
+
+    #[tokio::test]
+    async fn test_restore_snapshot() {
+        let arena = MemArena::new();
+        let start = FenKey::starting_position();
+        let root = arena.push(make_node(None, &start, None, 0)).await;
+        let (e4, _) = apply_uci(&start, "e2e4").unwrap();
+        let mv = Some(UciMove::from_uci("e2e4").unwrap());
+        let (child, _) = arena.push_or_link(make_node(Some(root), &e4, mv, 1)).await;
+        arena.mark_expanded(root).await;
+        let (nodes, frontier) = arena.snapshot().await;
+        assert_eq!(frontier, vec![child]);
+
+        let restored = MemArena::new();
+        restored.restore(nodes, &frontier).await.unwrap();
+        assert_eq!(restored.len().await, 2);
+        assert_eq!(restored.find(&e4).await, Some(child));
+        assert_eq!(restored.get(root).await.unwrap().children, vec![child]);
+        assert_eq!(restored.snapshot().await.1, vec![child]);
+
+        let mut out_of_order = restored.all_nodes().await;
+        out_of_order.swap(0, 1);
+        assert!(restored.restore(out_of_order, &[]).await.is_err());
     }
 }
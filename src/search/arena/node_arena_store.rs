@@ -1,9 +1,26 @@
-use crate::domain::RepertoireNode;
+use crate::domain::{FenKey, RepertoireNode, Signals};
 
 #[async_trait::async_trait]
 pub trait NodeArenaStore: Send + Sync {
     async fn len(&self) -> usize;
+    async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
     async fn get(&self, id: u64) -> Option<RepertoireNode>;
     async fn push(&self, node: RepertoireNode) -> u64; // returns id
     async fn push_child(&self, parent: u64, child_id: u64);
+    async fn set_signals(&self, id: u64, signals: Signals);
+    /// Records that `id` has been expanded, i.e. its children are in the arena.
+    async fn mark_expanded(&self, id: u64);
+    /// Id of the node holding the position of `fen_key` (see `FenKey::key`).
+    async fn find(&self, fen_key: &FenKey) -> Option<u64>;
+    /// Adds `node` as a child of its parent, unless its position is already in the
+    /// arena: then the parent is linked to the existing node as a transposition, with
+    /// `node`'s move and play rate.
+    /// A new link adds `node`'s reach (the new path's probability) to the existing
+    /// node's, capped at 1.0, since the position is now reachable along either path. An
+    /// unknown reach on either side counts as 1.0 and is left as is. Descendants already
+    /// in the arena and queued priorities keep their old reach.
+    /// Returns the node's id and whether it was created.
+    async fn push_or_link(&self, node: RepertoireNode) -> (u64, bool);
 }
@@ -15,5 +15,6 @@ pub fn make_node(
         ply_depth: ply,
         children: Vec::new(),
         signals: Default::default(),
+        other_parents: Vec::new(),
     }
 }
@@ -1,6 +1,6 @@
-use crate::domain::{FenKey, PieceColor};
-use anyhow::{anyhow, Result};
-use shakmaty::{fen::Fen, san::San, Chess, Color, EnPassantMode, Position};
+use crate::domain::FenKey;
+use anyhow::{Result, anyhow};
+use shakmaty::{Chess, Color, Position, san::San};
 
 /// Parse SAN into starting FEN (and side to move)
 pub fn start_from_san(san_line: Option<&str>) -> Result<(FenKey, Color)> {
@@ -17,13 +17,5 @@ pub fn start_from_san(san_line: Option<&str>) -> Result<(FenKey, Color)> {
             pos.play_unchecked(&mv);
         }
     }
-    let fen = Fen::from_position(pos.clone(), EnPassantMode::Legal).to_string();
-    let stm = pos.turn();
-    Ok((
-        FenKey {
-            fen_string: fen,
-            side_to_move: PieceColor::from_shakmaty(stm),
-        },
-        stm,
-    ))
+    Ok((FenKey::from_position(&pos), pos.turn()))
 }
@@ -0,0 +1,157 @@
+//! Checkpoints: a build in progress saved as JSON, so `--resume` can continue it
+//! after a crash or interrupt without querying the expanded nodes again.
+//!
+//! Only the nodes and the frontier are stored. The position index (which positions
+//! were already seen) is rebuilt from the nodes on load.
+
+use anyhow::{Context, Result, ensure};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{domain::RepertoireNode, orchestration::CheckpointPort, search::arena::MemArena};
+
+const VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    version: u32,
+    /// The `--side` the build optimizes for.
+    pub side: String,
+    pub max_plies: u32,
+    pub root: u64,
+    /// The arena, indexed by node id.
+    pub nodes: Vec<RepertoireNode>,
+    /// Nodes queued or being expanded when the checkpoint was taken; every other node
+    /// has been expanded.
+    pub frontier: Vec<u64>,
+}
+
+impl Checkpoint {
+    pub fn new(
+        side: String,
+        max_plies: u32,
+        root: u64,
+        nodes: Vec<RepertoireNode>,
+        frontier: Vec<u64>,
+    ) -> Self {
+        Self {
+            version: VERSION,
+            side,
+            max_plies,
+            root,
+            nodes,
+            frontier,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let json =
+            fs::read(path).with_context(|| format!("reading checkpoint {}", path.display()))?;
+        let checkpoint: Self = serde_json::from_slice(&json)
+            .with_context(|| format!("parsing checkpoint {}", path.display()))?;
+        ensure!(
+            checkpoint.version == VERSION,
+            "checkpoint {} has version {}, expected {}",
+            path.display(),
+            checkpoint.version,
+            VERSION
+        );
+        Ok(checkpoint)
+    }
+
+    /// Writes to a temporary file first, so an interrupted save keeps the previous
+    /// checkpoint intact.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        fs::write(&tmp, serde_json::to_vec(self)?)
+            .with_context(|| format!("writing checkpoint {}", tmp.display()))?;
+        fs::rename(&tmp, path).with_context(|| format!("writing checkpoint {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Saves the arena of a running build to `path`.
+pub struct FileCheckpoint {
+    pub path: PathBuf,
+    pub side: String,
+    pub max_plies: u32,
+    pub root: u64,
+    pub arena: MemArena,
+}
+
+#[async_trait::async_trait]
+impl CheckpointPort for FileCheckpoint {
+    async fn save(&self) -> Result<()> {
+        let (nodes, frontier) = self.arena.snapshot().await;
+        let checkpoint = Checkpoint::new(
+            self.side.clone(),
+            self.max_plies,
+            self.root,
+            nodes,
+            frontier,
+        );
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || checkpoint.save(&path)).await?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::{Eval, FenKey, chess::UciMove},
+        search::{arena::NodeArenaStore, build::make_node, util::apply_uci},
+    };
+
+    #[tokio::test]
+    async fn test_save_and_load_round_trip() {
+        let arena = MemArena::new();
+        let start = FenKey::starting_position();
+        let root = arena.push(make_node(None, &start, None, 0)).await;
+        let (e4, _) = apply_uci(&start, "e2e4").unwrap();
+        let mv = Some(UciMove::from_uci("e2e4").unwrap());
+        let (child, _) = arena.push_or_link(make_node(Some(root), &e4, mv, 1)).await;
+        let mut signals = arena.get(child).await.unwrap().signals;
+        signals.eval = Some(Eval::Cp(30));
+        arena.set_signals(child, signals).await;
+        arena.mark_expanded(root).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("build.ckpt");
+        let writer = FileCheckpoint {
+            path: path.clone(),
+            side: "white".to_string(),
+            max_plies: 6,
+            root,
+            arena,
+        };
+        writer.save().await.unwrap();
+
+        let loaded = Checkpoint::load(&path).unwrap();
+        assert_eq!(loaded.side, "white");
+        assert_eq!(loaded.max_plies, 6);
+        assert_eq!(loaded.root, root);
+        assert_eq!(loaded.frontier, vec![child]);
+        assert_eq!(loaded.nodes.len(), 2);
+        let node = &loaded.nodes[child as usize];
+        assert_eq!(node.fen_key, e4);
+        assert_eq!(node.fen_key.key(), e4.key());
+        assert_eq!(node.signals.eval, Some(Eval::Cp(30)));
+        assert_eq!(node.last_move_uci.as_ref().unwrap().to_uci(), "e2e4");
+    }
+
+    #[test]
+    fn test_load_rejects_other_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("build.ckpt");
+        let mut checkpoint = Checkpoint::new("black".to_string(), 4, 0, Vec::new(), Vec::new());
+        checkpoint.version = VERSION + 1;
+        checkpoint.save(&path).unwrap();
+        assert!(Checkpoint::load(&path).is_err());
+    }
+}
@@ -1,53 +1,81 @@
 use super::{
+    RunSummary,
     arena::{MemArena, NodeArenaStore},
     build::{make_node, start_from_san},
-    worker::expand_node_task,
+    checkpoint::{Checkpoint, FileCheckpoint},
 };
 use crate::{
     config::SearchConfig,
-    domain::{FenKey, RepertoireNode},
+    domain::{RepertoireNode, candidate_request::CandidateRequestBuilder},
+    orchestration::{
+        CheckpointPort, ChildEnqueuer, CompositeTermination, HighLevelOrchestrator, Interrupt,
+        PriorityWorkQueue, ShakmatyMoveApplier, TerminationPolicyPort, TokioMpscWorkQueue,
+        WorkQueuePort,
+    },
     policy::MovePolicy,
     provider::{MovePopularity, MoveQuality},
 };
-use dashmap::DashSet;
-use std::sync::Arc;
-use tokio::{sync::mpsc, task::JoinSet};
+use std::{path::PathBuf, sync::Arc, time::Instant};
 use tracing::{debug, info};
 
-/// Orchestrator: drains the work queue (single consumer) and spawns a worker per item.
+/// Orchestrator: builds a repertoire tree from a starting position, running the
+/// `orchestration` pipeline over an in-memory arena.
 pub struct Orchestrator {
     cfg: SearchConfig,
     policy: Arc<dyn MovePolicy>,
     quality: Arc<dyn MoveQuality>,
     popularity: Arc<dyn MovePopularity>,
     arena: MemArena,
-    seen: DashSet<FenKey>,
+    checkpoint: Option<CheckpointTarget>,
+    interrupt: Option<Interrupt>,
+}
+
+/// Where checkpoints go, and the side recorded in them for `--resume`.
+struct CheckpointTarget {
+    path: PathBuf,
+    side: String,
 }
 
 impl Orchestrator {
     pub fn new(
         cfg: SearchConfig,
-        policy: impl MovePolicy + 'static,
+        policy: Arc<dyn MovePolicy>,
         quality: Arc<dyn MoveQuality>,
         popularity: Arc<dyn MovePopularity>,
     ) -> Self {
         Self {
             cfg,
-            policy: Arc::new(policy),
+            policy,
             quality,
             popularity,
             arena: MemArena::new(),
-            seen: DashSet::new(),
+            checkpoint: None,
+            interrupt: None,
         }
     }
 
-    /// Build repertoire from an optional SAN line and expand up to `max_plies`.
+    /// Stop the build, as if a budget ran out, once `interrupt` is triggered.
+    pub fn with_interrupt(mut self, interrupt: Interrupt) -> Self {
+        self.interrupt = Some(interrupt);
+        self
+    }
+
+    /// Save the build to `path` every `cfg.checkpoint_seconds` and when it stops.
+    /// `side` is stored with it so a resumed build doesn't need `--side` again.
+    pub fn with_checkpoint(mut self, path: PathBuf, side: String) -> Self {
+        self.checkpoint = Some(CheckpointTarget { path, side });
+        self
+    }
+
+    /// Build repertoire from an optional SAN line and expand up to `max_plies`, within
+    /// the node, time and provider-call budgets from the search config.
     /// Single-consumer dispatcher pattern: no Receiver clones.
     pub async fn build_from_start(
         &self,
         san_line: Option<&str>,
         max_plies: u32,
-    ) -> anyhow::Result<RepertoireNode> {
+    ) -> anyhow::Result<(RepertoireNode, RunSummary)> {
+        let started = Instant::now();
         info!(
             "Orchestrator: build_from_start called with san_line={:?}, max_plies={}",
             san_line, max_plies
@@ -56,50 +84,343 @@ impl Orchestrator {
         debug!("Root FEN: {:?}", root_fen);
         let root_id = self.arena.push(make_node(None, &root_fen, None, 0)).await;
         debug!("Root node pushed with id: {}", root_id);
-        let root = self.arena.get(root_id).await.expect("root in arena");
+        self.run(root_id, &[root_id], max_plies, started).await
+    }
 
-        let (tx, mut rx) = mpsc::channel::<u64>(self.cfg.concurrency * 4);
-        tx.send(root.id).await.ok();
-
-        let mut joinset = JoinSet::new();
-
-        while let Some(nid) = rx.recv().await {
-            debug!("Dequeued node id: {} for expansion", nid);
-            let tx2 = tx.clone();
-            let cfg2 = self.cfg.clone();
-            let policy2 = Arc::clone(&self.policy);
-            let quality2 = Arc::clone(&self.quality);
-            let popularity2 = Arc::clone(&self.popularity);
-            let seen2 = self.seen.clone();
-            let arena_ref = self.arena.clone();
-
-            joinset.spawn(async move {
-                debug!("Worker spawned for node id: {}", nid);
-                let _ = expand_node_task(
-                    nid,
-                    max_plies,
-                    &cfg2,
-                    &*policy2,
-                    &quality2,
-                    &popularity2,
-                    &arena_ref,
-                    &seen2,
-                    &tx2,
-                )
-                .await;
-                debug!("Worker finished for node id: {}", nid);
-            });
+    /// Continue a build saved in `checkpoint`, expanding its frontier up to
+    /// `max_plies`. Nodes expanded before the checkpoint are not queried again; the
+    /// node budget counts them, the time and provider-call budgets start afresh.
+    pub async fn resume(
+        &self,
+        checkpoint: Checkpoint,
+        max_plies: u32,
+    ) -> anyhow::Result<(RepertoireNode, RunSummary)> {
+        let started = Instant::now();
+        info!(
+            "Orchestrator: resuming {} nodes with {} to expand, max_plies={}",
+            checkpoint.nodes.len(),
+            checkpoint.frontier.len(),
+            max_plies
+        );
+        self.arena
+            .restore(checkpoint.nodes, &checkpoint.frontier)
+            .await?;
+        self.run(checkpoint.root, &checkpoint.frontier, max_plies, started)
+            .await
+    }
+
+    /// Expands `frontier` (nodes already in the arena) and everything found from it,
+    /// then returns the root as it is after the build.
+    async fn run(
+        &self,
+        root_id: u64,
+        frontier: &[u64],
+        max_plies: u32,
+        started: Instant,
+    ) -> anyhow::Result<(RepertoireNode, RunSummary)> {
+        anyhow::ensure!(
+            self.arena.get(root_id).await.is_some(),
+            "missing root node {root_id}"
+        );
+
+        let mut termination = CompositeTermination::from_config(&self.cfg, max_plies);
+        if let Some(interrupt) = &self.interrupt {
+            termination = termination.with(interrupt.clone());
+        }
+        let termination: Arc<dyn TerminationPolicyPort> = Arc::new(termination);
+        termination.reserve_nodes(self.arena.len().await);
+        let queue = build_work_queue(&self.cfg)?;
+        ChildEnqueuer {
+            queue: &*queue,
+            arena: &self.arena,
+            policy: &*self.policy,
         }
+        .enqueue_all(frontier)
+        .await?;
 
-        // Sender dropped → wait for all in-flight workers to finish
-        info!("Waiting for all workers to finish...");
-        while let Some(_res) = joinset.join_next().await {}
+        let checkpoint = self.checkpoint.as_ref().map(|target| {
+            Arc::new(FileCheckpoint {
+                path: target.path.clone(),
+                side: target.side.clone(),
+                max_plies,
+                root: root_id,
+                arena: self.arena.clone(),
+            }) as Arc<dyn CheckpointPort>
+        });
+        let base_req = CandidateRequestBuilder::default()
+            .multipv(self.quality.caps().max_multipv)
+            .build()?;
+        let dispatcher = Arc::new(HighLevelOrchestrator {
+            queue,
+            termination: Arc::clone(&termination),
+            arena: Arc::new(self.arena.clone()),
+            policy: Arc::clone(&self.policy),
+            quality: Arc::clone(&self.quality),
+            popularity: Arc::clone(&self.popularity),
+            applier: Arc::new(ShakmatyMoveApplier),
+            cfg: self.cfg.clone(),
+            base_req,
+            checkpoint,
+        });
+        let stop_reason = dispatcher.run().await?;
+        self.mark_pending(&*termination).await;
         info!("All workers finished. Returning root node.");
+        let root = self.arena.get(root_id).await.expect("root in arena");
 
-        Ok(root)
+        let summary = RunSummary {
+            stop_reason,
+            nodes: self.arena.len().await,
+            elapsed: started.elapsed(),
+        };
+        info!("Build finished: {}", summary);
+        Ok((root, summary))
+    }
+    /// Flags the nodes the build stopped before expanding, including ones that only
+    /// got some of their children before the node budget ran out; nodes past the ply
+    /// limit are finished lines, not pending ones.
+    async fn mark_pending(&self, termination: &dyn TerminationPolicyPort) {
+        let (nodes, frontier) = self.arena.snapshot().await;
+        for id in frontier {
+            let node = &nodes[id as usize];
+            if termination.should_expand(node.ply_depth) {
+                let mut signals = node.signals.clone();
+                signals.pending = true;
+                self.arena.set_signals(id, signals).await;
+            }
+        }
     }
+
     /// Returns a clone of all nodes in the arena (for testing/inspection).
     pub async fn all_nodes(&self) -> Vec<crate::domain::RepertoireNode> {
         self.arena.all_nodes().await
     }
 }
+
+/// Work queue selected by `cfg.queue`: "fifo" (the default) or "priority".
+pub fn build_work_queue(cfg: &SearchConfig) -> anyhow::Result<Arc<dyn WorkQueuePort>> {
+    match cfg.queue.as_deref().unwrap_or("fifo") {
+        "fifo" => Ok(Arc::new(TokioMpscWorkQueue::new())),
+        "priority" => Ok(Arc::new(PriorityWorkQueue::new())),
+        other => anyhow::bail!("unknown search queue: {other}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::{Centipawns, PlayRate},
+        orchestration::StopReason,
+        policy::SideSplitPolicy,
+        test_support::{StubPopularity, StubQuality},
+    };
+    use shakmaty::Color;
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    #[tokio::test]
+    async fn test_build_stops_at_node_budget() {
+        let cfg = SearchConfig {
+            concurrency: 2,
+            max_total_nodes: Some(10),
+            max_children_my_side: Some(2),
+            max_children_opp_side: Some(2),
+            ..SearchConfig::default()
+        };
+        let policy =
+            SideSplitPolicy::new(Color::White, Centipawns::from_int(50), PlayRate::new(0.0));
+        let orch = Orchestrator::new(
+            cfg,
+            Arc::new(policy),
+            Arc::new(StubQuality::first_moves()),
+            Arc::new(StubPopularity::first_moves()),
+        );
+        let (root, summary) = orch.build_from_start(None, 20).await.unwrap();
+        assert_eq!(summary.stop_reason, StopReason::NodeBudget);
+        assert_eq!(summary.nodes, 10);
+        assert_eq!(orch.all_nodes().await.len(), 10);
+        assert!(root.is_root());
+        assert_eq!(root.children.len(), 2);
+    }
+
+    fn orchestrator(cfg: SearchConfig, quality: Arc<dyn MoveQuality>) -> Orchestrator {
+        let policy =
+            SideSplitPolicy::new(Color::White, Centipawns::from_int(50), PlayRate::new(0.0));
+        Orchestrator::new(
+            cfg,
+            Arc::new(policy),
+            quality,
+            Arc::new(StubPopularity::first_moves()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_build_completes_when_frontier_is_empty() {
+        for queue in ["fifo", "priority"] {
+            let cfg = SearchConfig {
+                concurrency: 3,
+                max_total_nodes: None,
+                max_children_my_side: Some(2),
+                max_children_opp_side: Some(2),
+                queue: Some(queue.to_string()),
+                ..SearchConfig::default()
+            };
+            let orch = orchestrator(cfg, Arc::new(StubQuality::first_moves()));
+            let (_root, summary) = orch.build_from_start(None, 2).await.unwrap();
+            assert_eq!(summary.stop_reason, StopReason::Completed);
+            // Root, two moves of mine, two replies to each.
+            assert_eq!(summary.nodes, 7);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_build_completes_when_workers_fail() {
+        let cfg = SearchConfig {
+            max_total_nodes: None,
+            ..SearchConfig::default()
+        };
+        let orch = orchestrator(cfg, Arc::new(StubQuality::failing()));
+        let (_root, summary) =
+            tokio::time::timeout(Duration::from_secs(5), orch.build_from_start(None, 10))
+                .await
+                .expect("build finishes")
+                .unwrap();
+        assert_eq!(summary.stop_reason, StopReason::Completed);
+        assert_eq!(summary.nodes, 1);
+    }
+
+    fn counted_orchestrator(cfg: SearchConfig, calls: &Arc<AtomicUsize>) -> Orchestrator {
+        let policy =
+            SideSplitPolicy::new(Color::White, Centipawns::from_int(50), PlayRate::new(0.0));
+        let quality = StubQuality::first_moves().counting(calls);
+        let popularity = StubPopularity::first_moves().counting(calls);
+        Orchestrator::new(
+            cfg,
+            Arc::new(policy),
+            Arc::new(quality),
+            Arc::new(popularity),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_resume_continues_without_requerying() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("build.ckpt");
+        let cfg = SearchConfig {
+            concurrency: 1,
+            max_total_nodes: Some(3),
+            max_children_my_side: Some(2),
+            max_children_opp_side: Some(2),
+            ..SearchConfig::default()
+        };
+        let calls = Arc::new(AtomicUsize::new(0));
+        let orch = counted_orchestrator(cfg.clone(), &calls)
+            .with_checkpoint(path.clone(), "white".to_string());
+        let (_root, summary) = orch.build_from_start(None, 2).await.unwrap();
+        assert_eq!(summary.stop_reason, StopReason::NodeBudget);
+        let first_calls = calls.load(Ordering::SeqCst);
+
+        let checkpoint = Checkpoint::load(&path).unwrap();
+        assert_eq!(checkpoint.side, "white");
+        assert_eq!(checkpoint.nodes.len(), 3);
+        assert!(!checkpoint.frontier.contains(&checkpoint.root));
+
+        let cfg = SearchConfig {
+            max_total_nodes: None,
+            ..cfg
+        };
+        let orch = counted_orchestrator(cfg, &calls);
+        let (root, summary) = orch.resume(checkpoint, 2).await.unwrap();
+        assert_eq!(summary.stop_reason, StopReason::Completed);
+        assert_eq!(summary.nodes, 7);
+        assert!(root.is_root());
+        // One lookup for the root and one for each of my two moves, across both runs.
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert!(first_calls >= 1);
+    }
+
+    #[tokio::test]
+    async fn test_resume_finishes_nodes_starved_by_the_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("build.ckpt");
+        let cfg = SearchConfig {
+            concurrency: 3,
+            max_total_nodes: Some(4),
+            max_children_my_side: Some(2),
+            max_children_opp_side: Some(2),
+            ..SearchConfig::default()
+        };
+        let calls = Arc::new(AtomicUsize::new(0));
+        let orch = counted_orchestrator(cfg.clone(), &calls)
+            .with_checkpoint(path.clone(), "white".to_string());
+        let (_root, summary) = orch.build_from_start(None, 2).await.unwrap();
+        assert_eq!(summary.stop_reason, StopReason::NodeBudget);
+
+        // Only one of the four replies fit, so both of my moves are still to expand,
+        // whether or not a worker got to them before the budget ran out.
+        let checkpoint = Checkpoint::load(&path).unwrap();
+        assert_eq!(checkpoint.nodes.len(), 4);
+        let mine: Vec<u64> = checkpoint.nodes[checkpoint.root as usize].children.clone();
+        assert_eq!(mine.len(), 2);
+        for id in &mine {
+            assert!(checkpoint.frontier.contains(id));
+        }
+
+        let cfg = SearchConfig {
+            max_total_nodes: None,
+            ..cfg
+        };
+        let orch = counted_orchestrator(cfg, &calls);
+        let (_root, summary) = orch.resume(checkpoint, 2).await.unwrap();
+        assert_eq!(summary.stop_reason, StopReason::Completed);
+        assert_eq!(summary.nodes, 7);
+        let nodes = orch.all_nodes().await;
+        for id in mine {
+            assert_eq!(nodes[id as usize].children.len(), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stopped_build_marks_pending_nodes() {
+        let cfg = SearchConfig {
+            max_children_my_side: Some(2),
+            ..SearchConfig::default()
+        };
+        let interrupt = Interrupt::new();
+        let orch = orchestrator(cfg.clone(), Arc::new(StubQuality::first_moves()))
+            .with_interrupt(interrupt.clone());
+        interrupt.trigger();
+        let (root, summary) = orch.build_from_start(None, 4).await.unwrap();
+        assert_eq!(summary.stop_reason, StopReason::Interrupted);
+        assert_eq!(summary.nodes, 1);
+        assert!(orch.all_nodes().await[root.id as usize].signals.pending);
+
+        // Only one reply fits in the budget, so one of my moves is half expanded.
+        let budget_cfg = SearchConfig {
+            concurrency: 3,
+            max_total_nodes: Some(4),
+            max_children_opp_side: Some(2),
+            ..cfg.clone()
+        };
+        let orch = orchestrator(budget_cfg, Arc::new(StubQuality::first_moves()));
+        let (root, summary) = orch.build_from_start(None, 2).await.unwrap();
+        assert_eq!(summary.stop_reason, StopReason::NodeBudget);
+        let nodes = orch.all_nodes().await;
+        assert!(!root.signals.pending);
+        let mine: Vec<&RepertoireNode> = root
+            .children
+            .iter()
+            .map(|&id| &nodes[id as usize])
+            .collect();
+        assert!(mine.iter().all(|n| n.signals.pending));
+        assert!(mine.iter().any(|n| n.children.len() == 1));
+
+        // Leaves at the ply limit are finished, not pending.
+        let orch = orchestrator(cfg, Arc::new(StubQuality::first_moves()));
+        let (_root, summary) = orch.build_from_start(None, 1).await.unwrap();
+        assert_eq!(summary.stop_reason, StopReason::Completed);
+        assert!(orch.all_nodes().await.iter().all(|n| !n.signals.pending));
+    }
+}
@@ -1,7 +1,10 @@
 pub mod arena;
 pub mod build;
+pub mod checkpoint;
 pub mod dispatcher;
+pub mod run_summary;
 pub mod util;
-pub mod worker;
 
+pub use checkpoint::Checkpoint;
 pub use dispatcher::Orchestrator;
+pub use run_summary::RunSummary;
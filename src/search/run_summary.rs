@@ -0,0 +1,35 @@
+use std::{fmt, time::Duration};
+
+use crate::orchestration::StopReason;
+
+/// Outcome of a build: why it stopped and how much it did.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunSummary {
+    pub stop_reason: StopReason,
+    /// Nodes in the tree, root included.
+    pub nodes: usize,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for RunSummary {
+    /// # Examples
+    /// ```
+    /// use repgrow::{orchestration::StopReason, search::RunSummary};
+    /// use std::time::Duration;
+    /// let summary = RunSummary {
+    ///     stop_reason: StopReason::NodeBudget,
+    ///     nodes: 200,
+    ///     elapsed: Duration::from_millis(12_340),
+    /// };
+    /// assert_eq!(summary.to_string(), "200 nodes in 12.3s, stopped: node budget exhausted");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} nodes in {:.1}s, stopped: {}",
+            self.nodes,
+            self.elapsed.as_secs_f64(),
+            self.stop_reason
+        )
+    }
+}
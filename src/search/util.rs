@@ -1,22 +1,14 @@
-use crate::domain::{FenKey, PieceColor};
+use crate::domain::FenKey;
 use anyhow::{Error, Result, anyhow};
-use shakmaty::CastlingMode;
-use shakmaty::{Chess, EnPassantMode, Position, fen::Fen, uci::Uci};
+use shakmaty::{Chess, Position, uci::Uci};
 
 pub fn apply_uci(fen_key: &FenKey, uci: &str) -> Result<(FenKey, shakmaty::Color)> {
-    let position: Chess = extract_position_from_fen_key(fen_key)?;
+    let position = fen_key.position();
     let extracted_move = extract_move_from_parsed_uci_and_position(uci, &position)?;
 
     let mut next = position.clone();
     next.play_unchecked(&extracted_move);
-    let next_fen = Fen::from_position(next.clone(), EnPassantMode::Legal).to_string();
-    Ok((
-        FenKey {
-            fen_string: next_fen,
-            side_to_move: PieceColor::from_shakmaty(next.turn()),
-        },
-        next.turn(),
-    ))
+    Ok((FenKey::from_position(&next), next.turn()))
 }
 
 pub fn extract_move_from_parsed_uci_and_position(
@@ -29,25 +21,17 @@ pub fn extract_move_from_parsed_uci_and_position(
         .map_err(|_| anyhow!("illegal UCI"))
 }
 
-pub fn extract_position_from_fen_key(fen_key: &FenKey) -> Result<Chess, Error> {
-    Ok(fen_key
-        .fen_string
-        .parse::<Fen>()?
-        .into_position(CastlingMode::Standard)?)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::BLACK_TO_MOVE;
     use shakmaty::Color;
 
     #[test]
-    fn test_extract_position_from_fen_key() {
-        let fen_key = FenKey {
-            fen_string: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(),
-            side_to_move: PieceColor::White,
-        };
-        let position = extract_position_from_fen_key(&fen_key).unwrap();
-        assert_eq!(position.turn(), Color::White);
+    fn test_apply_uci() {
+        let (next, turn) = apply_uci(&FenKey::starting_position(), "e2e4").unwrap();
+        assert_eq!(turn, Color::Black);
+        assert_eq!(next.normalized_fen(), BLACK_TO_MOVE);
+        assert!(apply_uci(&FenKey::starting_position(), "e2e5").is_err());
     }
 }
@@ -0,0 +1,192 @@
+//! Fixtures shared by the unit tests: candidate moves, a position with Black to move
+//! and stub providers.
+
+use shakmaty::{CastlingMode, Position};
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    domain::{
+        CandidateMove, Eval, EvalLine, FenKey, PlayRate, PopularityRow, Signals, chess::UciMove,
+    },
+    provider::{MovePopularity, MoveQuality, PopularityCaps, QualityCaps},
+};
+
+/// The position after 1. e4.
+pub const BLACK_TO_MOVE: &str = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1";
+
+pub fn black_to_move() -> FenKey {
+    FenKey::parse(BLACK_TO_MOVE).unwrap()
+}
+
+/// A candidate from the starting position with the given eval and play rate.
+pub fn cand(uci: &str, eval: Option<Eval>, play_rate: Option<f32>) -> CandidateMove {
+    CandidateMove {
+        uci: UciMove::from_uci(uci).unwrap(),
+        next_fen: FenKey::starting_position(),
+        signals: Signals {
+            eval,
+            play_rate: play_rate.map(PlayRate::new),
+            ..Default::default()
+        },
+    }
+}
+
+/// The first three legal moves of a position.
+fn first_moves(fen: &FenKey) -> Vec<UciMove> {
+    fen.position()
+        .legal_moves()
+        .iter()
+        .take(3)
+        .map(|m| UciMove::from_uci(&m.to_uci(CastlingMode::Standard).to_string()).unwrap())
+        .collect()
+}
+
+/// What a stub provider answers with.
+enum Answer<T> {
+    /// The same moves in every position.
+    Fixed(Vec<(&'static str, T)>),
+    /// The first three legal moves of the position.
+    FirstMoves,
+    Fail,
+}
+
+/// Quality stub: every move it answers with is evaluated at the given centipawns,
+/// or at 0 for `first_moves`. Counts its calls and records their multipv.
+pub struct StubQuality {
+    answer: Answer<i32>,
+    pub calls: Arc<AtomicUsize>,
+    pub multipvs: Mutex<Vec<Option<usize>>>,
+}
+
+impl StubQuality {
+    fn new(answer: Answer<i32>) -> Self {
+        Self {
+            answer,
+            calls: Arc::default(),
+            multipvs: Mutex::default(),
+        }
+    }
+
+    pub fn fixed(lines: &[(&'static str, i32)]) -> Self {
+        Self::new(Answer::Fixed(lines.to_vec()))
+    }
+
+    pub fn first_moves() -> Self {
+        Self::new(Answer::FirstMoves)
+    }
+
+    pub fn failing() -> Self {
+        Self::new(Answer::Fail)
+    }
+
+    /// Count calls in `calls`, e.g. to share one counter between providers.
+    pub fn counting(self, calls: &Arc<AtomicUsize>) -> Self {
+        Self {
+            calls: Arc::clone(calls),
+            ..self
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl MoveQuality for StubQuality {
+    async fn evaluate(
+        &self,
+        fen: &FenKey,
+        multipv: Option<usize>,
+    ) -> anyhow::Result<Vec<EvalLine>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        self.multipvs.lock().unwrap().push(multipv);
+        let lines: Vec<(UciMove, i32)> = match &self.answer {
+            Answer::Fixed(lines) => lines
+                .iter()
+                .map(|(uci, cp)| (UciMove::from_uci(uci).unwrap(), *cp))
+                .collect(),
+            Answer::FirstMoves => first_moves(fen).into_iter().map(|uci| (uci, 0)).collect(),
+            Answer::Fail => anyhow::bail!("engine unavailable"),
+        };
+        Ok(lines
+            .into_iter()
+            .map(|(uci, cp)| EvalLine {
+                uci,
+                eval: Eval::Cp(cp),
+                depth: 20,
+                pv: Vec::new(),
+                source: None,
+            })
+            .collect())
+    }
+    fn caps(&self) -> QualityCaps {
+        QualityCaps::default()
+    }
+}
+
+/// Popularity stub: every move it answers with has the given play rate, or 0.3 for
+/// `first_moves`, out of 100 games. Counts its calls.
+pub struct StubPopularity {
+    answer: Answer<f32>,
+    pub calls: Arc<AtomicUsize>,
+}
+
+impl StubPopularity {
+    fn new(answer: Answer<f32>) -> Self {
+        Self {
+            answer,
+            calls: Arc::default(),
+        }
+    }
+
+    pub fn fixed(rows: &[(&'static str, f32)]) -> Self {
+        Self::new(Answer::Fixed(rows.to_vec()))
+    }
+
+    pub fn first_moves() -> Self {
+        Self::new(Answer::FirstMoves)
+    }
+
+    pub fn failing() -> Self {
+        Self::new(Answer::Fail)
+    }
+
+    /// Count calls in `calls`, e.g. to share one counter between providers.
+    pub fn counting(self, calls: &Arc<AtomicUsize>) -> Self {
+        Self {
+            calls: Arc::clone(calls),
+            ..self
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl MovePopularity for StubPopularity {
+    async fn sample(&self, fen: &FenKey) -> anyhow::Result<Vec<PopularityRow>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let rows: Vec<(UciMove, f32)> = match &self.answer {
+            Answer::Fixed(rows) => rows
+                .iter()
+                .map(|(uci, rate)| (UciMove::from_uci(uci).unwrap(), *rate))
+                .collect(),
+            Answer::FirstMoves => first_moves(fen).into_iter().map(|uci| (uci, 0.3)).collect(),
+            Answer::Fail => anyhow::bail!("popularity unavailable"),
+        };
+        Ok(rows
+            .into_iter()
+            .map(|(uci, rate)| PopularityRow {
+                uci,
+                play_rate: PlayRate::new(rate),
+                games: (rate * 100.0).round() as u32,
+                wdl: None,
+                avg_rating: None,
+                games_by_source: Vec::new(),
+            })
+            .collect())
+    }
+    fn caps(&self) -> PopularityCaps {
+        PopularityCaps {
+            supports_filters: false,
+        }
+    }
+}
@@ -0,0 +1,59 @@
+#!/bin/sh
+# Scripted stand-in for a UCI engine, used by the local engine provider tests.
+# Replies to the handshake, honours MultiPV and prints canned search output on `go`.
+# Special positions, told apart by their piece placement: bare kings (4K3) search
+# until `stop`, an extra white queen (3QK3) never answers `stop`, and an extra white
+# rook (3RK3) makes the engine exit.
+multipv=1
+mode=normal
+while read -r line; do
+    set -- $line
+    case "$1" in
+        uci)
+            echo "id name FakeEngine"
+            echo "id author repgrow"
+            echo "option name MultiPV type spin default 1 min 1 max 500"
+            echo "uciok"
+            ;;
+        isready)
+            echo "readyok"
+            ;;
+        setoption)
+            if [ "$3" = "MultiPV" ]; then
+                multipv=$5
+            fi
+            ;;
+        position)
+            case "$3" in
+                8/8/8/4k3/8/8/8/4K3) mode=slow ;;
+                8/8/8/4k3/8/8/8/3QK3) mode=deaf ;;
+                8/8/8/4k3/8/8/8/3RK3) exit 1 ;;
+                *) mode=normal ;;
+            esac
+            ;;
+        stop)
+            if [ "$mode" = "slow" ]; then
+                echo "bestmove c2c4"
+            fi
+            ;;
+        go)
+            if [ "$mode" != "normal" ]; then
+                echo "info depth 5 seldepth 7 multipv 1 score cp 15 nodes 500 pv c2c4 e7e5"
+                continue
+            fi
+            echo "info depth 1 seldepth 1 multipv 1 score cp 10 nodes 20 pv e2e4"
+            echo "info depth 13 seldepth 17 multipv 1 score cp 90 lowerbound nodes 9000 pv e2e4"
+            echo "info depth 12 seldepth 15 multipv 1 score cp 35 nodes 8000 nps 100000 time 80 pv e2e4 e7e5 g1f3"
+            if [ "$multipv" -ge 2 ]; then
+                echo "info depth 12 seldepth 14 multipv 2 score cp 20 nodes 8000 pv d2d4 d7d5"
+            fi
+            if [ "$multipv" -ge 3 ]; then
+                echo "info depth 12 seldepth 13 multipv 3 score mate 5 nodes 8000 pv g1f3"
+            fi
+            echo "bestmove e2e4 ponder e7e5"
+            ;;
+        quit)
+            exit 0
+            ;;
+    esac
+done